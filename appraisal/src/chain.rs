@@ -0,0 +1,564 @@
+use async_trait::*;
+use futures::future::join_all;
+use std::time::Duration;
+
+use crate::{Appraisal, AppraisalEntry, AppraisalInformation, AppraisalItem, AppraisalOptions, Error, Evepraisal, Janice, LocalMarket, MarketHub, ProviderPrice};
+
+/// Determines how the providers of an [AppraisalChain] are asked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainMode {
+    /// The providers are asked in order, the first appraisal is returned
+    Fallback,
+    /// All providers are asked at the same time and their prices are merged
+    Merge,
+}
+
+/// Single provider of a chain
+struct Provider {
+    /// Implementation of the provider
+    appraisal: Box<dyn Appraisal + Send + Sync>,
+    /// Maximum time the provider has for creating an appraisal
+    timeout:   Duration,
+}
+
+/// Holds multiple [Appraisal] implementations and asks them in priority
+/// order, or all of them at once.
+///
+/// In [ChainMode::Fallback] the next provider is asked if the previous one
+/// fails or does not answer in time. In [ChainMode::Merge] the prices of
+/// all providers that answered are averaged, every item contains the
+/// prices of each provider and the spread between them.
+pub struct AppraisalChain {
+    /// Providers in priority order
+    providers: Vec<Provider>,
+    /// How the providers are asked
+    mode:      ChainMode,
+}
+
+impl std::fmt::Debug for AppraisalChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppraisalChain")
+            .field("providers", &self.providers.iter().map(|x| x.appraisal.name()).collect::<Vec<_>>())
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+impl AppraisalChain {
+    /// Name of the optional ENV containing the providers in priority order
+    const PROVIDERS: &'static str = "APPRAISAL_PROVIDERS";
+    /// Name of the optional ENV containing the timeout per provider in seconds
+    const TIMEOUT: &'static str = "APPRAISAL_TIMEOUT";
+    /// Name of the optional ENV containing the mode, `fallback` or `merge`
+    const MODE: &'static str = "APPRAISAL_MODE";
+    /// Providers that are tried if `APPRAISAL_PROVIDERS` is not set
+    const DEFAULT_PROVIDERS: &'static [&'static str] = &[
+        Janice::NAME,
+        Evepraisal::NAME,
+        LocalMarket::NAME,
+    ];
+    /// Default time a provider has for creating an appraisal
+    const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+    /// Name of the provider for merged appraisals
+    pub const NAME: &'static str = "chain";
+
+    /// Creates a new chain without any providers.
+    ///
+    /// # Params
+    ///
+    /// * `mode` -> How the providers are asked
+    ///
+    /// # Returns
+    ///
+    /// Empty chain
+    ///
+    pub fn new(mode: ChainMode) -> Self {
+        Self {
+            providers: Vec::new(),
+            mode,
+        }
+    }
+
+    /// Adds a provider with a lower priority than all previous ones.
+    ///
+    /// # Params
+    ///
+    /// * `appraisal` -> Implementation of the provider
+    /// * `timeout`   -> Maximum time the provider has for an appraisal
+    ///
+    /// # Returns
+    ///
+    /// The modified chain
+    ///
+    pub fn push<A>(mut self, appraisal: A, timeout: Duration) -> Self
+    where
+        A: Appraisal + Send + Sync + 'static,
+    {
+        self.providers.push(Provider {
+            appraisal: Box::new(appraisal),
+            timeout,
+        });
+        self
+    }
+
+    /// Gets the names of all providers in priority order.
+    ///
+    /// # Returns
+    ///
+    /// List of provider names
+    ///
+    pub fn providers(&self) -> Vec<&'static str> {
+        self.providers
+            .iter()
+            .map(|x| x.appraisal.name())
+            .collect()
+    }
+
+    /// Asks a single provider, limited by its timeout.
+    ///
+    /// # Params
+    ///
+    /// * `provider` -> Provider to ask
+    /// * `options`  -> Options that are passed to the provider
+    /// * `entries`  -> List of entries to create a apprisal for
+    ///
+    /// # Errors
+    ///
+    /// - If the provider fails
+    /// - If the provider does not answer in time
+    ///
+    /// # Returns
+    ///
+    /// Appraisal of the provider
+    ///
+    async fn ask(
+        provider: &Provider,
+        options:  &AppraisalOptions,
+        entries:  Vec<AppraisalEntry>,
+    ) -> Result<AppraisalInformation, Error> {
+        let name = provider.appraisal.name();
+        tokio::time::timeout(provider.timeout, provider.appraisal.create(options, entries))
+            .await
+            .map_err(|_| Error::Timeout(name))?
+    }
+
+    /// Reads the mode from the optional ENV `APPRAISAL_MODE`.
+    ///
+    /// # Errors
+    ///
+    /// If the ENV is neither `fallback` nor `merge`
+    ///
+    /// # Returns
+    ///
+    /// Configured mode, [ChainMode::Fallback] by default
+    ///
+    fn env_mode() -> Result<ChainMode, Error> {
+        match std::env::var(Self::MODE).as_deref() {
+            Ok("fallback") | Err(_) => Ok(ChainMode::Fallback),
+            Ok("merge")             => Ok(ChainMode::Merge),
+            Ok(_)                   => Err(Error::InvalidEnv(Self::MODE.into())),
+        }
+    }
+
+    /// Reads the timeout from the optional ENV `APPRAISAL_TIMEOUT`.
+    ///
+    /// # Errors
+    ///
+    /// If the ENV is not a number of seconds
+    ///
+    /// # Returns
+    ///
+    /// Configured timeout, 10 seconds by default
+    ///
+    fn env_timeout() -> Result<Duration, Error> {
+        match std::env::var(Self::TIMEOUT) {
+            Ok(x) => x
+                .trim()
+                .parse::<u64>()
+                .map(Duration::from_secs)
+                .map_err(|_| Error::InvalidEnv(Self::TIMEOUT.into())),
+            Err(_) => Ok(Self::DEFAULT_TIMEOUT),
+        }
+    }
+
+    /// Reads the providers from the optional ENV `APPRAISAL_PROVIDERS`.
+    ///
+    /// # Returns
+    ///
+    /// - Configured providers, if the ENV is set
+    /// - All providers whose ENVs are set, if the ENV is not set
+    ///
+    fn env_providers() -> Result<Vec<String>, Error> {
+        if let Ok(x) = std::env::var(Self::PROVIDERS) {
+            let providers = x
+                .split(',')
+                .map(|x| x.trim().to_lowercase())
+                .filter(|x| !x.is_empty())
+                .collect::<Vec<_>>();
+            for provider in providers.iter() {
+                validate_provider(provider)?;
+            }
+            return Ok(providers);
+        }
+
+        let providers = Self::DEFAULT_PROVIDERS
+            .iter()
+            .filter(|x| validate_provider(x).is_ok())
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        Ok(providers)
+    }
+}
+
+#[async_trait]
+impl Appraisal for AppraisalChain
+where
+    Self: Sized,
+{
+    /// Validates that all required Environment variables are set
+    ///
+    /// # Error
+    ///
+    /// - If a configured provider is unknown or misses an ENV
+    /// - If no provider is configured
+    ///
+    /// # Returns
+    ///
+    /// `Ok`  -> If at least one provider can be used
+    /// `Err` -> No provider can be used
+    ///
+    fn validate() -> Result<(), Error> {
+        Self::env_mode()?;
+        Self::env_timeout()?;
+        if Self::env_providers()?.is_empty() {
+            return Err(Error::NoProvider);
+        }
+        Ok(())
+    }
+
+    /// Creates a new chain from the Environment.
+    ///
+    /// `APPRAISAL_PROVIDERS` is a comma separated list of `janice`,
+    /// `evepraisal` and `local` in priority order. If it is not set, all
+    /// providers whose ENVs are set are used in that order.
+    /// `APPRAISAL_TIMEOUT` sets the timeout per provider in seconds and
+    /// `APPRAISAL_MODE` is either `fallback` or `merge`.
+    ///
+    /// # Error
+    ///
+    /// - If a ENV is invalid
+    /// - If a provider could not be created
+    /// - If no provider is configured
+    ///
+    /// # Returns
+    ///
+    /// Appraisal instance
+    ///
+    fn init() -> Result<Self, Error> {
+        let timeout = Self::env_timeout()?;
+
+        let mut chain = Self::new(Self::env_mode()?);
+        for provider in Self::env_providers()? {
+            chain = match provider.as_str() {
+                Janice::NAME      => chain.push(Janice::init()?, timeout),
+                Evepraisal::NAME  => chain.push(Evepraisal::init()?, timeout),
+                LocalMarket::NAME => chain.push(LocalMarket::init()?, timeout),
+                _                 => return Err(Error::UnknownProvider(provider)),
+            };
+        }
+
+        if chain.providers.is_empty() {
+            return Err(Error::NoProvider);
+        }
+        Ok(chain)
+    }
+
+    /// Name of the provider
+    ///
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    /// Creates a new apprisal
+    ///
+    /// # Params
+    ///
+    /// * `options` -> Options that are passed to every provider
+    /// * `entries` -> List of entries to create a apprisal for
+    ///
+    /// # Errors
+    ///
+    /// If no provider answered in time, contains the error of every
+    /// provider
+    ///
+    /// # Returns
+    ///
+    /// - The appraisal of the first provider that answered in time
+    /// - The merged appraisal of all providers that answered in time
+    ///
+    async fn create(
+        &self,
+        options: &AppraisalOptions,
+        entries: Vec<AppraisalEntry>,
+    ) -> Result<AppraisalInformation, Error> {
+        let mut errors = Vec::new();
+
+        match self.mode {
+            ChainMode::Fallback => {
+                for provider in self.providers.iter() {
+                    match Self::ask(provider, options, entries.clone()).await {
+                        Ok(x)  => return Ok(x),
+                        Err(e) => errors.push((provider.appraisal.name(), e)),
+                    }
+                }
+            }
+            ChainMode::Merge => {
+                let results = join_all(
+                    self.providers
+                        .iter()
+                        .map(|x| Self::ask(x, options, entries.clone()))
+                )
+                .await;
+
+                let mut appraisals = Vec::new();
+                for (provider, result) in self.providers.iter().zip(results) {
+                    match result {
+                        Ok(x)  => appraisals.push(x),
+                        Err(e) => errors.push((provider.appraisal.name(), e)),
+                    }
+                }
+
+                if !appraisals.is_empty() {
+                    return Ok(merge(appraisals, options.hub));
+                }
+            }
+        }
+
+        Err(Error::AllProvidersFailed(errors))
+    }
+}
+
+/// Validates the ENVs of a single provider.
+///
+/// # Params
+///
+/// * `provider` -> Name of the provider
+///
+/// # Errors
+///
+/// - If the provider is unknown
+/// - If the provider misses an ENV
+///
+fn validate_provider(provider: &str) -> Result<(), Error> {
+    match provider {
+        Janice::NAME      => Janice::validate(),
+        Evepraisal::NAME  => Evepraisal::validate(),
+        LocalMarket::NAME => LocalMarket::validate(),
+        _                 => Err(Error::UnknownProvider(provider.into())),
+    }
+}
+
+/// Merges the appraisals of multiple providers.
+///
+/// Every price is the average of all providers that know the item, the
+/// prices of each provider are kept as sources of the item.
+///
+/// # Params
+///
+/// * `appraisals` -> Appraisals to merge, in priority order
+/// * `market`     -> Market hub all providers were asked for
+///
+/// # Returns
+///
+/// Merged appraisal, code and uri are always empty because the merged
+/// prices do not match any shared appraisal of a single provider
+///
+fn merge(
+    appraisals: Vec<AppraisalInformation>,
+    market:     MarketHub,
+) -> AppraisalInformation {
+    let mut merged = AppraisalInformation {
+        sell_price: 0f64,
+        split_price: 0f64,
+        buy_price: 0f64,
+        items: Vec::new(),
+        code: None,
+        uri: None,
+        provider: AppraisalChain::NAME,
+        market: market,
+    };
+
+    for appraisal in appraisals {
+        for item in appraisal.items {
+            let source = ProviderPrice {
+                provider: item.provider,
+                sell_price: item.sell_price,
+                split_price: item.split_price,
+                buy_price: item.buy_price,
+            };
+
+            if let Some(x) = merged.items.iter_mut().find(|x| x.type_id == item.type_id) {
+                x.sources.push(source);
+            } else {
+                merged.items.push(AppraisalItem {
+                    provider: AppraisalChain::NAME,
+                    sources: vec![source],
+                    ..item
+                });
+            }
+        }
+    }
+
+    for item in merged.items.iter_mut() {
+        let count = item.sources.len() as f64;
+        let amount = item.amount as f64;
+
+        item.sell_price = item.sources.iter().map(|x| x.sell_price).sum::<f64>() / count;
+        item.split_price = item.sources.iter().map(|x| x.split_price).sum::<f64>() / count;
+        item.buy_price = item.sources.iter().map(|x| x.buy_price).sum::<f64>() / count;
+
+        item.sell_price_total = item.sell_price * amount;
+        item.split_price_total = item.split_price * amount;
+        item.buy_price_total = item.buy_price * amount;
+
+        let highest = item.sources.iter().map(|x| x.split_price).fold(f64::MIN, f64::max);
+        let lowest = item.sources.iter().map(|x| x.split_price).fold(f64::MAX, f64::min);
+        item.spread = highest - lowest;
+
+        merged.sell_price += item.sell_price_total;
+        merged.split_price += item.split_price_total;
+        merged.buy_price += item.buy_price_total;
+    }
+
+    merged
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod chain_tests {
+    use caph_connector::TypeId;
+
+    use super::*;
+
+    /// Provider that answers with a fixed price for Tritanium
+    struct Fixed {
+        /// Name of the provider
+        name:  &'static str,
+        /// Split price of Tritanium, fails if [None]
+        price: Option<f64>,
+        /// Time until the provider answers
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl Appraisal for Fixed {
+        fn validate() -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn init() -> Result<Self, Error> {
+            Err(Error::NoProvider)
+        }
+
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn create(
+            &self,
+            options: &AppraisalOptions,
+            entries: Vec<AppraisalEntry>,
+        ) -> Result<AppraisalInformation, Error> {
+            tokio::time::sleep(self.delay).await;
+            let price = self.price.ok_or(Error::NoProvider)?;
+            assert_eq!(entries, tritanium());
+
+            Ok(AppraisalInformation {
+                sell_price: price * 10f64,
+                split_price: price * 10f64,
+                buy_price: price * 10f64,
+                items: vec![AppraisalItem {
+                    type_id: TypeId::from(34),
+                    name: "Tritanium".into(),
+                    amount: 10,
+                    sell_price: price,
+                    split_price: price,
+                    buy_price: price,
+                    sell_price_total: price * 10f64,
+                    split_price_total: price * 10f64,
+                    buy_price_total: price * 10f64,
+                    provider: self.name,
+                    spread: 0f64,
+                    sources: Vec::new(),
+                }],
+                code: Some(self.name.into()),
+                uri: None,
+                provider: self.name,
+                market: options.hub,
+            })
+        }
+    }
+
+    /// Entries every test appraises
+    fn tritanium() -> Vec<AppraisalEntry> {
+        vec![AppraisalEntry::new(34, "Tritanium", 10)]
+    }
+
+    /// Creates a provider that answers after the given milliseconds
+    fn fixed(name: &'static str, price: Option<f64>, delay: u64) -> Fixed {
+        Fixed {
+            name,
+            price,
+            delay: Duration::from_millis(delay),
+        }
+    }
+
+    #[tokio::test]
+    async fn fallback_skips_failing_and_slow_providers() {
+        let timeout = Duration::from_millis(50);
+        let chain = AppraisalChain::new(ChainMode::Fallback)
+            .push(fixed("failing", None, 0), timeout)
+            .push(fixed("slow", Some(1f64), 500), timeout)
+            .push(fixed("third", Some(5f64), 0), timeout)
+            .push(fixed("fourth", Some(7f64), 0), timeout);
+
+        let appraisal = chain.create(&AppraisalOptions::default(), tritanium()).await.unwrap();
+        assert_eq!(appraisal.provider, "third");
+        assert_eq!(appraisal.split_price, 50f64);
+
+        let chain = AppraisalChain::new(ChainMode::Fallback)
+            .push(fixed("failing", None, 0), timeout)
+            .push(fixed("slow", Some(1f64), 500), timeout);
+        match chain.create(&AppraisalOptions::default(), tritanium()).await {
+            Err(Error::AllProvidersFailed(x)) => {
+                assert_eq!(x.len(), 2);
+                assert!(matches!(x[1], ("slow", Error::Timeout("slow"))));
+            }
+            x => panic!("Unexpected result {:?}", x),
+        }
+    }
+
+    #[tokio::test]
+    async fn merge_averages_prices_of_all_providers() {
+        let timeout = Duration::from_millis(50);
+        let chain = AppraisalChain::new(ChainMode::Merge)
+            .push(fixed("first", Some(4f64), 0), timeout)
+            .push(fixed("failing", None, 0), timeout)
+            .push(fixed("second", Some(6f64), 10), timeout)
+            .push(fixed("slow", Some(100f64), 500), timeout);
+
+        let appraisal = chain.create(&AppraisalOptions::default(), tritanium()).await.unwrap();
+        assert_eq!(appraisal.provider, AppraisalChain::NAME);
+        assert_eq!(appraisal.code, None);
+        assert_eq!(appraisal.uri, None);
+        assert_eq!(appraisal.split_price, 50f64);
+
+        let item = &appraisal.items[0];
+        assert_eq!(item.split_price, 5f64);
+        assert_eq!(item.spread, 2f64);
+        assert_eq!(
+            item.sources.iter().map(|x| x.provider).collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+    }
+}
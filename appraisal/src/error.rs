@@ -1,12 +1,34 @@
+use caph_connector::TypeId;
+
 /// Error that can be thrown by the application.
 #[derive(Debug)]
 pub enum Error {
     /// Thrown when a ENV is missing, contains the name of the missing ENV
     MissingEnv(String),
+    /// Thrown when a ENV has an invalid value, contains the name of the ENV
+    InvalidEnv(String),
     /// Error when constructing a reqwest client fails
     CouldNotConstructClient(reqwest::Error),
     /// Error during request
     RequestError(reqwest::Error),
+    /// Error while reading the collected market or the items from the
+    /// database
+    DatabaseError(sqlx::Error),
+
+    /// A provider does not support the requested market hub, contains the
+    /// name of the provider
+    UnsupportedMarket(&'static str),
+    /// A provider has no price for some types, contains the name of the
+    /// provider and the ids of the types
+    MissingPrices(&'static str, Vec<TypeId>),
+    /// A provider did not answer in time, contains the name of the provider
+    Timeout(&'static str),
+    /// A configured provider is not known, contains the name of the provider
+    UnknownProvider(String),
+    /// No provider is configured
+    NoProvider,
+    /// All providers of a chain failed, contains the error of each provider
+    AllProvidersFailed(Vec<(&'static str, Error)>),
 }
 
 impl std::error::Error for Error {}
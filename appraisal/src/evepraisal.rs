@@ -0,0 +1,531 @@
+use async_trait::*;
+use caph_connector::TypeId;
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::{Appraisal, AppraisalEntry, AppraisalInformation, AppraisalItem, AppraisalOptions, Error, MarketHub, Pricing};
+
+/// Implementation for [evepraisal](https://evepraisal.com/)
+///
+/// Additional documentation [api-docs](https://evepraisal.com/api-docs)
+pub struct Evepraisal {
+    /// Client for communicating with evepraisal
+    client: Client,
+    /// Base url of evepraisal, without trailing slash
+    url:    String,
+}
+
+impl Evepraisal {
+    /// Name of the UserAgent ENV
+    const USER_AGENT: &'static str = "EVEPRAISAL_USER_AGENT";
+    /// Name of the optional ENV for a different evepraisal instance
+    const URL: &'static str = "EVEPRAISAL_URL";
+    /// Default url of evepraisal
+    const DEFAULT_URL: &'static str = "https://evepraisal.com";
+    /// Path for creating appraisals
+    const APPRAISAL_PATH: &'static str = "appraisal.json";
+    /// Name of the provider
+    pub const NAME: &'static str = "evepraisal";
+
+    /// Creates a new instance pointing to the given evepraisal instance.
+    ///
+    /// # Params
+    ///
+    /// * `url`        -> Base url of the evepraisal instance
+    /// * `user_agent` -> User agent that is send with every request
+    ///
+    /// # Errors
+    ///
+    /// If the client could not be constructed
+    ///
+    /// # Returns
+    ///
+    /// Appraisal instance
+    ///
+    pub fn with_url(
+        url:        impl Into<String>,
+        user_agent: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", HeaderValue::from_static("text/plain"));
+
+        let client = Client::builder()
+            .user_agent(user_agent.into())
+            .default_headers(headers)
+            .build()
+            .map_err(Error::CouldNotConstructClient)?;
+
+        Ok(Self {
+            client,
+            url: url.into().trim_end_matches('/').into(),
+        })
+    }
+
+    /// Maps the hub to the name evepraisal uses for the market.
+    ///
+    /// # Params
+    ///
+    /// * `hub` -> Requested market hub
+    ///
+    /// # Errors
+    ///
+    /// If evepraisal does not know the hub
+    ///
+    /// # Returns
+    ///
+    /// Name of the market
+    ///
+    fn market_name(hub: MarketHub) -> Result<&'static str, Error> {
+        match hub {
+            MarketHub::JITA  => Ok("jita"),
+            MarketHub::AMARR => Ok("amarr"),
+            _                => Err(Error::UnsupportedMarket(Self::NAME)),
+        }
+    }
+}
+
+#[async_trait]
+impl Appraisal for Evepraisal
+where
+    Self: Sized,
+{
+    /// Validates that all required Environment variables are set
+    ///
+    /// # Error
+    ///
+    /// Fails when a Environment-Variable is missing
+    ///
+    /// # Returns
+    ///
+    /// `Ok`  -> If all Environment-Variables are set
+    /// `Err` -> Not all Environment-Variables are set, contains the missing ENV-Name
+    ///
+    fn validate() -> Result<(), Error> {
+        std::env::var(Self::USER_AGENT)
+            .map_err(|_| Error::MissingEnv(Self::USER_AGENT.into()))
+            .map(drop)
+    }
+
+    /// Creates a new evepraisal appraisal instance.
+    ///
+    /// The url can be changed with the ENV `EVEPRAISAL_URL`.
+    ///
+    /// # Error
+    ///
+    /// If not all required Environment-Variables are set.
+    ///
+    /// # Returns
+    ///
+    /// Appraisal instance
+    ///
+    fn init() -> Result<Self, Error> {
+        let user_agent = std::env::var(Self::USER_AGENT)
+            .map_err(|_| Error::MissingEnv(Self::USER_AGENT.into()))?;
+        let url = std::env::var(Self::URL)
+            .unwrap_or_else(|_| Self::DEFAULT_URL.into());
+
+        Self::with_url(url, user_agent)
+    }
+
+    /// Name of the provider
+    ///
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    /// Creates a new apprisal
+    ///
+    /// The percentile of the options is ignored, evepraisal only reports
+    /// the 5th percentile.
+    ///
+    /// # Params
+    ///
+    /// * `options` -> Market hub, pricing and if the apprisal should be stored
+    /// * `entries` -> List of entries to create a apprisal for
+    ///
+    /// # Errors
+    ///
+    /// - When the server is not reachable
+    /// - Invalid Format
+    /// - When evepraisal does not support the market hub
+    ///
+    /// # Returns
+    ///
+    /// Appraisal information
+    ///
+    async fn create(
+        &self,
+        options: &AppraisalOptions,
+        entries: Vec<AppraisalEntry>,
+    ) -> Result<AppraisalInformation, Error> {
+        let persist = if options.persist { "yes" } else { "no" };
+
+        let mut params = HashMap::new();
+        params.insert("market", Self::market_name(options.hub)?);
+        params.insert("persist", persist);
+
+        let response = self.client
+            .post(format!("{}/{}", self.url, Self::APPRAISAL_PATH))
+            .query(&params)
+            .body(
+                entries
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+            .send()
+            .await
+            .map_err(Error::RequestError)?
+            .error_for_status()
+            .map_err(Error::RequestError)?
+            .json::<AppraisalResponse>()
+            .await
+            .map_err(Error::RequestError)?;
+
+        Ok(response.appraisal.into_information(&self.url, options))
+    }
+}
+
+/// Represents the response from evepraisal
+#[derive(Debug, Deserialize)]
+pub struct AppraisalResponse {
+    /// The created appraisal
+    pub appraisal: EvepraisalAppraisal,
+}
+
+/// Represents an evepraisal appraisal
+///
+/// Not all fields are represented
+#[derive(Debug, Deserialize)]
+pub struct EvepraisalAppraisal {
+    /// Id of the appraisal, empty if it was not persisted
+    #[serde(default)]
+    pub id: String,
+    /// Totals of all items
+    pub totals: Totals,
+    /// Breakdown of all items
+    #[serde(default)]
+    pub items: Vec<Item>,
+}
+
+impl EvepraisalAppraisal {
+    /// Converts the appraisal into the generalized information.
+    ///
+    /// # Params
+    ///
+    /// * `url`     -> Base url of the evepraisal instance, used for the share uri
+    /// * `options` -> Options the appraisal was created with
+    ///
+    /// # Returns
+    ///
+    /// Generalized appraisal information
+    ///
+    fn into_information(
+        self,
+        url:     &str,
+        options: &AppraisalOptions,
+    ) -> AppraisalInformation {
+        let code = Some(self.id).filter(|x| !x.is_empty());
+        let uri = code
+            .as_ref()
+            .map(|x| format!("{}/a/{}", url, x));
+
+        let items = self
+            .items
+            .into_iter()
+            .map(|x| x.into_item(options.pricing))
+            .collect::<Vec<_>>();
+        // the totals of evepraisal are always based on the best order
+        let (sell_price, buy_price) = match options.pricing {
+            Pricing::Immediate => (self.totals.sell, self.totals.buy),
+            Pricing::Top5      => (
+                items.iter().map(|x| x.sell_price_total).sum::<f64>(),
+                items.iter().map(|x| x.buy_price_total).sum::<f64>(),
+            ),
+        };
+
+        AppraisalInformation {
+            sell_price: sell_price,
+            split_price: (sell_price + buy_price) / 2f64,
+            buy_price: buy_price,
+            items: items,
+            code: code,
+            uri: uri,
+            provider: Evepraisal::NAME,
+            market: options.hub,
+        }
+    }
+}
+
+/// Represents the evepraisal totals
+#[derive(Debug, Deserialize)]
+pub struct Totals {
+    /// Buy price for all items
+    pub buy: f64,
+    /// Sell price for all items
+    pub sell: f64,
+    /// Volume of all items
+    pub volume: f64,
+}
+
+/// Represents an evepraisal item
+#[derive(Debug, Deserialize)]
+pub struct Item {
+    /// [TypeId] of the item
+    #[serde(rename = "typeID")]
+    pub type_id: TypeId,
+    /// Name of the item
+    #[serde(rename = "typeName")]
+    pub name: String,
+    /// Given amount of the item
+    pub quantity: u64,
+    /// Prices of the item
+    pub prices: ItemPrices,
+}
+
+impl Item {
+    /// Converts the item into the generalized item.
+    ///
+    /// # Params
+    ///
+    /// * `pricing` -> Determines which prices are used
+    ///
+    /// # Returns
+    ///
+    /// Generalized appraisal item
+    ///
+    fn into_item(self, pricing: Pricing) -> AppraisalItem {
+        let (sell_price, buy_price) = match pricing {
+            Pricing::Immediate => (self.prices.sell.min, self.prices.buy.max),
+            Pricing::Top5      => (
+                self.prices.sell.percentile.unwrap_or(self.prices.sell.min),
+                self.prices.buy.percentile.unwrap_or(self.prices.buy.max),
+            ),
+        };
+        let split_price = (sell_price + buy_price) / 2f64;
+        let amount = self.quantity as f64;
+
+        AppraisalItem {
+            type_id: self.type_id,
+            name: self.name,
+
+            amount: self.quantity,
+
+            sell_price: sell_price,
+            split_price: split_price,
+            buy_price: buy_price,
+
+            sell_price_total: sell_price * amount,
+            split_price_total: split_price * amount,
+            buy_price_total: buy_price * amount,
+
+            provider: Evepraisal::NAME,
+            spread: 0f64,
+            sources: Vec::new(),
+        }
+    }
+}
+
+/// Represents the prices of an evepraisal item
+#[derive(Debug, Deserialize)]
+pub struct ItemPrices {
+    /// Statistics of all buy orders
+    pub buy: PriceStats,
+    /// Statistics of all sell orders
+    pub sell: PriceStats,
+}
+
+/// Represents the order statistics of an evepraisal item
+///
+/// Not all fields are represented
+#[derive(Debug, Deserialize)]
+pub struct PriceStats {
+    /// Highest price of all orders
+    pub max: f64,
+    /// Lowest price of all orders
+    pub min: f64,
+    /// Average price of the best orders that make up 5% of the volume,
+    /// [None] if evepraisal did not send it, in that case the best price
+    /// is used
+    #[serde(default)]
+    pub percentile: Option<f64>,
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod evepraisal_tests {
+    use super::*;
+    use caph_connector::{LocationId, RegionId};
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    /// Response of evepraisal for `Tritanium 100` and `Pyerite 10`
+    const RESPONSE: &str = r#"{
+        "appraisal": {
+            "id": "1abcd",
+            "market_name": "jita",
+            "totals": { "buy": 560.0, "sell": 620.0, "volume": 1.1 },
+            "items": [
+                {
+                    "name": "Tritanium",
+                    "typeID": 34,
+                    "typeName": "Tritanium",
+                    "quantity": 100,
+                    "prices": {
+                        "buy": { "max": 4.0, "min": 1.0, "median": 3.5, "percentile": 3.75 },
+                        "sell": { "max": 9.0, "min": 4.5, "median": 5.0, "percentile": 4.75 }
+                    }
+                },
+                {
+                    "name": "Pyerite",
+                    "typeID": 35,
+                    "typeName": "Pyerite",
+                    "quantity": 10,
+                    "prices": {
+                        "buy": { "max": 16.0, "min": 10.0, "percentile": 15.5 },
+                        "sell": { "max": 30.0, "min": 17.0, "percentile": 18.5 }
+                    }
+                }
+            ]
+        }
+    }"#;
+
+    /// Starts a local stand-in for evepraisal that answers every request with
+    /// [RESPONSE] and records the query and body of each request.
+    async fn stand_in() -> (String, Arc<Mutex<Vec<(String, String)>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        let service = make_service_fn(move |_| {
+            let recorded = recorded.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let recorded = recorded.clone();
+                    async move {
+                        let query = format!(
+                            "{} {}",
+                            request.uri().path(),
+                            request.uri().query().unwrap_or_default()
+                        );
+                        let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+                        recorded
+                            .lock()
+                            .unwrap()
+                            .push((query, String::from_utf8_lossy(&body).into()));
+                        Ok::<_, Infallible>(Response::new(Body::from(RESPONSE)))
+                    }
+                }))
+            }
+        });
+
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(service);
+        let url = format!("http://{}/", server.local_addr());
+        tokio::spawn(server);
+        (url, requests)
+    }
+
+    /// Entries the stand-in answers for
+    fn entries() -> Vec<AppraisalEntry> {
+        vec![
+            AppraisalEntry::new(34, "Tritanium", 100),
+            AppraisalEntry::new(35, "Pyerite", 10),
+        ]
+    }
+
+    #[tokio::test]
+    async fn create_maps_the_appraisal() {
+        let (url, requests) = stand_in().await;
+        let evepraisal = Evepraisal::with_url(url.clone(), "caph tests").unwrap();
+
+        let options = AppraisalOptions {
+            pricing: Pricing::Immediate,
+            persist: true,
+            ..AppraisalOptions::default()
+        };
+        let appraisal = evepraisal
+            .create(&options, entries())
+            .await
+            .unwrap();
+
+        assert_eq!(appraisal.sell_price, 620f64);
+        assert_eq!(appraisal.buy_price, 560f64);
+        assert_eq!(appraisal.split_price, 590f64);
+        assert_eq!(appraisal.code, Some("1abcd".into()));
+        assert_eq!(appraisal.uri, Some(format!("{}a/1abcd", url)));
+        assert_eq!(appraisal.market, MarketHub::JITA);
+
+        let tritanium = &appraisal.items[0];
+        assert_eq!(tritanium.type_id, TypeId::from(34));
+        assert_eq!(tritanium.amount, 100);
+        assert_eq!(tritanium.sell_price, 4.5f64);
+        assert_eq!(tritanium.buy_price, 4f64);
+        assert_eq!(tritanium.split_price, 4.25f64);
+        assert_eq!(tritanium.split_price_total, 425f64);
+
+        let requests = requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 1);
+        let (query, body) = &requests[0];
+        assert!(query.starts_with("/appraisal.json "));
+        assert!(query.contains("market=jita"));
+        assert!(query.contains("persist=yes"));
+        assert_eq!(body, "Tritanium 100\nPyerite 10");
+    }
+
+    #[tokio::test]
+    async fn top5_pricing_and_other_markets() {
+        let (url, requests) = stand_in().await;
+        let evepraisal = Evepraisal::with_url(url, "caph tests").unwrap();
+
+        let options = AppraisalOptions {
+            hub: MarketHub::AMARR,
+            ..AppraisalOptions::default()
+        };
+        let appraisal = evepraisal
+            .create(&options, entries())
+            .await
+            .unwrap();
+
+        assert_eq!(appraisal.market, MarketHub::AMARR);
+        assert_eq!(appraisal.sell_price, 660f64);
+        assert_eq!(appraisal.buy_price, 530f64);
+        assert_eq!(appraisal.split_price, 595f64);
+        assert_eq!(appraisal.items[0].sell_price, 4.75f64);
+        assert_eq!(appraisal.items[0].buy_price, 3.75f64);
+
+        let (query, _) = requests.lock().unwrap()[0].clone();
+        assert!(query.contains("market=amarr"));
+        assert!(query.contains("persist=no"));
+
+        let options = AppraisalOptions {
+            hub: MarketHub::new(RegionId(10_000_060), LocationId(1_035_466_617_946)),
+            ..AppraisalOptions::default()
+        };
+        assert!(matches!(
+            evepraisal.create(&options, entries()).await,
+            Err(Error::UnsupportedMarket(Evepraisal::NAME))
+        ));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn missing_percentile_falls_back_to_best_price() {
+        let item = Item {
+            type_id:  TypeId::from(34),
+            name:     "Tritanium".into(),
+            quantity: 100,
+            prices:   ItemPrices {
+                buy:  PriceStats { max: 4.0, min: 1.0, percentile: None },
+                sell: PriceStats { max: 9.0, min: 4.5, percentile: None },
+            },
+        }
+        .into_item(Pricing::Top5);
+
+        assert_eq!(item.sell_price, 4.5f64);
+        assert_eq!(item.buy_price, 4.0f64);
+    }
+}
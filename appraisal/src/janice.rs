@@ -1,10 +1,11 @@
 use async_trait::*;
+use caph_connector::TypeId;
 use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::Client;
 use serde::Deserialize;
 use std::collections::HashMap;
 
-use crate::{Appraisal, AppraisalInformation, AppraisalItem, Error};
+use crate::{Appraisal, AppraisalEntry, AppraisalInformation, AppraisalItem, AppraisalOptions, Error, MarketHub, Pricing};
 
 /// Implementation for [janice](https://janice.e-351.com/)
 ///
@@ -18,6 +19,59 @@ impl Janice {
     const API_KEY: &'static str = "JANICE_API_KEY";
     /// Url for creating appraisals
     const APPRAISAL_URL: &'static str = "https://janice.e-351.com/api/rest/v2/appraisal";
+    /// Name of the provider
+    pub const NAME: &'static str = "janice";
+
+    /// Maps the hub to the id janice uses for the market.
+    ///
+    /// # Params
+    ///
+    /// * `hub` -> Requested market hub
+    ///
+    /// # Errors
+    ///
+    /// If janice does not know the hub
+    ///
+    /// # Returns
+    ///
+    /// Id of the market
+    ///
+    fn market_id(hub: MarketHub) -> Result<u8, Error> {
+        match hub {
+            MarketHub::JITA  => Ok(2),
+            MarketHub::AMARR => Ok(115),
+            _                => Err(Error::UnsupportedMarket(Self::NAME)),
+        }
+    }
+
+    /// Creates the query parameters for an appraisal.
+    ///
+    /// # Params
+    ///
+    /// * `options` -> Options of the appraisal
+    ///
+    /// # Errors
+    ///
+    /// If janice does not know the hub
+    ///
+    /// # Returns
+    ///
+    /// Query parameters of the request
+    ///
+    fn query(options: &AppraisalOptions) -> Result<HashMap<&'static str, String>, Error> {
+        let pricing_variant = match options.pricing {
+            Pricing::Immediate => "immediate",
+            Pricing::Top5      => "top5percent",
+        };
+
+        let mut params = HashMap::new();
+        params.insert("market", Self::market_id(options.hub)?.to_string());
+        params.insert("persist", options.persist.to_string());
+        params.insert("designation", "appraisal".into());
+        params.insert("pricing", "split".into());
+        params.insert("pricingVariant", pricing_variant.into());
+        Ok(params)
+    }
 }
 
 #[async_trait]
@@ -75,18 +129,27 @@ where
         Ok(Self(client))
     }
 
+    /// Name of the provider
+    ///
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
     /// Creates a new apprisal
     ///
+    /// The percentile of the options is ignored, janice only supports the
+    /// top 5%.
+    ///
     /// # Params
     ///
-    /// * `persist` -> Determines if the apprisal should be stored
+    /// * `options` -> Market hub, pricing and if the apprisal should be stored
     /// * `entries` -> List of entries to create a apprisal for
-    ///                Format: `item_name quantity`
     ///
     /// # Errors
     ///
     /// - When the server is not reachable
     /// - Invalid Format
+    /// - When janice does not support the market hub
     ///
     /// # Returns
     ///
@@ -94,26 +157,28 @@ where
     ///
     async fn create(
         &self,
-        persist: bool,
-        entries: Vec<String>,
+        options: &AppraisalOptions,
+        entries: Vec<AppraisalEntry>,
     ) -> Result<AppraisalInformation, Error> {
-        let mut params = HashMap::new();
-        params.insert("persist", persist.to_string());
-        params.insert("designation", "appraisal".into());
-        params.insert("pricing", "split".into());
-        params.insert("pricingVariant", "immediate".into());
+        let params = Self::query(options)?;
 
         self.0
             .post(Self::APPRAISAL_URL)
             .query(&params)
-            .body(entries.join("\n"))
+            .body(
+                entries
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
             .send()
             .await
             .map_err(Error::RequestError)?
             .json::<ApprisalResponse>()
             .await
             .map_err(Error::RequestError)
-            .map(Into::into)
+            .map(|x| x.into_information(options))
     }
 }
 
@@ -139,20 +204,40 @@ pub struct ApprisalResponse {
     pub code: Option<String>,
 }
 
-impl Into<AppraisalInformation> for ApprisalResponse {
-    fn into(self) -> AppraisalInformation {
+impl ApprisalResponse {
+    /// Converts the response into the generalized information.
+    ///
+    /// # Params
+    ///
+    /// * `options` -> Options the appraisal was created with
+    ///
+    /// # Returns
+    ///
+    /// Generalized appraisal information
+    ///
+    fn into_information(self, options: &AppraisalOptions) -> AppraisalInformation {
         let uri = self
             .code
             .as_ref()
             .map(|x| format!("https://janice.e-351.com/a/{}", x));
+        let prices = match options.pricing {
+            Pricing::Immediate => self.immidiate_price,
+            Pricing::Top5      => self.average_price,
+        };
 
         AppraisalInformation {
-            sell_price: self.average_price.sell_price,
-            split_price: self.average_price.split_price,
-            buy_price: self.average_price.buy_price,
-            items: self.items.into_iter().map(Into::into).collect::<Vec<_>>(),
+            sell_price: prices.sell_price,
+            split_price: prices.split_price,
+            buy_price: prices.buy_price,
+            items: self
+                .items
+                .into_iter()
+                .map(|x| x.into_item(options.pricing))
+                .collect::<Vec<_>>(),
             code: self.code,
             uri: uri,
+            provider: Janice::NAME,
+            market: options.hub,
         }
     }
 }
@@ -178,21 +263,40 @@ pub struct Item {
     pub item_type: ItemType,
 }
 
-impl Into<AppraisalItem> for Item {
-    fn into(self) -> AppraisalItem {
+impl Item {
+    /// Converts the item into the generalized item.
+    ///
+    /// # Params
+    ///
+    /// * `pricing` -> Determines which prices are used
+    ///
+    /// # Returns
+    ///
+    /// Generalized appraisal item
+    ///
+    fn into_item(self, pricing: Pricing) -> AppraisalItem {
+        let prices = match pricing {
+            Pricing::Immediate => self.immidiate_price,
+            Pricing::Top5      => self.average_price,
+        };
+
         AppraisalItem {
             type_id: self.item_type.eid,
             name: self.item_type.name,
 
             amount: self.amount,
 
-            sell_price: self.average_price.sell_price,
-            split_price: self.average_price.split_price,
-            buy_price: self.average_price.buy_price,
+            sell_price: prices.sell_price,
+            split_price: prices.split_price,
+            buy_price: prices.buy_price,
+
+            sell_price_total: prices.sell_price_total,
+            split_price_total: prices.split_price_total,
+            buy_price_total: prices.buy_price_total,
 
-            sell_price_total: self.average_price.sell_price_total,
-            split_price_total: self.average_price.split_price_total,
-            buy_price_total: self.average_price.buy_price_total,
+            provider: Janice::NAME,
+            spread: 0f64,
+            sources: Vec::new(),
         }
     }
 }
@@ -200,8 +304,8 @@ impl Into<AppraisalItem> for Item {
 /// Represents an janice Appraisal Value
 #[derive(Debug, Deserialize)]
 pub struct ItemType {
-    /// TypeId of the item
-    pub eid: u32,
+    /// [TypeId] of the item
+    pub eid: TypeId,
     /// Name of the item
     pub name: String,
 }
@@ -211,13 +315,13 @@ pub struct ItemType {
 pub struct AppraisalValue {
     /// Buy price for all items
     #[serde(rename = "totalBuyPrice")]
-    pub buy_price: f32,
+    pub buy_price: f64,
     /// Split price for all items
     #[serde(rename = "totalSplitPrice")]
-    pub split_price: f32,
+    pub split_price: f64,
     /// Sell price for all items
     #[serde(rename = "totalSellPrice")]
-    pub sell_price: f32,
+    pub sell_price: f64,
 }
 
 /// Represents an janice Appraisal Item Value
@@ -225,21 +329,120 @@ pub struct AppraisalValue {
 pub struct AppraisalValueItem {
     /// Buy price for one items
     #[serde(rename = "buyPrice")]
-    pub buy_price: f32,
+    pub buy_price: f64,
     /// Split price for one items
     #[serde(rename = "splitPrice")]
-    pub split_price: f32,
+    pub split_price: f64,
     /// Sell price for one items
     #[serde(rename = "sellPrice")]
-    pub sell_price: f32,
+    pub sell_price: f64,
 
     /// Buy price for all items
     #[serde(rename = "buyPriceTotal")]
-    pub buy_price_total: f32,
+    pub buy_price_total: f64,
     /// Split price for all items
     #[serde(rename = "splitPriceTotal")]
-    pub split_price_total: f32,
+    pub split_price_total: f64,
     /// Sell price for all items
     #[serde(rename = "sellPriceTotal")]
-    pub sell_price_total: f32,
+    pub sell_price_total: f64,
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod janice_tests {
+    use super::*;
+    use caph_connector::{LocationId, RegionId};
+
+    /// Prices of a single unit, the totals are the prices times 10
+    fn item_value(buy: f64, sell: f64) -> AppraisalValueItem {
+        AppraisalValueItem {
+            buy_price:         buy,
+            split_price:       (buy + sell) / 2f64,
+            sell_price:        sell,
+            buy_price_total:   buy * 10f64,
+            split_price_total: (buy + sell) * 5f64,
+            sell_price_total:  sell * 10f64,
+        }
+    }
+
+    /// Prices of the whole appraisal
+    fn value(buy: f64, sell: f64) -> AppraisalValue {
+        AppraisalValue {
+            buy_price:   buy,
+            split_price: (buy + sell) / 2f64,
+            sell_price:  sell,
+        }
+    }
+
+    /// Response for `Tritanium 10` with different prices for each variant
+    fn response() -> ApprisalResponse {
+        ApprisalResponse {
+            effective_price: value(30f64, 50f64),
+            immidiate_price: value(40f64, 60f64),
+            average_price:   value(35f64, 55f64),
+            items:           vec![Item {
+                effective_price: item_value(3f64, 5f64),
+                immidiate_price: item_value(4f64, 6f64),
+                average_price:   item_value(3.5f64, 5.5f64),
+                amount:          10,
+                item_type:       ItemType {
+                    eid:  TypeId::from(34),
+                    name: "Tritanium".into(),
+                },
+            }],
+            code:            Some("abc".into()),
+        }
+    }
+
+    #[test]
+    fn only_jita_and_amarr_are_supported() {
+        assert_eq!(Janice::market_id(MarketHub::JITA).unwrap(), 2);
+        assert_eq!(Janice::market_id(MarketHub::AMARR).unwrap(), 115);
+        assert!(matches!(
+            Janice::market_id(MarketHub::new(RegionId(10_000_060), LocationId(1_035_466_617_946))),
+            Err(Error::UnsupportedMarket(Janice::NAME))
+        ));
+    }
+
+    #[test]
+    fn pricing_selects_the_variant() {
+        let options = AppraisalOptions {
+            hub:     MarketHub::AMARR,
+            pricing: Pricing::Immediate,
+            persist: true,
+            ..AppraisalOptions::default()
+        };
+        let query = Janice::query(&options).unwrap();
+        assert_eq!(query["market"], "115");
+        assert_eq!(query["persist"], "true");
+        assert_eq!(query["pricingVariant"], "immediate");
+
+        let query = Janice::query(&AppraisalOptions::default()).unwrap();
+        assert_eq!(query["market"], "2");
+        assert_eq!(query["persist"], "false");
+        assert_eq!(query["pricingVariant"], "top5percent");
+    }
+
+    #[test]
+    fn pricing_selects_the_prices() {
+        let options = AppraisalOptions {
+            pricing: Pricing::Immediate,
+            ..AppraisalOptions::default()
+        };
+        let appraisal = response().into_information(&options);
+        assert_eq!(appraisal.sell_price, 60f64);
+        assert_eq!(appraisal.buy_price, 40f64);
+        assert_eq!(appraisal.items[0].sell_price, 6f64);
+        assert_eq!(appraisal.items[0].buy_price_total, 40f64);
+        assert_eq!(appraisal.uri, Some("https://janice.e-351.com/a/abc".into()));
+        assert_eq!(appraisal.market, MarketHub::JITA);
+
+        let appraisal = response().into_information(&AppraisalOptions::default());
+        assert_eq!(appraisal.sell_price, 55f64);
+        assert_eq!(appraisal.split_price, 45f64);
+        assert_eq!(appraisal.items[0].buy_price, 3.5f64);
+        assert_eq!(appraisal.items[0].split_price_total, 45f64);
+        assert_eq!(appraisal.items[0].type_id, TypeId::from(34));
+    }
 }
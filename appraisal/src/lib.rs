@@ -1,6 +1,7 @@
 //! Wrapper for different praisal sites.
 //!
-//! Currently supports [janice](https://janice.e-351.com/) and [evepraisal](https://evepraisal.com/).
+//! Currently supports [janice](https://janice.e-351.com/), [evepraisal](https://evepraisal.com/)
+//! and the market orders that are stored by the collector.
 
 #![forbid(
     missing_docs,
@@ -22,41 +23,112 @@
 pub mod error;
 pub use self::error::Error;
 
+/// Apprisal implementation for [evepraisal](https://evepraisal.com/)
+pub mod evepraisal;
+pub use self::evepraisal::Evepraisal;
+
 /// Apprisal implementation for [janice](https://janice.e-351.com/)
 pub mod janice;
 pub use self::janice::Janice;
 
+/// Apprisal implementation based on the market orders of the collector
+pub mod local;
+pub use self::local::LocalMarket;
+
+/// Options that are passed to every apprisal implementation
+pub mod options;
+pub use self::options::{AppraisalOptions, MarketHub, Pricing};
+
+/// Combines multiple apprisal implementations
+pub mod chain;
+pub use self::chain::{AppraisalChain, ChainMode};
+
+/// Parsers for text that is copied from the EVE client
+pub mod parser;
+pub use self::parser::{ParsedItem, ParsedText, ResolvedText};
+
 use async_trait::*;
-use serde::Serialize;
+use caph_connector::TypeId;
+use serde::{Deserialize, Serialize};
 
 /// Generalized trait for communicating with a praisal site.
 #[async_trait]
 pub trait Appraisal {
     /// Validates that all required Environment variables are set
-    fn validate() -> Result<(), Error>;
+    fn validate() -> Result<(), Error>
+    where
+        Self: Sized;
 
     /// Creates a new appraisal instance
     fn init() -> Result<Self, Error>
     where
         Self: Sized;
 
+    /// Name of the provider, set as `provider` of every appraisal
+    fn name(&self) -> &'static str;
+
     /// Creates a new appraisal
     async fn create(
         &self,
-        persist: bool,
-        entries: Vec<String>,
+        options: &AppraisalOptions,
+        entries: Vec<AppraisalEntry>,
     ) -> Result<AppraisalInformation, Error>;
 }
 
+/// Single item that should be appraised
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AppraisalEntry {
+    /// [TypeId] of the item
+    pub type_id: TypeId,
+    /// Name of the item, required by providers that only understand text
+    pub name: String,
+    /// Amount of the item
+    pub quantity: u64,
+}
+
+impl AppraisalEntry {
+    /// Creates a new entry.
+    ///
+    /// # Params
+    ///
+    /// * `type_id`  -> [TypeId] of the item
+    /// * `name`     -> Name of the item
+    /// * `quantity` -> Amount of the item
+    ///
+    /// # Returns
+    ///
+    /// New entry
+    ///
+    pub fn new(
+        type_id:  impl Into<TypeId>,
+        name:     impl Into<String>,
+        quantity: u64,
+    ) -> Self {
+        Self {
+            type_id: type_id.into(),
+            name: name.into(),
+            quantity,
+        }
+    }
+}
+
+impl std::fmt::Display for AppraisalEntry {
+    /// Formats the entry as `item_name quantity`, the format that is
+    /// understood by all appraisal sites
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.name, self.quantity)
+    }
+}
+
 /// Generalized Appraisal information
 #[derive(Debug, Serialize)]
 pub struct AppraisalInformation {
     /// Sell price for all items
-    pub sell_price: f32,
+    pub sell_price: f64,
     /// Split price for all items
-    pub split_price: f32,
+    pub split_price: f64,
     /// Buy price for all items
-    pub buy_price: f32,
+    pub buy_price: f64,
 
     /// Breakdown of all items
     pub items: Vec<AppraisalItem>,
@@ -65,29 +137,57 @@ pub struct AppraisalInformation {
     pub code: Option<String>,
     /// Uri for sharing the appraisal
     pub uri: Option<String>,
+
+    /// Provider that created the appraisal
+    pub provider: &'static str,
+    /// Market hub the prices were taken from
+    pub market: MarketHub,
 }
 
 /// Single item for an appraisal
 #[derive(Debug, Serialize)]
 pub struct AppraisalItem {
-    /// TypeId of the item
-    pub type_id: u32,
+    /// [TypeId] of the item
+    pub type_id: TypeId,
     /// Name of the item
     pub name: String,
     /// Amount that is required
     pub amount: u64,
 
     /// Sell price for a single item
-    pub sell_price: f32,
+    pub sell_price: f64,
     /// Split price for a single item
-    pub split_price: f32,
+    pub split_price: f64,
     /// Buy price for a single item
-    pub buy_price: f32,
+    pub buy_price: f64,
 
     /// Total sell price for the required amount
-    pub sell_price_total: f32,
+    pub sell_price_total: f64,
     /// Total split price for the required amount
-    pub split_price_total: f32,
+    pub split_price_total: f64,
     /// Total buy price for the required amount
-    pub buy_price_total: f32,
+    pub buy_price_total: f64,
+
+    /// Provider that produced the prices
+    pub provider: &'static str,
+    /// Difference between the highest and lowest split price of all
+    /// providers, only set if the prices of multiple providers were merged
+    pub spread: f64,
+    /// Prices of every provider the merged prices are based on
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<ProviderPrice>,
+}
+
+/// Prices of a single item by a single provider
+#[derive(Clone, Debug, Serialize)]
+pub struct ProviderPrice {
+    /// Provider that produced the prices
+    pub provider: &'static str,
+
+    /// Sell price for a single item
+    pub sell_price: f64,
+    /// Split price for a single item
+    pub split_price: f64,
+    /// Buy price for a single item
+    pub buy_price: f64,
 }
@@ -0,0 +1,508 @@
+use async_trait::*;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::collections::HashMap;
+
+use crate::{Appraisal, AppraisalEntry, AppraisalInformation, AppraisalItem, AppraisalOptions, Error, MarketHub, Pricing};
+
+/// Appraisal that is calculated from the market orders and history the
+/// collector stores in postgres.
+///
+/// Supports every hub whose orders are collected by the collector. With
+/// [Pricing::Top5] sell and buy prices are the volume weighted average of
+/// the best orders that make up the percentile of the total volume, so that
+/// a single order with a tiny volume does not decide the price. With
+/// [Pricing::Immediate] the best order is used. If there are no orders, the
+/// average of the latest market history is used. If there is neither, the
+/// appraisal fails with [Error::MissingPrices].
+///
+/// If a type has orders on one side only and no history, for example only
+/// sell orders, the price of that side is also used for the other side.
+/// Such an item has the same buy, split and sell price.
+///
+/// Local appraisals can not be shared, `code` and `uri` are always empty.
+pub struct LocalMarket {
+    /// Connection to the database of the collector
+    pool:       PgPool,
+    /// Share of the total volume that is used if the options do not contain
+    /// a percentile, between 0 and 1
+    percentile: f32,
+}
+
+impl LocalMarket {
+    /// Name of the ENV containing the database of the collector
+    const DATABASE_URL: &'static str = "APPRAISAL_DATABASE_URL";
+    /// Name of the optional ENV for the percentile
+    const PERCENTILE: &'static str = "APPRAISAL_PERCENTILE";
+    /// The best 5% of the volume are used by default
+    const DEFAULT_PERCENTILE: f32 = 0.05;
+    /// Name of the provider
+    pub const NAME: &'static str = "local";
+
+    /// Creates a new instance that uses the best 5% of the volume.
+    ///
+    /// # Params
+    ///
+    /// * `pool` -> Connection to the database of the collector
+    ///
+    /// # Returns
+    ///
+    /// Appraisal instance
+    ///
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            percentile: Self::DEFAULT_PERCENTILE,
+        }
+    }
+
+    /// Sets the share of the total volume that is used if the options do
+    /// not contain a percentile.
+    ///
+    /// # Params
+    ///
+    /// * `percentile` -> Share between 0 and 1, 0 uses the best order only
+    ///
+    /// # Returns
+    ///
+    /// The modified instance
+    ///
+    pub fn percentile(mut self, percentile: f32) -> Self {
+        self.percentile = percentile.clamp(0f32, 1f32);
+        self
+    }
+
+    /// Calculates the buy and sell price of the given types.
+    ///
+    /// # Params
+    ///
+    /// * `hub`        -> Market hub the prices are taken from
+    /// * `percentile` -> Share of the total volume, 0 uses the best order only
+    /// * `type_ids`   -> Types to get the prices for
+    ///
+    /// # Errors
+    ///
+    /// If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// Map from the type id to its buy and sell price, see [type_prices]
+    ///
+    async fn prices(
+        &self,
+        hub:        MarketHub,
+        percentile: f32,
+        type_ids: Vec<i32>,
+    ) -> Result<HashMap<i32, (f64, f64)>, Error> {
+        let orders = sqlx::query_as::<_, (i32, bool, f64, i32)>("
+                SELECT type_id, is_buy_order, price, volume_remain
+                FROM market_orders
+                WHERE location_id = $1
+                  AND type_id = ANY($2)
+            ")
+            .bind(*hub.location_id)
+            .bind(&type_ids)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(Error::DatabaseError)?;
+
+        let history = sqlx::query_as::<_, (i32, f64)>("
+                SELECT DISTINCT ON (type_id) type_id, average
+                FROM market_history
+                WHERE region_id = $1
+                  AND type_id = ANY($2)
+                ORDER BY type_id, date DESC
+            ")
+            .bind(*hub.region_id)
+            .bind(&type_ids)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(Error::DatabaseError)?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        Ok(type_prices(type_ids, orders, history, percentile))
+    }
+}
+
+/// Calculates the buy and sell price of the given types from their orders
+/// and history.
+///
+/// # Params
+///
+/// * `type_ids`   -> Types to get the prices for
+/// * `orders`     -> Type id, buy order flag, price and remaining volume of all orders
+/// * `history`    -> Latest average price of the types
+/// * `percentile` -> Share of the total volume, 0 uses the best order only
+///
+/// # Returns
+///
+/// Map from the type id to its buy and sell price, types without orders
+/// and history are missing. If only one side has a price, it is used for
+/// both sides.
+///
+fn type_prices(
+    type_ids:   Vec<i32>,
+    orders:     Vec<(i32, bool, f64, i32)>,
+    history:    HashMap<i32, f64>,
+    percentile: f32,
+) -> HashMap<i32, (f64, f64)> {
+    let mut buy_orders = HashMap::new();
+    let mut sell_orders = HashMap::new();
+    for (type_id, is_buy_order, price, volume) in orders {
+        let orders = if is_buy_order { &mut buy_orders } else { &mut sell_orders };
+        orders
+            .entry(type_id)
+            .or_insert_with(Vec::new)
+            .push((price, volume as u64));
+    }
+
+    type_ids
+        .into_iter()
+        .filter_map(|type_id| {
+            let average = history.get(&type_id).copied();
+            let buy = buy_orders
+                .get(&type_id)
+                .and_then(|x| percentile_price(x.clone(), percentile, true))
+                .or(average);
+            let sell = sell_orders
+                .get(&type_id)
+                .and_then(|x| percentile_price(x.clone(), percentile, false))
+                .or(average);
+
+            match (buy, sell) {
+                (Some(buy), Some(sell)) => Some((type_id, (buy, sell))),
+                (Some(x), None) |
+                (None, Some(x))         => Some((type_id, (x, x))),
+                (None, None)            => None,
+            }
+        })
+        .collect::<HashMap<_, _>>()
+}
+
+#[async_trait]
+impl Appraisal for LocalMarket
+where
+    Self: Sized,
+{
+    /// Validates that all required Environment variables are set
+    ///
+    /// # Error
+    ///
+    /// Fails when a Environment-Variable is missing or invalid
+    ///
+    /// # Returns
+    ///
+    /// `Ok`  -> If all Environment-Variables are set
+    /// `Err` -> Not all Environment-Variables are set, contains the missing ENV-Name
+    ///
+    fn validate() -> Result<(), Error> {
+        std::env::var(Self::DATABASE_URL)
+            .map_err(|_| Error::MissingEnv(Self::DATABASE_URL.into()))?;
+        env_percentile()?;
+        Ok(())
+    }
+
+    /// Creates a new local appraisal instance.
+    ///
+    /// The default percentile can be set with `APPRAISAL_PERCENTILE`, for
+    /// example `0.05`.
+    ///
+    /// # Error
+    ///
+    /// If not all required Environment-Variables are set or are invalid.
+    ///
+    /// # Returns
+    ///
+    /// Appraisal instance
+    ///
+    fn init() -> Result<Self, Error> {
+        let url = std::env::var(Self::DATABASE_URL)
+            .map_err(|_| Error::MissingEnv(Self::DATABASE_URL.into()))?;
+        let pool = PgPoolOptions::new()
+            .max_connections(2)
+            .connect_lazy(&url)
+            .map_err(Error::DatabaseError)?;
+
+        Ok(
+            Self::new(pool)
+                .percentile(env_percentile()?)
+        )
+    }
+
+    /// Name of the provider
+    ///
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    /// Creates a new apprisal
+    ///
+    /// # Params
+    ///
+    /// * `options` -> Market hub and pricing, `persist` is ignored as local
+    ///                appraisals are not stored
+    /// * `entries` -> List of entries to create a apprisal for
+    ///
+    /// # Errors
+    ///
+    /// - When the database is not reachable
+    /// - When there are neither orders nor history for some of the types,
+    ///   contains the ids of those types
+    ///
+    /// # Returns
+    ///
+    /// Appraisal information
+    ///
+    async fn create(
+        &self,
+        options: &AppraisalOptions,
+        entries: Vec<AppraisalEntry>,
+    ) -> Result<AppraisalInformation, Error> {
+        let percentile = match options.pricing {
+            Pricing::Immediate => 0f32,
+            Pricing::Top5      => options
+                .percentile
+                .map_or(self.percentile, |x| x.clamp(0f32, 1f32)),
+        };
+        let prices = self
+            .prices(
+                options.hub,
+                percentile,
+                entries.iter().map(|x| *x.type_id).collect(),
+            )
+            .await?;
+
+        appraisal_information(options, entries, &prices)
+    }
+}
+
+/// Creates the appraisal of the given entries.
+///
+/// # Params
+///
+/// * `options` -> Options the appraisal is created with
+/// * `entries` -> List of entries to appraise
+/// * `prices`  -> Buy and sell price of the types, see [type_prices]
+///
+/// # Errors
+///
+/// If there is no price for some of the types, contains the ids of those
+/// types
+///
+/// # Returns
+///
+/// Appraisal information
+///
+fn appraisal_information(
+    options: &AppraisalOptions,
+    entries: Vec<AppraisalEntry>,
+    prices:  &HashMap<i32, (f64, f64)>,
+) -> Result<AppraisalInformation, Error> {
+    let mut missing = entries
+        .iter()
+        .map(|x| x.type_id)
+        .filter(|x| !prices.contains_key(&**x))
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        missing.sort_unstable();
+        missing.dedup();
+        return Err(Error::MissingPrices(LocalMarket::NAME, missing));
+    }
+
+    let mut information = AppraisalInformation {
+        sell_price: 0f64,
+        split_price: 0f64,
+        buy_price: 0f64,
+        items: Vec::new(),
+        code: None,
+        uri: None,
+        provider: LocalMarket::NAME,
+        market: options.hub,
+    };
+
+    for entry in entries {
+        let (buy, sell) = prices
+            .get(&*entry.type_id)
+            .copied()
+            .unwrap_or_default();
+        let split = (buy + sell) / 2f64;
+        let total = entry.quantity as f64;
+
+        let item = AppraisalItem {
+            type_id: entry.type_id,
+            name: entry.name,
+
+            amount: entry.quantity,
+
+            sell_price: sell,
+            split_price: split,
+            buy_price: buy,
+
+            sell_price_total: sell * total,
+            split_price_total: split * total,
+            buy_price_total: buy * total,
+
+            provider: LocalMarket::NAME,
+            spread: 0f64,
+            sources: Vec::new(),
+        };
+
+        information.sell_price += item.sell_price_total;
+        information.split_price += item.split_price_total;
+        information.buy_price += item.buy_price_total;
+        information.items.push(item);
+    }
+
+    Ok(information)
+}
+
+/// Reads the optional ENV `APPRAISAL_PERCENTILE`.
+///
+/// # Errors
+///
+/// If the ENV is set but not a number
+///
+/// # Returns
+///
+/// Configured percentile, 5% by default
+///
+fn env_percentile() -> Result<f32, Error> {
+    match std::env::var(LocalMarket::PERCENTILE) {
+        Ok(x) => x
+            .trim()
+            .parse::<f32>()
+            .map_err(|_| Error::InvalidEnv(LocalMarket::PERCENTILE.into())),
+        Err(_) => Ok(LocalMarket::DEFAULT_PERCENTILE),
+    }
+}
+
+/// Calculates the volume weighted average price of the best orders that
+/// make up the given share of the total volume.
+///
+/// # Params
+///
+/// * `orders`     -> Price and remaining volume of all orders
+/// * `percentile` -> Share of the total volume, at least one unit is used
+/// * `buy`        -> `true` for buy orders, where higher prices are better
+///
+/// # Returns
+///
+/// - [None] if there are no orders
+/// - Average price
+///
+fn percentile_price(
+    mut orders: Vec<(f64, u64)>,
+    percentile: f32,
+    buy:        bool,
+) -> Option<f64> {
+    orders.retain(|(_, volume)| *volume > 0);
+    if orders.is_empty() {
+        return None;
+    }
+
+    orders.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    if buy {
+        orders.reverse();
+    }
+
+    let total = orders.iter().map(|(_, volume)| volume).sum::<u64>();
+    let wanted = ((total as f64 * percentile as f64).round() as u64).max(1);
+
+    let mut volume = 0u64;
+    let mut value = 0f64;
+    for (price, remain) in orders {
+        let take = remain.min(wanted - volume);
+        volume += take;
+        value += price * take as f64;
+
+        if volume >= wanted {
+            break;
+        }
+    }
+
+    Some(value / volume as f64)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod local_tests {
+    use super::*;
+
+    #[test]
+    fn best_orders_of_the_percentile_are_averaged() {
+        let sell = vec![(6f64, 100), (4f64, 5), (5f64, 95)];
+        // 10% of 200 are 20 units, 5 for 4 ISK and 15 for 5 ISK
+        assert_eq!(percentile_price(sell.clone(), 0.1, false), Some(4.75));
+        // only the best order
+        assert_eq!(percentile_price(sell.clone(), 0f32, false), Some(4f64));
+
+        let buy = vec![(3f64, 10), (2f64, 10)];
+        assert_eq!(percentile_price(buy.clone(), 0.5, true), Some(3f64));
+        assert_eq!(percentile_price(buy, 1f32, true), Some(2.5));
+
+        assert_eq!(percentile_price(vec![(1f64, 0)], 0.05, false), None);
+    }
+
+    #[test]
+    fn prices_fall_back_to_history_and_the_other_side() {
+        let orders = vec![
+            (1, true, 4f64, 10),
+            (1, false, 6f64, 10),
+            (2, false, 8f64, 10),
+            (3, true, 1f64, 10),
+        ];
+        let history = vec![(3, 2f64), (4, 3f64)]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        let prices = type_prices(vec![1, 2, 3, 4, 5, 1], orders, history, 0.05);
+        assert_eq!(prices.get(&1), Some(&(4f64, 6f64)));
+        // only sell orders and no history, the sell price is used for both
+        assert_eq!(prices.get(&2), Some(&(8f64, 8f64)));
+        // no sell orders, the history is used
+        assert_eq!(prices.get(&3), Some(&(1f64, 2f64)));
+        assert_eq!(prices.get(&4), Some(&(3f64, 3f64)));
+        assert_eq!(prices.get(&5), None);
+    }
+
+    #[test]
+    fn information_sums_all_items() {
+        let prices = vec![(1, (4f64, 6f64)), (2, (8f64, 8f64))]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let entries = vec![
+            AppraisalEntry::new(1, "Tritanium", 10),
+            AppraisalEntry::new(2, "Pyerite", 1),
+        ];
+
+        let information = appraisal_information(&AppraisalOptions::default(), entries, &prices).unwrap();
+        assert_eq!(information.items.len(), 2);
+        assert_eq!(information.items[0].split_price, 5f64);
+        assert_eq!(information.items[0].sell_price_total, 60f64);
+        assert_eq!(information.buy_price, 48f64);
+        assert_eq!(information.split_price, 58f64);
+        assert_eq!(information.sell_price, 68f64);
+        assert_eq!(information.provider, LocalMarket::NAME);
+        assert_eq!(information.market, MarketHub::JITA);
+    }
+
+    #[test]
+    fn missing_prices_are_reported() {
+        let prices = vec![(1, (4f64, 6f64))]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let entries = vec![
+            AppraisalEntry::new(3, "Mexallon", 1),
+            AppraisalEntry::new(1, "Tritanium", 10),
+            AppraisalEntry::new(3, "Mexallon", 2),
+        ];
+
+        match appraisal_information(&AppraisalOptions::default(), entries, &prices) {
+            Err(Error::MissingPrices(provider, x)) => {
+                assert_eq!(provider, LocalMarket::NAME);
+                assert_eq!(x, vec![3.into()]);
+            }
+            x => panic!("Unexpected result {:?}", x.map(|x| x.items.len())),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use caph_connector::{LocationId, RegionId};
+use serde::{Deserialize, Serialize};
+
+/// Options for creating an appraisal.
+///
+/// Every provider maps the options to its own parameters. Providers that
+/// do not support the requested hub fail with [crate::Error::UnsupportedMarket],
+/// so that an [crate::AppraisalChain] asks the next provider.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AppraisalOptions {
+    /// Market hub the prices are taken from
+    pub hub:        MarketHub,
+    /// Determines how the price of an item is calculated
+    pub pricing:    Pricing,
+    /// Share of the total volume that is used for [Pricing::Top5], between
+    /// 0 and 1, only supported by [crate::LocalMarket]
+    pub percentile: Option<f32>,
+    /// Determines if the provider should store the appraisal for sharing
+    pub persist:    bool,
+}
+
+impl Default for AppraisalOptions {
+    fn default() -> Self {
+        Self {
+            hub:        MarketHub::JITA,
+            pricing:    Pricing::Top5,
+            percentile: None,
+            persist:    false,
+        }
+    }
+}
+
+/// Determines how the price of an item is calculated
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pricing {
+    /// Price of the best order, what the items are worth when sold or
+    /// bought right now
+    Immediate,
+    /// Average price of the best orders that make up 5% of the volume
+    Top5,
+}
+
+impl Pricing {
+    /// Name of the pricing, the same name that is used by serde.
+    ///
+    /// # Returns
+    ///
+    /// `immediate` or `top5`
+    ///
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Immediate => "immediate",
+            Self::Top5      => "top5",
+        }
+    }
+
+    /// Parses a pricing.
+    ///
+    /// # Params
+    ///
+    /// * `pricing` -> Name of the pricing, `immediate` or `top5`
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the pricing is not known
+    /// - Parsed pricing
+    ///
+    pub fn parse(pricing: &str) -> Option<Self> {
+        match pricing.trim().to_lowercase().as_str() {
+            "immediate" => Some(Self::Immediate),
+            "top5"      => Some(Self::Top5),
+            _           => None,
+        }
+    }
+}
+
+/// Market hub the prices are taken from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MarketHub {
+    /// Region the hub is located in
+    pub region_id:   RegionId,
+    /// Station or structure of the hub
+    pub location_id: LocationId,
+}
+
+impl MarketHub {
+    /// Jita IV - Moon 4 - Caldari Navy Assembly Plant
+    pub const JITA: MarketHub = MarketHub::new(RegionId(10_000_002), LocationId(60_003_760));
+    /// Amarr VIII (Oris) - Emperor Family Academy
+    pub const AMARR: MarketHub = MarketHub::new(RegionId(10_000_043), LocationId(60_008_494));
+
+    /// Creates a new hub, for example for a private structure.
+    ///
+    /// Only [crate::LocalMarket] supports hubs other than Jita and Amarr,
+    /// their orders must be collected by the collector.
+    ///
+    /// # Params
+    ///
+    /// * `region_id`   -> Region the hub is located in
+    /// * `location_id` -> Station or structure of the hub
+    ///
+    /// # Returns
+    ///
+    /// New hub
+    ///
+    pub const fn new(region_id: RegionId, location_id: LocationId) -> Self {
+        Self {
+            region_id,
+            location_id,
+        }
+    }
+
+    /// Parses a hub.
+    ///
+    /// Accepted are the names `jita` and `amarr` and the format
+    /// `region_id:location_id`.
+    ///
+    /// # Params
+    ///
+    /// * `hub` -> Hub to parse
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the hub could not be parsed
+    /// - Parsed hub
+    ///
+    pub fn parse(hub: &str) -> Option<Self> {
+        match hub.trim().to_lowercase().as_str() {
+            "jita"  => Some(Self::JITA),
+            "amarr" => Some(Self::AMARR),
+            x       => {
+                let (region_id, location_id) = x.split_once(':')?;
+                Some(Self::new(
+                    region_id.trim().parse::<RegionId>().ok()?,
+                    location_id.trim().parse::<LocationId>().ok()?,
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod options_tests {
+    use super::*;
+
+    #[test]
+    fn hubs_are_parsed() {
+        assert_eq!(MarketHub::parse("Jita"), Some(MarketHub::JITA));
+        assert_eq!(MarketHub::parse("amarr"), Some(MarketHub::AMARR));
+        assert_eq!(
+            MarketHub::parse("10000002:1035466617946"),
+            Some(MarketHub::new(RegionId(10_000_002), LocationId(1_035_466_617_946)))
+        );
+        assert_eq!(MarketHub::parse("dodixie"), None);
+    }
+
+    #[test]
+    fn pricing_is_parsed() {
+        for pricing in [Pricing::Immediate, Pricing::Top5] {
+            assert_eq!(Pricing::parse(pricing.as_str()), Some(pricing));
+        }
+        assert_eq!(Pricing::parse("Top5"), Some(Pricing::Top5));
+        assert_eq!(Pricing::parse("median"), None);
+    }
+}
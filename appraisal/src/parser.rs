@@ -0,0 +1,473 @@
+//! Parses text that is copied from the EVE client into items and resolves
+//! their names to type ids.
+//!
+//! Supported formats, every line is parsed on its own so that formats can
+//! be mixed:
+//!
+//! - Inventory window, contract view, survey scanner and multibuy copies,
+//!   tab separated with the name in the first and the quantity in the
+//!   second column
+//! - D-Scan, tab separated with the item id in the first and the type name
+//!   in the third column
+//! - EFT fittings, `[Hull, Fitting name]` followed by modules, where loaded
+//!   charges are ignored, and `Drone x5` for drones and cargo
+//! - Plain lists like `Tritanium 1000`, `Tritanium x1000` or `1000x Tritanium`
+//!
+//! # Usage
+//!
+//! ``` rust,ignore
+//! use appraisal::*;
+//!
+//! let resolved = parser::resolve(&pool, "Tritanium\t1,000\tMineral").await?;
+//! let appraisal = AppraisalChain::init()?
+//!     .create(&AppraisalOptions::default(), resolved.entries)
+//!     .await?;
+//! ```
+
+use caph_connector::TypeId;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+use crate::{AppraisalEntry, Error};
+
+/// Single item that was found in the text
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ParsedItem {
+    /// Name of the item as it was written in the text
+    pub name:     String,
+    /// Summed up quantity of all lines of the item
+    pub quantity: u64,
+}
+
+/// Result of parsing a text
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ParsedText {
+    /// Items in the order they first appeared
+    pub items:    Vec<ParsedItem>,
+    /// Lines that are not in a known format
+    pub unparsed: Vec<String>,
+}
+
+/// Result of parsing a text and resolving the names of its items
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ResolvedText {
+    /// Items with a known name, ready to be appraised
+    pub entries:  Vec<AppraisalEntry>,
+    /// Lines that are not in a known format and names that are not known
+    pub unparsed: Vec<String>,
+}
+
+/// Outcome of parsing a single line
+#[derive(Debug, PartialEq, Eq)]
+enum Line {
+    /// Line contains an item and its quantity
+    Item(String, u64),
+    /// Line does not contain an item, for example an empty EFT slot
+    Skip,
+    /// Line is not in a known format
+    Unparsed,
+}
+
+/// Parses the given text.
+///
+/// Items that appear multiple times are summed up, names are compared case
+/// insensitive.
+///
+/// # Params
+///
+/// * `text` -> Text copied from the EVE client
+///
+/// # Returns
+///
+/// All found items and the lines that could not be parsed
+///
+pub fn parse(text: &str) -> ParsedText {
+    let mut parsed = ParsedText::default();
+
+    for line in text.lines() {
+        match parse_line(line) {
+            Line::Item(name, quantity) => {
+                let existing = parsed
+                    .items
+                    .iter_mut()
+                    .find(|x| x.name.eq_ignore_ascii_case(&name));
+
+                if let Some(x) = existing {
+                    x.quantity += quantity;
+                } else {
+                    parsed.items.push(ParsedItem { name, quantity });
+                }
+            }
+            Line::Skip     => continue,
+            Line::Unparsed => parsed.unparsed.push(line.trim().into()),
+        }
+    }
+
+    parsed
+}
+
+/// Parses the given text and resolves the names with the `items` table of
+/// the SDE.
+///
+/// # Params
+///
+/// * `pool` -> Connection to the database containing the `items` table
+/// * `text` -> Text copied from the EVE client
+///
+/// # Errors
+///
+/// If the database is not available
+///
+/// # Returns
+///
+/// Entries that can be appraised and everything that could not be parsed
+/// or resolved
+///
+pub async fn resolve(
+    pool: &PgPool,
+    text: &str,
+) -> Result<ResolvedText, Error> {
+    let parsed = parse(text);
+
+    let names = parsed
+        .items
+        .iter()
+        .map(|x| x.name.to_lowercase())
+        .collect::<Vec<_>>();
+    let types = sqlx::query_as::<_, (i32, String)>("
+            SELECT type_id, name
+            FROM items
+            WHERE LOWER(name) = ANY($1)
+        ")
+        .bind(&names)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::DatabaseError)?
+        .into_iter()
+        .map(|(type_id, name)| (name.to_lowercase(), (type_id, name)))
+        .collect::<HashMap<_, _>>();
+
+    let mut resolved = ResolvedText {
+        entries:  Vec::new(),
+        unparsed: parsed.unparsed,
+    };
+    for item in parsed.items {
+        match types.get(&item.name.to_lowercase()) {
+            Some((type_id, name)) => resolved.entries.push(
+                AppraisalEntry::new(*type_id, name.clone(), item.quantity)
+            ),
+            None => resolved.unparsed.push(item.name),
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Adds the names from the `items` table of the SDE to the given types.
+///
+/// # Params
+///
+/// * `pool`  -> Connection to the database containing the `items` table
+/// * `types` -> [TypeId]s and their quantity
+///
+/// # Errors
+///
+/// If the database is not available
+///
+/// # Returns
+///
+/// Entries that can be appraised, unknown types are left out
+///
+pub async fn entries(
+    pool:  &PgPool,
+    types: Vec<(TypeId, u64)>,
+) -> Result<Vec<AppraisalEntry>, Error> {
+    let type_ids = types
+        .iter()
+        .map(|(type_id, _)| **type_id)
+        .collect::<Vec<_>>();
+    let names = sqlx::query_as::<_, (i32, String)>("
+            SELECT type_id, name
+            FROM items
+            WHERE type_id = ANY($1)
+        ")
+        .bind(&type_ids)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::DatabaseError)?
+        .into_iter()
+        .map(|(type_id, name)| (TypeId::from(type_id), name))
+        .collect::<HashMap<_, _>>();
+
+    let mut entries: Vec<AppraisalEntry> = Vec::new();
+    for (type_id, quantity) in types {
+        if let Some(x) = entries.iter_mut().find(|x| x.type_id == type_id) {
+            x.quantity += quantity;
+        } else if let Some(name) = names.get(&type_id) {
+            entries.push(AppraisalEntry::new(type_id, name.clone(), quantity));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Parses a single line of any supported format.
+///
+/// # Params
+///
+/// * `line` -> Line to parse
+///
+/// # Returns
+///
+/// Found item, or whether the line is skipped or unknown
+///
+fn parse_line(line: &str) -> Line {
+    let line = line.trim();
+    if line.is_empty() {
+        return Line::Skip;
+    }
+
+    // EFT header `[Hull, Fitting name]` and empty slots `[Empty High slot]`
+    if line.starts_with('[') && line.ends_with(']') {
+        let inner = &line[1..line.len() - 1];
+        if inner.to_lowercase().starts_with("empty ") {
+            return Line::Skip;
+        }
+        return inner
+            .split_once(',')
+            .map_or(Line::Unparsed, |(hull, _)| item(hull, 1));
+    }
+
+    if line.contains('\t') {
+        let columns = line
+            .split('\t')
+            .map(str::trim)
+            .collect::<Vec<_>>();
+
+        // Total of a multibuy copy
+        if columns[0].eq_ignore_ascii_case("total:") {
+            return Line::Skip;
+        }
+
+        // D-Scan: item id, name, type name, distance
+        if columns.len() >= 3 && columns[0].parse::<u64>().is_ok() {
+            return item(columns[2], 1);
+        }
+
+        // Inventory, contract, survey scanner and multibuy: name, quantity,
+        // the quantity is empty for single assembled items
+        return match columns.get(1) {
+            Some(x) if !x.is_empty() => quantity(x)
+                .map_or(Line::Unparsed, |x| item(columns[0], x)),
+            _ => item(columns[0], 1),
+        };
+    }
+
+    // EFT modules `Module, Charge` and offline modules `Module /OFFLINE`
+    let line = line
+        .strip_suffix("/OFFLINE")
+        .unwrap_or(line)
+        .trim_end();
+    let line = line
+        .split_once(", ")
+        .map_or(line, |(module, _)| module);
+
+    // `Tritanium 1000`, `Tritanium x1000` or `Tritanium x 1000`
+    if let Some((name, amount)) = line.rsplit_once(' ') {
+        let amount = amount
+            .strip_prefix(['x', 'X'])
+            .unwrap_or(amount);
+        if let Some(amount) = quantity(amount) {
+            let name = name
+                .strip_suffix(" x")
+                .or_else(|| name.strip_suffix(" X"))
+                .unwrap_or(name);
+            return item(name, amount);
+        }
+    }
+
+    // `1000 Tritanium`, `1000x Tritanium` or `1000 x Tritanium`
+    if let Some((amount, name)) = line.split_once(' ') {
+        let amount = amount
+            .strip_suffix(['x', 'X'])
+            .unwrap_or(amount);
+        if let Some(amount) = quantity(amount) {
+            let name = name
+                .strip_prefix("x ")
+                .or_else(|| name.strip_prefix("X "))
+                .unwrap_or(name);
+            return item(name, amount);
+        }
+    }
+
+    item(line, 1)
+}
+
+/// Creates an item, if the name looks like a name.
+///
+/// # Params
+///
+/// * `name`     -> Name of the item
+/// * `quantity` -> Amount of the item
+///
+/// # Returns
+///
+/// - [Line::Unparsed] if the name does not contain a single letter
+/// - [Line::Item] with the trimmed name
+///
+fn item(name: &str, quantity: u64) -> Line {
+    let name = name.trim();
+    if name.chars().any(char::is_alphabetic) {
+        Line::Item(name.into(), quantity)
+    } else {
+        Line::Unparsed
+    }
+}
+
+/// Parses a quantity, thousands separators are ignored.
+///
+/// A separator only counts as thousands separator if it is followed by
+/// exactly three digits, so that decimals like `1.5` are not read as `15`.
+///
+/// # Params
+///
+/// * `quantity` -> Quantity to parse, for example `1,000` or `1.000`
+///
+/// # Returns
+///
+/// - [None] if the quantity is not a whole number
+/// - Parsed quantity
+///
+fn quantity(quantity: &str) -> Option<u64> {
+    let groups = quantity
+        .split([',', '.', '\'', ' '])
+        .collect::<Vec<_>>();
+    let (first, rest) = groups.split_first()?;
+
+    let digits = groups
+        .iter()
+        .all(|x| !x.is_empty() && x.chars().all(|x| x.is_ascii_digit()));
+    let grouped = rest.is_empty() ||
+        (first.len() <= 3 && rest.iter().all(|x| x.len() == 3));
+    if !digits || !grouped {
+        return None;
+    }
+    groups.concat().parse::<u64>().ok()
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use super::*;
+
+    /// Shorthand for creating an expected item
+    fn parsed(name: &str, quantity: u64) -> ParsedItem {
+        ParsedItem {
+            name: name.into(),
+            quantity,
+        }
+    }
+
+    #[test]
+    fn inventory_and_contract_copies() {
+        let text = "Tritanium\t1,000\tMineral\tMaterial\t\t10 m3\t5,000.00 ISK\n\
+                    Rifter\t\tFrigate\tShip\t\t27,289 m3\n\
+                    tritanium\t500\tMineral\tMaterial\t";
+        assert_eq!(parse(text).items, vec![
+            parsed("Tritanium", 1500),
+            parsed("Rifter", 1),
+        ]);
+    }
+
+    #[test]
+    fn multibuy_and_plain_lists() {
+        let text = "Tritanium 1000\n\
+                    Pyerite x200\n\
+                    Mexallon x 30\n\
+                    5x Isogen\n\
+                    2 x Nocxium\n\
+                    7 Zydrine\n\
+                    Capital Construction Parts\n\
+                    Megacyte\t10\t1,200.00\t12,000.00\n\
+                    Total:\t\t\t12,000.00";
+        let parsed_text = parse(text);
+        assert_eq!(parsed_text.items, vec![
+            parsed("Tritanium", 1000),
+            parsed("Pyerite", 200),
+            parsed("Mexallon", 30),
+            parsed("Isogen", 5),
+            parsed("Nocxium", 2),
+            parsed("Zydrine", 7),
+            parsed("Capital Construction Parts", 1),
+            parsed("Megacyte", 10),
+        ]);
+        assert!(parsed_text.unparsed.is_empty());
+    }
+
+    #[test]
+    fn eft_fittings() {
+        let text = "[Rifter, Tackle]\n\
+                    Damage Control I\n\
+                    [Empty Low slot]\n\
+                    \n\
+                    200mm AutoCannon I, EMP S\n\
+                    200mm AutoCannon I, EMP S\n\
+                    Warp Scrambler I /OFFLINE\n\
+                    \n\
+                    Hobgoblin I x5\n\
+                    EMP S x1000";
+        assert_eq!(parse(text).items, vec![
+            parsed("Rifter", 1),
+            parsed("Damage Control I", 1),
+            parsed("200mm AutoCannon I", 2),
+            parsed("Warp Scrambler I", 1),
+            parsed("Hobgoblin I", 5),
+            parsed("EMP S", 1000),
+        ]);
+    }
+
+    #[test]
+    fn dscan_and_survey_scanner() {
+        let dscan = "1035466617946\tJita Keepstar\tKeepstar\t-\n\
+                     1044235321\tSomeone's Rifter\tRifter\t12 km\n\
+                     1044235322\tSomeone else's Rifter\tRifter\t1,200 km";
+        assert_eq!(parse(dscan).items, vec![
+            parsed("Keepstar", 1),
+            parsed("Rifter", 2),
+        ]);
+
+        let survey = "Concentrated Veldspar\t12,345\t1,234 m3\t21 km\n\
+                      Dense Veldspar\t8,000\t800 m3\t14 km";
+        assert_eq!(parse(survey).items, vec![
+            parsed("Concentrated Veldspar", 12345),
+            parsed("Dense Veldspar", 8000),
+        ]);
+    }
+
+    #[test]
+    fn decimals_are_no_quantities() {
+        assert_eq!(quantity("1,000"), Some(1000));
+        assert_eq!(quantity("1.000.000"), Some(1_000_000));
+        assert_eq!(quantity("1'000"), Some(1000));
+        assert_eq!(quantity("1000"), Some(1000));
+        assert_eq!(quantity("1.5"), None);
+        assert_eq!(quantity("1,0000"), None);
+        assert_eq!(quantity("1000,000"), None);
+        assert_eq!(quantity(""), None);
+
+        let parsed_text = parse("Tritanium\t1.5\nPyerite 2.5");
+        assert_eq!(parsed_text.items, vec![parsed("Pyerite 2.5", 1)]);
+        assert_eq!(parsed_text.unparsed, vec!["Tritanium\t1.5".to_string()]);
+    }
+
+    #[test]
+    fn unknown_lines_are_reported() {
+        let parsed_text = parse("Tritanium 10\n12345\n[Rifter]\n---");
+        assert_eq!(parsed_text.items, vec![parsed("Tritanium", 10)]);
+        assert_eq!(parsed_text.unparsed, vec![
+            "12345".to_string(),
+            "[Rifter]".to_string(),
+            "---".to_string(),
+        ]);
+    }
+}
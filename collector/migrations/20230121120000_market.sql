@@ -0,0 +1,39 @@
+-- Latest snapshot of the orders in every configured market hub
+CREATE TABLE market_orders(
+  time          TIMESTAMPTZ      NOT NULL,
+
+  region_id     INTEGER          NOT NULL,
+  location_id   BIGINT           NOT NULL,
+  type_id       INTEGER          NOT NULL,
+
+  is_buy_order  BOOLEAN          NOT NULL,
+  price         DOUBLE PRECISION NOT NULL,
+  volume_remain INTEGER          NOT NULL
+);
+
+CREATE INDEX market_orders_location_type ON market_orders (location_id, type_id);
+
+-- Daily history of every type that is traded in a hub
+CREATE TABLE market_history(
+  region_id   INTEGER          NOT NULL,
+  type_id     INTEGER          NOT NULL,
+  date        DATE             NOT NULL,
+
+  average     DOUBLE PRECISION NOT NULL,
+  highest     DOUBLE PRECISION NOT NULL,
+  lowest      DOUBLE PRECISION NOT NULL,
+  volume      BIGINT           NOT NULL,
+  order_count BIGINT           NOT NULL,
+
+  PRIMARY KEY (region_id, type_id, date)
+);
+
+-- Names of all types that are traded in a hub
+CREATE TABLE market_types(
+  type_id INTEGER NOT NULL,
+  name    VARCHAR NOT NULL,
+
+  PRIMARY KEY (type_id)
+);
+
+CREATE INDEX market_types_name ON market_types (LOWER(name));
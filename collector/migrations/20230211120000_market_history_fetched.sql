@@ -0,0 +1,9 @@
+-- Last time the history of a type was fetched, types are only fetched again
+-- after the EVE API updated the history
+CREATE TABLE market_history_fetched(
+  region_id INTEGER     NOT NULL,
+  type_id   INTEGER     NOT NULL,
+  fetched   TIMESTAMPTZ NOT NULL,
+
+  PRIMARY KEY (region_id, type_id)
+);
@@ -0,0 +1,8 @@
+-- The EVE-SSO rotates the refresh token configured in MARKET_REFRESH_TOKEN,
+-- the latest token is stored here so that it survives a restart
+CREATE TABLE market_refresh_token(
+  configured VARCHAR NOT NULL,
+  current    VARCHAR NOT NULL,
+
+  PRIMARY KEY (configured)
+);
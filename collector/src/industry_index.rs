@@ -0,0 +1,197 @@
+use caph_connector::{ConnectError, EveClient, EveIndustryService, IndustryActivity, SystemId};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::time::Duration;
+
+/// ENV variable for limiting the collected systems
+const INDUSTRY_SYSTEMS: &str = "INDUSTRY_SYSTEMS";
+/// The EVE API caches the cost indices for one hour, used if the response
+/// has no `Expires` header or the request failed
+const INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Minimum time between two snapshots
+const MIN_INTERVAL: Duration = Duration::from_secs(60);
+/// Time waited after the `Expires` header, so that the EVE API already
+/// serves the new cost indices
+const EXPIRES_MARGIN: Duration = Duration::from_secs(10);
+
+/// Periodically stores a snapshot of the industry cost indices in the table
+/// `industry_index`.
+pub struct IndustryIndex {
+    /// Connection to postgres, the snapshots are stored in it
+    pool:    PgPool,
+    /// Client for fetching the cost indices
+    client:  EveClient,
+    /// If set, only the given systems are stored
+    systems: Option<Vec<SystemId>>,
+}
+
+impl IndustryIndex {
+    /// Creates a new collector.
+    ///
+    /// The optional ENV `INDUSTRY_SYSTEMS` takes a comma separated list of
+    /// [SystemId]s. If it is set, only those systems are stored, otherwise
+    /// all systems are stored.
+    ///
+    /// # Params
+    ///
+    /// * `pool`   > Connection to postgres
+    /// * `client` > Client for communicating with the EVE API
+    ///
+    /// # Returns
+    ///
+    /// New collector instance
+    ///
+    pub fn new(
+        pool:   PgPool,
+        client: EveClient,
+    ) -> Self {
+        let systems = std::env::var(INDUSTRY_SYSTEMS)
+            .ok()
+            .map(|x| {
+                x.split(',')
+                    .filter_map(|x| x.trim().parse::<i64>().ok())
+                    .map(SystemId::from)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|x| !x.is_empty());
+
+        Self {
+            pool,
+            client,
+            systems,
+        }
+    }
+
+    /// Collects the cost indices every time the EVE API updates them,
+    /// forever.
+    ///
+    /// The next snapshot is taken when the `Expires` header of the last
+    /// response is reached. Errors are logged and the next snapshot is
+    /// taken after [INTERVAL].
+    ///
+    pub async fn run(self) {
+        loop {
+            let expires = match self.collect().await {
+                Ok((x, expires)) => {
+                    tracing::info!({ systems = x }, "Stored industry index");
+                    expires
+                }
+                Err(IndustryIndexError::FetchSystems(e)) => {
+                    tracing::error!({ error = %e }, "Error fetching industry systems");
+                    None
+                }
+                Err(IndustryIndexError::InsertIndex(e)) => {
+                    tracing::error!({ error = ?e }, "Error inserting industry index");
+                    None
+                }
+            };
+
+            tokio::time::sleep(Self::delay(expires)).await;
+        }
+    }
+
+    /// Calculates the time until the next snapshot.
+    ///
+    /// # Params
+    ///
+    /// * `expires` > `Expires` header of the last response
+    ///
+    /// # Returns
+    ///
+    /// Time until the EVE API serves new cost indices, [INTERVAL] if it is
+    /// not known
+    ///
+    fn delay(expires: Option<DateTime<Utc>>) -> Duration {
+        expires
+            .and_then(|x| (x - Utc::now()).to_std().ok())
+            .map(|x| (x + EXPIRES_MARGIN).clamp(MIN_INTERVAL, INTERVAL))
+            .unwrap_or(INTERVAL)
+    }
+
+    /// Fetches the current cost indices and stores them.
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    /// - If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// Number of stored systems and the `Expires` header of the response
+    ///
+    async fn collect(&self) -> Result<(usize, Option<DateTime<Utc>>), IndustryIndexError> {
+        let (systems, expires) = EveIndustryService
+            .systems_expires(&self.client)
+            .await
+            .map_err(IndustryIndexError::FetchSystems)?;
+
+        let mut system_ids        = Vec::new();
+        let mut manufacturing     = Vec::new();
+        let mut copying           = Vec::new();
+        let mut invention         = Vec::new();
+        let mut reaction          = Vec::new();
+        let mut research_time     = Vec::new();
+        let mut research_material = Vec::new();
+
+        for system in systems {
+            if let Some(ref x) = self.systems {
+                if !x.contains(&system.solar_system_id) {
+                    continue;
+                }
+            }
+
+            system_ids.push(*system.solar_system_id as i32);
+            manufacturing.push(system.cost_index(IndustryActivity::Manufacturing));
+            copying.push(system.cost_index(IndustryActivity::Copying));
+            invention.push(system.cost_index(IndustryActivity::Invention));
+            reaction.push(system.cost_index(IndustryActivity::Reactions));
+            research_time.push(system.cost_index(IndustryActivity::TimeEfficiencyResearch));
+            research_material.push(system.cost_index(IndustryActivity::MaterialEfficiencyResearch));
+        }
+
+        sqlx::query("
+                INSERT INTO industry_index
+                (
+                    time,
+                    system_id,
+                    manufacturing,
+                    copying,
+                    invention,
+                    reaction,
+                    research_time,
+                    research_material
+                )
+                SELECT $1, * FROM UNNEST(
+                    $2::INTEGER[],
+                    $3::REAL[],
+                    $4::REAL[],
+                    $5::REAL[],
+                    $6::REAL[],
+                    $7::REAL[],
+                    $8::REAL[]
+                )
+            ")
+            .bind(Utc::now())
+            .bind(&system_ids)
+            .bind(&manufacturing)
+            .bind(&copying)
+            .bind(&invention)
+            .bind(&reaction)
+            .bind(&research_time)
+            .bind(&research_material)
+            .execute(&self.pool)
+            .await
+            .map_err(IndustryIndexError::InsertIndex)?;
+
+        Ok((system_ids.len(), expires))
+    }
+}
+
+/// Errors that can occur while collecting the industry index
+#[derive(Debug)]
+pub enum IndustryIndexError {
+    /// The cost indices could not be fetched
+    FetchSystems(ConnectError),
+    /// The cost indices could not be stored
+    InsertIndex(sqlx::Error),
+}
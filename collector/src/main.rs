@@ -1,4 +1,13 @@
+mod industry_index;
+mod market;
+
+use caph_connector::EveClient;
 use sqlx::postgres::PgPoolOptions;
+use tracing::Level;
+use tracing_subscriber::EnvFilter;
+
+use self::industry_index::IndustryIndex;
+use self::market::Market;
 
 /// ENV variable for the database URL
 const PG_ADDR: &str = "DATABASE_URL";
@@ -7,6 +16,15 @@ const PG_ADDR: &str = "DATABASE_URL";
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenvy::dotenv().ok();
 
+    if std::env::var("GIT_HEAD_SHORT").is_ok() {
+        tracing_subscriber::fmt().with_max_level(Level::WARN).init();
+    } else {
+        tracing_subscriber::fmt()
+            .pretty()
+            .with_env_filter(EnvFilter::from_default_env())
+            .init();
+    }
+
     let pg_addr = std::env::var(PG_ADDR).expect("Expected that a DATABASE_URL ENV is set");
     let pool = PgPoolOptions::new()
         .max_connections(10)
@@ -14,5 +32,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .await?;
     sqlx::migrate!().run(&pool).await?;
 
+    let client = EveClient::new().expect("Expected that a EVE_USER_AGENT ENV is set");
+    let industry_index = IndustryIndex::new(pool.clone(), client.clone());
+    let market = Market::new(pool.clone(), client).await;
+
+    tracing::info!("Starting collector");
+    tokio::select! {
+        _ = industry_index.run() => {},
+        _ = market.run() => {},
+        _ = tokio::signal::ctrl_c() => {
+            tracing::info!("Stopping collector");
+        }
+    }
+
     Ok(())
 }
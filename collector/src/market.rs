@@ -0,0 +1,694 @@
+use async_trait::async_trait;
+use caph_connector::{ConnectError, EveAuthClient, EveClient, EveMarketService, EveUniverseService, LocationId, MarketHistory, MarketOrder, OrderType, RegionId, TokenStore, TypeId};
+use chrono::{DateTime, Duration as ChronoDuration, NaiveTime, Utc};
+use futures::stream::{self, StreamExt};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// ENV variable for the collected market hubs
+const MARKET_HUBS: &str = "MARKET_HUBS";
+/// ENV variable for the refresh token that is used for structure markets
+const MARKET_REFRESH_TOKEN: &str = "MARKET_REFRESH_TOKEN";
+/// Jita 4-4, collected if no hubs are configured
+const DEFAULT_HUB: &str = "10000002:60003760";
+/// The EVE API caches market orders for five minutes, there is no need to
+/// be that precise for appraisals
+const INTERVAL: Duration = Duration::from_secs(30 * 60);
+/// Number of days of market history that are stored
+const HISTORY_DAYS: i64 = 30;
+/// Number of history requests that are send at the same time
+const HISTORY_CONCURRENCY: usize = 10;
+/// The EVE API updates the history once per day after downtime, the hour and
+/// minute in UTC after which the history of the previous day is available
+const HISTORY_UPDATE: (u32, u32) = (11, 5);
+/// Ids of player owned structures start at this id
+const MIN_STRUCTURE_ID: i64 = 1_000_000_000_000;
+
+/// Single market whose orders are collected
+#[derive(Clone, Copy, Debug)]
+pub struct MarketHub {
+    /// Region the hub is located in
+    region_id:   RegionId,
+    /// Station or structure of the hub
+    location_id: LocationId,
+}
+
+impl MarketHub {
+    /// Parses a hub in the format `region_id:location_id`.
+    ///
+    /// # Params
+    ///
+    /// * `hub` > Hub to parse, for example `10000002:60003760`
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the format is invalid
+    /// - Parsed hub
+    ///
+    fn parse(hub: &str) -> Option<Self> {
+        let (region_id, location_id) = hub.trim().split_once(':')?;
+        Some(Self {
+            region_id:   region_id.trim().parse::<RegionId>().ok()?,
+            location_id: location_id.trim().parse::<LocationId>().ok()?,
+        })
+    }
+
+    /// Checks if the hub is a player owned structure.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the orders are only available with an authenticated client
+    ///
+    fn is_structure(&self) -> bool {
+        *self.location_id >= MIN_STRUCTURE_ID
+    }
+}
+
+/// Keeps the refresh token of the structure markets in the table
+/// `market_refresh_token` up to date, when the EVE-SSO rotates it.
+#[derive(Clone, Debug)]
+struct RefreshTokenStore {
+    /// Connection to postgres
+    pool:       PgPool,
+    /// Refresh token from the ENV `MARKET_REFRESH_TOKEN`
+    configured: String,
+}
+
+impl RefreshTokenStore {
+    /// Gets the latest refresh token for the configured one.
+    ///
+    /// Errors are logged and the configured token is used.
+    ///
+    /// # Returns
+    ///
+    /// The stored token, or the configured one if there is none
+    ///
+    async fn current(&self) -> String {
+        let result = sqlx::query_as::<_, (String,)>("
+                SELECT current
+                FROM market_refresh_token
+                WHERE configured = $1
+            ")
+            .bind(&self.configured)
+            .fetch_optional(&self.pool)
+            .await;
+
+        match result {
+            Ok(Some((x,))) => x,
+            Ok(None)       => self.configured.clone(),
+            Err(e)         => {
+                tracing::error!({ error = %e }, "Error loading refresh token for structure markets");
+                self.configured.clone()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl TokenStore for RefreshTokenStore {
+    async fn store(&self, _: &str, refresh_token: &str) {
+        tracing::warn!("The EVE-SSO rotated the refresh token for structure markets");
+
+        let result = sqlx::query("
+                INSERT INTO market_refresh_token (configured, current)
+                VALUES ($1, $2)
+                ON CONFLICT (configured)
+                DO UPDATE SET current = EXCLUDED.current
+            ")
+            .bind(&self.configured)
+            .bind(refresh_token)
+            .execute(&self.pool)
+            .await;
+
+        if let Err(e) = result {
+            tracing::error!(
+                { error = %e },
+                "Error storing refresh token for structure markets, the ENV MARKET_REFRESH_TOKEN must be updated before the next restart",
+            );
+        }
+    }
+}
+
+/// Periodically stores a snapshot of the orders of all configured hubs in
+/// the table `market_orders`, their daily history in `market_history` and
+/// the names of all traded types in `market_types`.
+pub struct Market {
+    /// Connection to postgres
+    pool:        PgPool,
+    /// Client for communicating with the EVE API
+    client:      EveClient,
+    /// Client for reading structure markets, only set if the ENV
+    /// `MARKET_REFRESH_TOKEN` is set
+    auth_client: Option<EveAuthClient>,
+    /// Hubs whose orders are collected
+    hubs:        Vec<MarketHub>,
+}
+
+impl Market {
+    /// Creates a new collector.
+    ///
+    /// The optional ENV `MARKET_HUBS` takes a comma separated list of hubs
+    /// in the format `region_id:location_id`, by default Jita 4-4 is
+    /// collected, also if the ENV is empty or contains no valid hub.
+    /// Structures are only collected if the ENV
+    /// `MARKET_REFRESH_TOKEN` contains a refresh token with the scope
+    /// `esi-markets.structure_markets.v1`, an empty ENV counts as not set.
+    /// When the EVE-SSO rotates the token, the new token is stored in the
+    /// table `market_refresh_token` and used instead of the ENV, as long as
+    /// the ENV is not changed.
+    ///
+    /// # Params
+    ///
+    /// * `pool`   > Connection to postgres
+    /// * `client` > Client for communicating with the EVE API
+    ///
+    /// # Returns
+    ///
+    /// New collector instance
+    ///
+    pub async fn new(
+        pool:   PgPool,
+        client: EveClient,
+    ) -> Self {
+        let hubs = std::env::var(MARKET_HUBS)
+            .ok()
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| {
+                let hubs = x
+                    .split(',')
+                    .filter_map(MarketHub::parse)
+                    .collect::<Vec<_>>();
+                if hubs.is_empty() {
+                    tracing::warn!({ hubs = x }, "No valid market hub configured, using the default hub");
+                }
+                hubs
+            })
+            .filter(|x| !x.is_empty())
+            .unwrap_or_else(|| {
+                MarketHub::parse(DEFAULT_HUB)
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            });
+
+        let configured = std::env::var(MARKET_REFRESH_TOKEN)
+            .ok()
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty());
+        let auth_client = if let Some(configured) = configured {
+            let store = RefreshTokenStore {
+                pool:       pool.clone(),
+                configured: configured.clone(),
+            };
+            match EveAuthClient::new(store.current().await) {
+                Ok(x)  => Some(x.with_token_store(store)),
+                Err(e) => {
+                    tracing::error!({ error = %e }, "Error creating client for structure markets");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        Self {
+            pool,
+            client,
+            auth_client,
+            hubs,
+        }
+    }
+
+    /// Collects the orders of all hubs once per interval, forever.
+    ///
+    /// The history of a type is only fetched if the EVE API updated it since
+    /// the last fetch. Errors are logged and the hub is collected again in
+    /// the next interval.
+    ///
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(INTERVAL);
+        loop {
+            interval.tick().await;
+
+            for hub in self.hubs.iter() {
+                match self.collect(*hub).await {
+                    Ok(x)  => tracing::info!({ location_id = *hub.location_id, orders = x }, "Stored market orders"),
+                    Err(e) => e.log(*hub),
+                }
+            }
+        }
+    }
+
+    /// Fetches the current orders of a hub and stores them, together with
+    /// the names and history of all traded types.
+    ///
+    /// # Params
+    ///
+    /// * `hub` > Hub to collect
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    /// - If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// Number of stored orders
+    ///
+    async fn collect(
+        &self,
+        hub: MarketHub,
+    ) -> Result<usize, MarketError> {
+        let orders = self.orders(hub).await?;
+        let count = orders.len();
+
+        let type_ids = orders
+            .iter()
+            .map(|x| x.type_id)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        self.insert_orders(hub, orders).await?;
+        self.insert_names(&type_ids).await?;
+        self.insert_history(hub.region_id, type_ids).await?;
+
+        Ok(count)
+    }
+
+    /// Fetches all orders that are located in the hub.
+    ///
+    /// # Params
+    ///
+    /// * `hub` > Hub to fetch the orders for
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    /// - If the hub is a structure and there is no authenticated client
+    ///
+    /// # Returns
+    ///
+    /// All orders of the hub
+    ///
+    async fn orders(
+        &self,
+        hub: MarketHub,
+    ) -> Result<Vec<MarketOrder>, MarketError> {
+        if hub.is_structure() {
+            let client = self.auth_client
+                .as_ref()
+                .ok_or(MarketError::MissingRefreshToken)?;
+            return EveMarketService
+                .structure_orders(client, hub.location_id)
+                .await
+                .map_err(MarketError::FetchOrders);
+        }
+
+        let orders = EveMarketService
+            .orders(&self.client, hub.region_id, OrderType::All, None)
+            .await
+            .map_err(MarketError::FetchOrders)?
+            .into_iter()
+            .filter(|x| x.location_id == hub.location_id)
+            .collect::<Vec<_>>();
+        Ok(orders)
+    }
+
+    /// Replaces the stored orders of a hub.
+    ///
+    /// # Params
+    ///
+    /// * `hub`    > Hub the orders belong to
+    /// * `orders` > New orders of the hub
+    ///
+    /// # Errors
+    ///
+    /// If the database is not available
+    ///
+    async fn insert_orders(
+        &self,
+        hub:    MarketHub,
+        orders: Vec<MarketOrder>,
+    ) -> Result<(), MarketError> {
+        let mut type_ids      = Vec::new();
+        let mut is_buy_orders = Vec::new();
+        let mut prices        = Vec::new();
+        let mut volumes       = Vec::new();
+
+        for order in orders {
+            type_ids.push(*order.type_id);
+            is_buy_orders.push(order.is_buy_order);
+            prices.push(order.price);
+            volumes.push(order.volume_remain);
+        }
+
+        let mut transaction = self.pool
+            .begin()
+            .await
+            .map_err(MarketError::InsertOrders)?;
+
+        sqlx::query("
+                DELETE FROM market_orders
+                WHERE location_id = $1
+            ")
+            .bind(*hub.location_id)
+            .execute(&mut transaction)
+            .await
+            .map_err(MarketError::InsertOrders)?;
+
+        sqlx::query("
+                INSERT INTO market_orders
+                (
+                    time,
+                    region_id,
+                    location_id,
+                    type_id,
+                    is_buy_order,
+                    price,
+                    volume_remain
+                )
+                SELECT $1, $2, $3, * FROM UNNEST(
+                    $4::INTEGER[],
+                    $5::BOOLEAN[],
+                    $6::DOUBLE PRECISION[],
+                    $7::INTEGER[]
+                )
+            ")
+            .bind(Utc::now())
+            .bind(*hub.region_id)
+            .bind(*hub.location_id)
+            .bind(&type_ids)
+            .bind(&is_buy_orders)
+            .bind(&prices)
+            .bind(&volumes)
+            .execute(&mut transaction)
+            .await
+            .map_err(MarketError::InsertOrders)?;
+
+        transaction
+            .commit()
+            .await
+            .map_err(MarketError::InsertOrders)
+    }
+
+    /// Stores the names of all types that are not known yet.
+    ///
+    /// # Params
+    ///
+    /// * `type_ids` > Types that are traded
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    /// - If the database is not available
+    ///
+    async fn insert_names(
+        &self,
+        type_ids: &[TypeId],
+    ) -> Result<(), MarketError> {
+        let type_ids = type_ids.iter().map(|x| **x).collect::<Vec<_>>();
+        let missing = sqlx::query_scalar::<_, i32>("
+                SELECT type_id
+                FROM UNNEST($1::INTEGER[]) AS type_id
+                WHERE type_id NOT IN (SELECT type_id FROM market_types)
+            ")
+            .bind(&type_ids)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(MarketError::InsertNames)?;
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let (ids, names): (Vec<_>, Vec<_>) = EveUniverseService
+            .names(&self.client, missing)
+            .await
+            .map_err(MarketError::FetchNames)?
+            .into_iter()
+            .map(|x| (x.id, x.name))
+            .unzip();
+
+        sqlx::query("
+                INSERT INTO market_types (type_id, name)
+                SELECT * FROM UNNEST($1::INTEGER[], $2::VARCHAR[])
+                ON CONFLICT (type_id) DO UPDATE SET name = EXCLUDED.name
+            ")
+            .bind(&ids)
+            .bind(&names)
+            .execute(&self.pool)
+            .await
+            .map_err(MarketError::InsertNames)
+            .map(drop)
+    }
+
+    /// Fetches and stores the history of the last [HISTORY_DAYS] days of all
+    /// given types whose history was not fetched since the last update of
+    /// the EVE API.
+    ///
+    /// Types whose history could not be fetched are logged and skipped,
+    /// they are fetched again in the next interval.
+    ///
+    /// # Params
+    ///
+    /// * `region_id` > Region to fetch the history for
+    /// * `type_ids`  > Types to fetch the history for
+    ///
+    /// # Errors
+    ///
+    /// If the database is not available
+    ///
+    async fn insert_history(
+        &self,
+        region_id: RegionId,
+        type_ids:  Vec<TypeId>,
+    ) -> Result<(), MarketError> {
+        let type_ids = self.outdated_history(region_id, type_ids).await?;
+        if type_ids.is_empty() {
+            return Ok(());
+        }
+
+        let since = Utc::now().date_naive() - ChronoDuration::days(HISTORY_DAYS);
+        let fetched = Utc::now();
+
+        let histories = stream::iter(type_ids)
+            .map(|tid| async move {
+                EveMarketService
+                    .history(&self.client, region_id, tid)
+                    .await
+                    .map(|x| (tid, x))
+                    .map_err(|e| (tid, e))
+            })
+            .buffer_unordered(HISTORY_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut fetched_ids  = Vec::new();
+        let mut type_ids     = Vec::new();
+        let mut dates        = Vec::new();
+        let mut averages     = Vec::new();
+        let mut highests     = Vec::new();
+        let mut lowests      = Vec::new();
+        let mut volumes      = Vec::new();
+        let mut order_counts = Vec::new();
+
+        for history in histories {
+            let (tid, entries) = match history {
+                Ok(x)  => x,
+                Err((tid, e)) => {
+                    tracing::warn!({ region_id = *region_id, type_id = *tid, error = %e }, "Error fetching market history, skipping type");
+                    continue;
+                }
+            };
+
+            fetched_ids.push(*tid);
+            for MarketHistory { date, average, highest, lowest, order_count, volume } in entries {
+                if date < since {
+                    continue;
+                }
+
+                type_ids.push(*tid);
+                dates.push(date);
+                averages.push(average);
+                highests.push(highest);
+                lowests.push(lowest);
+                volumes.push(volume);
+                order_counts.push(order_count);
+            }
+        }
+
+        let mut transaction = self.pool
+            .begin()
+            .await
+            .map_err(MarketError::InsertHistory)?;
+
+        sqlx::query("
+                INSERT INTO market_history
+                (
+                    region_id,
+                    type_id,
+                    date,
+                    average,
+                    highest,
+                    lowest,
+                    volume,
+                    order_count
+                )
+                SELECT $1, * FROM UNNEST(
+                    $2::INTEGER[],
+                    $3::DATE[],
+                    $4::DOUBLE PRECISION[],
+                    $5::DOUBLE PRECISION[],
+                    $6::DOUBLE PRECISION[],
+                    $7::BIGINT[],
+                    $8::BIGINT[]
+                )
+                ON CONFLICT (region_id, type_id, date) DO NOTHING
+            ")
+            .bind(*region_id)
+            .bind(&type_ids)
+            .bind(&dates)
+            .bind(&averages)
+            .bind(&highests)
+            .bind(&lowests)
+            .bind(&volumes)
+            .bind(&order_counts)
+            .execute(&mut transaction)
+            .await
+            .map_err(MarketError::InsertHistory)?;
+
+        sqlx::query("
+                INSERT INTO market_history_fetched (region_id, type_id, fetched)
+                SELECT $1, type_id, $3 FROM UNNEST($2::INTEGER[]) AS type_id
+                ON CONFLICT (region_id, type_id) DO UPDATE SET fetched = EXCLUDED.fetched
+            ")
+            .bind(*region_id)
+            .bind(&fetched_ids)
+            .bind(fetched)
+            .execute(&mut transaction)
+            .await
+            .map_err(MarketError::InsertHistory)?;
+
+        transaction
+            .commit()
+            .await
+            .map_err(MarketError::InsertHistory)
+    }
+
+    /// Filters the types whose history was not fetched since the last
+    /// update of the EVE API.
+    ///
+    /// # Params
+    ///
+    /// * `region_id` > Region the history is fetched for
+    /// * `type_ids`  > Types that are traded
+    ///
+    /// # Errors
+    ///
+    /// If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// Types whose history needs to be fetched
+    ///
+    async fn outdated_history(
+        &self,
+        region_id: RegionId,
+        type_ids:  Vec<TypeId>,
+    ) -> Result<Vec<TypeId>, MarketError> {
+        let type_ids = type_ids.into_iter().map(|x| *x).collect::<Vec<_>>();
+        let outdated = sqlx::query_scalar::<_, i32>("
+                SELECT type_id
+                FROM UNNEST($2::INTEGER[]) AS type_id
+                WHERE type_id NOT IN (
+                    SELECT type_id
+                    FROM market_history_fetched
+                    WHERE region_id = $1
+                      AND fetched >= $3
+                )
+            ")
+            .bind(*region_id)
+            .bind(&type_ids)
+            .bind(history_updated(Utc::now()))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(MarketError::InsertHistory)?
+            .into_iter()
+            .map(TypeId::from)
+            .collect::<Vec<_>>();
+        Ok(outdated)
+    }
+}
+
+/// Calculates the last time the EVE API updated the market history.
+///
+/// # Params
+///
+/// * `now` > Current time
+///
+/// # Returns
+///
+/// Time of the last update of the history
+///
+fn history_updated(now: DateTime<Utc>) -> DateTime<Utc> {
+    let (hour, minute) = HISTORY_UPDATE;
+    let time = NaiveTime::from_hms_opt(hour, minute, 0)
+        .unwrap_or_default();
+    let updated = DateTime::<Utc>::from_utc(now.date_naive().and_time(time), Utc);
+
+    if updated > now {
+        updated - ChronoDuration::days(1)
+    } else {
+        updated
+    }
+}
+
+/// Errors that can occur while collecting the market
+#[derive(Debug)]
+pub enum MarketError {
+    /// The hub is a structure, but `MARKET_REFRESH_TOKEN` is not set
+    MissingRefreshToken,
+    /// The orders could not be fetched
+    FetchOrders(ConnectError),
+    /// The names of the types could not be fetched
+    FetchNames(ConnectError),
+    /// The orders could not be stored
+    InsertOrders(sqlx::Error),
+    /// The names could not be stored
+    InsertNames(sqlx::Error),
+    /// The history could not be stored
+    InsertHistory(sqlx::Error),
+}
+
+impl MarketError {
+    /// Logs the error together with the hub it occurred for.
+    ///
+    /// # Params
+    ///
+    /// * `hub` > Hub that was collected
+    ///
+    fn log(&self, hub: MarketHub) {
+        let location_id = *hub.location_id;
+        match self {
+            Self::MissingRefreshToken => {
+                tracing::warn!({ location_id }, "Skipping structure market, MARKET_REFRESH_TOKEN is not set")
+            }
+            Self::FetchOrders(e) => {
+                tracing::error!({ location_id, error = %e }, "Error fetching market orders")
+            }
+            Self::FetchNames(e) => {
+                tracing::error!({ location_id, error = %e }, "Error fetching type names")
+            }
+            Self::InsertOrders(e) => {
+                tracing::error!({ location_id, error = ?e }, "Error inserting market orders")
+            }
+            Self::InsertNames(e) => {
+                tracing::error!({ location_id, error = ?e }, "Error inserting type names")
+            }
+            Self::InsertHistory(e) => {
+                tracing::error!({ location_id, error = ?e }, "Error inserting market history")
+            }
+        }
+    }
+}
@@ -0,0 +1,612 @@
+use async_trait::*;
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use reqwest::{Response, StatusCode};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::{ConnectError, RequestError};
+
+/// Shared handle to a [ResponseCache], as stored by the clients
+pub type SharedCache = Arc<dyn ResponseCache>;
+
+/// Trait for implementing caches for responses of the EVE-API.
+///
+/// Every entry is identified by the full url of the request, for POST
+/// requests a hash of the body is appended. Responses of authenticated
+/// requests get the character id appended, so that characters never see
+/// responses that were requested with the roles of another character. The
+/// clients take care of
+/// checking if an entry is still valid, a cache only needs to store and
+/// return the entries.
+///
+/// Errors of a cache should not abort a request, implementations should log
+/// them and behave as if the entry does not exist.
+///
+#[async_trait]
+pub trait ResponseCache: Send + Sync {
+    /// Gets the cached response for the given key.
+    ///
+    /// # Params
+    ///
+    /// * `key` -> Url of the request
+    ///
+    /// # Returns
+    ///
+    /// - [None] if there is no entry for the key
+    /// - The cached entry, it may be expired
+    ///
+    async fn get(&self, key: &str) -> Option<CacheEntry>;
+
+    /// Stores the given entry, replacing an existing entry for the same key.
+    ///
+    /// # Params
+    ///
+    /// * `key`   -> Url of the request
+    /// * `entry` -> Response to store
+    ///
+    async fn set(&self, key: &str, entry: CacheEntry);
+}
+
+/// Single response of the EVE-API including the headers required for
+/// revalidating it.
+#[derive(Clone, Debug)]
+pub struct CacheEntry {
+    /// Raw body of the response
+    pub body: Vec<u8>,
+    /// Value of the `ETag` header
+    pub etag: Option<String>,
+    /// Value of the `Last-Modified` header
+    pub last_modified: Option<String>,
+    /// Parsed value of the `Expires` header
+    pub expires: Option<DateTime<Utc>>,
+    /// Value of the `X-Pages` header, 0 if the header is not set
+    pub pages: u32,
+}
+
+impl CacheEntry {
+    /// Reads the body and all relevant headers from the given response.
+    ///
+    /// # Params
+    ///
+    /// * `response` -> Successful response of the EVE-API
+    ///
+    /// # Errors
+    ///
+    /// Fails if the body could not be read
+    ///
+    /// # Returns
+    ///
+    /// New cache entry
+    ///
+    pub async fn from_response(response: Response) -> Result<Self, ConnectError> {
+        let mut entry = Self {
+            body: Vec::new(),
+            etag: None,
+            last_modified: None,
+            expires: None,
+            pages: 0,
+        };
+        entry.update_headers(response.headers());
+
+        let url = response.url().to_string();
+        entry.body = response
+            .bytes()
+            .await
+            .map_err(|e| ConnectError::RequestFailed(RequestError::new(&url, e)))?
+            .to_vec();
+        Ok(entry)
+    }
+
+    /// Checks if the entry can be used without asking the EVE-API.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the `Expires` header is set and lies in the future
+    ///
+    pub fn is_fresh(&self) -> bool {
+        self.expires.map(|x| x > Utc::now()).unwrap_or_default()
+    }
+
+    /// Updates the caching headers, used after a `304 Not Modified`.
+    ///
+    /// Headers that are not present in the given map are kept.
+    ///
+    /// # Params
+    ///
+    /// * `headers` -> Headers of the response
+    ///
+    pub fn update_headers(&mut self, headers: &HeaderMap) {
+        /// Reads the header with the given name as string
+        fn header(headers: &HeaderMap, name: &str) -> Option<String> {
+            headers
+                .get(name)
+                .and_then(|x| x.to_str().ok())
+                .map(|x| x.to_string())
+        }
+
+        if let Some(x) = header(headers, "etag") {
+            self.etag = Some(x);
+        }
+        if let Some(x) = header(headers, "last-modified") {
+            self.last_modified = Some(x);
+        }
+        if let Some(x) = header(headers, "expires") {
+            self.expires = DateTime::parse_from_rfc2822(&x)
+                .ok()
+                .map(|x| x.with_timezone(&Utc));
+        }
+        if let Some(x) = header(headers, "x-pages") {
+            self.pages = x.parse::<u32>().unwrap_or_default();
+        }
+    }
+}
+
+/// Resolves a request against the given cache.
+///
+/// - If there is a fresh entry, it is returned without a request
+/// - If there is an expired entry, its `ETag` is send along and a
+///   `304 Not Modified` is answered from the cache
+/// - All other responses are stored in the cache
+///
+/// # Params
+///
+/// * `cache`    -> Optional cache, if [None] every call results in a request
+/// * `key`      -> Url of the request
+/// * `duration` -> Time the entry is fresh, replaces the `Expires` header
+/// * `send`     -> Performs the request, gets `ETag` and `Last-Modified`
+///
+/// # Errors
+///
+/// Fails if the request fails or the body cannot be read
+///
+/// # Returns
+///
+/// Either the cached or the new response
+///
+pub(crate) async fn cached_for<F, Fut>(
+    cache: &Option<SharedCache>,
+    key: &str,
+    duration: Option<Duration>,
+    send: F,
+) -> Result<CacheEntry, ConnectError>
+where
+    F: FnOnce(Option<String>, Option<String>) -> Fut,
+    Fut: Future<Output = Result<Response, ConnectError>>,
+{
+    let cached = if let Some(x) = cache {
+        x.get(key).await
+    } else {
+        None
+    };
+
+    if let Some(x) = cached.as_ref().filter(|x| x.is_fresh()) {
+        tracing::debug!({ key = key }, "Serving response from cache");
+        return Ok(x.clone());
+    }
+
+    let etag = cached.as_ref().and_then(|x| x.etag.clone());
+    let last_modified = cached.as_ref().and_then(|x| x.last_modified.clone());
+    let response = send(etag, last_modified).await?;
+
+    let mut entry = match cached {
+        Some(mut x) if response.status() == StatusCode::NOT_MODIFIED => {
+            tracing::debug!({ key = key }, "Response not modified");
+            x.update_headers(response.headers());
+            x
+        }
+        _ => CacheEntry::from_response(response).await?,
+    };
+    if let Some(x) = duration.and_then(|x| chrono::Duration::from_std(x).ok()) {
+        entry.expires = Some(Utc::now() + x);
+    }
+
+    if let Some(x) = cache {
+        x.set(key, entry.clone()).await;
+    }
+    Ok(entry)
+}
+
+/// Creates the cache key for a POST request.
+///
+/// The body is hashed, so that large bodies do not result in large keys.
+/// The keys are stored in caches and fixtures, so the hash is FNV-1a, which
+/// in contrast to the hasher of the standard library is stable between
+/// releases.
+///
+/// # Params
+///
+/// * `url`  -> Url of the request
+/// * `body` -> Serialized body of the request
+///
+/// # Returns
+///
+/// Key in the format `url#hash`
+///
+pub(crate) fn post_key(url: &str, body: &[u8]) -> String {
+    /// Offset basis of the 64 bit FNV-1a hash
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    /// Prime of the 64 bit FNV-1a hash
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let hash = body
+        .iter()
+        .fold(FNV_OFFSET, |hash, x| (hash ^ *x as u64).wrapping_mul(FNV_PRIME));
+    format!("{}#{:016x}", url, hash)
+}
+
+/// Simple cache that keeps a limited number of entries in memory.
+///
+/// If the cache is full, expired entries without `ETag` are removed first,
+/// as they cannot be revalidated anymore. If that is not enough, the least
+/// recently used entries are removed until a tenth of the capacity is free
+/// again, so that not every insert has to search the whole cache.
+///
+/// Cloning the cache is cheap and all clones share the same entries, so a
+/// single instance can be used for multiple clients.
+#[derive(Clone, Debug)]
+pub struct MemoryCache(Arc<Mutex<MemoryEntries>>);
+
+/// Entries of a [MemoryCache]
+#[derive(Debug)]
+struct MemoryEntries {
+    /// Cached entries and the time they were last used
+    entries:  HashMap<String, (CacheEntry, u64)>,
+    /// Increased with every access, used as time of the last use
+    counter:  u64,
+    /// Maximum number of entries
+    capacity: usize,
+}
+
+impl MemoryCache {
+    /// Number of entries a cache holds by default
+    pub const DEFAULT_CAPACITY: usize = 10_000;
+
+    /// Creates a new empty cache with [MemoryCache::DEFAULT_CAPACITY].
+    ///
+    /// # Returns
+    ///
+    /// New cache instance
+    ///
+    pub fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    /// Creates a new empty cache that holds at most the given number of
+    /// entries.
+    ///
+    /// # Params
+    ///
+    /// * `capacity` -> Maximum number of entries, at least 1
+    ///
+    /// # Returns
+    ///
+    /// New cache instance
+    ///
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(Arc::new(Mutex::new(MemoryEntries {
+            entries:  HashMap::new(),
+            counter:  0,
+            capacity: capacity.max(1),
+        })))
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryEntries {
+    /// Makes room for a new entry if the cache is full.
+    ///
+    fn evict(&mut self) {
+        if self.entries.len() < self.capacity {
+            return;
+        }
+
+        // Expired entries without ETag cannot be revalidated anymore
+        self.entries.retain(|_, (x, _)| x.is_fresh() || x.etag.is_some());
+
+        let keep = self.capacity - (self.capacity / 10).max(1);
+        if self.entries.len() <= keep {
+            return;
+        }
+
+        let mut used = self.entries
+            .iter()
+            .map(|(key, (_, used))| (*used, key.clone()))
+            .collect::<Vec<_>>();
+        used.sort_unstable();
+        for (_, key) in used.into_iter().take(self.entries.len() - keep) {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+#[async_trait]
+impl ResponseCache for MemoryCache {
+    async fn get(&self, key: &str) -> Option<CacheEntry> {
+        let mut cache = self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        cache.counter += 1;
+        let counter = cache.counter;
+
+        cache.entries.get_mut(key).map(|(x, used)| {
+            *used = counter;
+            x.clone()
+        })
+    }
+
+    async fn set(&self, key: &str, entry: CacheEntry) {
+        let mut cache = self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if !cache.entries.contains_key(key) {
+            cache.evict();
+        }
+
+        cache.counter += 1;
+        let counter = cache.counter;
+        cache.entries.insert(key.into(), (entry, counter));
+    }
+}
+
+/// Cache that stores all entries in postgres.
+///
+/// Expired entries without `ETag` are removed at most once per
+/// [PostgresCache::CLEANUP_INTERVAL] while entries are written.
+///
+/// The cache expects the following table:
+///
+/// ``` sql
+/// CREATE TABLE esi_cache (
+///     key           VARCHAR     NOT NULL,
+///     etag          VARCHAR,
+///     last_modified VARCHAR,
+///     expires       TIMESTAMPTZ,
+///     pages         INTEGER     NOT NULL,
+///     body          BYTEA       NOT NULL,
+///
+///     PRIMARY KEY (key)
+/// );
+///
+/// CREATE INDEX esi_cache_expires ON esi_cache (expires) WHERE etag IS NULL;
+/// ```
+///
+#[cfg(feature = "postgres_cache")]
+#[derive(Clone, Debug)]
+pub struct PostgresCache {
+    /// Connection pool to postgres
+    pool:    sqlx::PgPool,
+    /// Time of the last cleanup, shared between all clones
+    cleaned: Arc<Mutex<Option<std::time::Instant>>>,
+}
+
+#[cfg(feature = "postgres_cache")]
+impl PostgresCache {
+    /// Creates a new cache using the given pool.
+    ///
+    /// # Params
+    ///
+    /// * `pool` -> Connection pool to postgres
+    ///
+    /// # Returns
+    ///
+    /// New cache instance
+    ///
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self {
+            pool,
+            cleaned: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Minimum time between two cleanups of the cache
+    pub const CLEANUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+    /// Removes all expired entries without `ETag`, as they cannot be
+    /// revalidated anymore.
+    ///
+    /// Errors are logged.
+    ///
+    pub async fn cleanup(&self) {
+        let result = sqlx::query("
+                DELETE FROM esi_cache
+                WHERE etag IS NULL
+                AND expires < NOW()
+            ")
+            .execute(&self.pool)
+            .await;
+
+        if let Err(e) = result {
+            tracing::error!({ error = e.to_string() }, "Error cleaning cache");
+        }
+    }
+
+    /// Checks if the last cleanup is longer ago than
+    /// [PostgresCache::CLEANUP_INTERVAL] and marks the cleanup as done.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the cache should be cleaned now
+    ///
+    fn cleanup_due(&self) -> bool {
+        let mut cleaned = self.cleaned
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if cleaned.map(|x| x.elapsed() < Self::CLEANUP_INTERVAL).unwrap_or_default() {
+            return false;
+        }
+        *cleaned = Some(std::time::Instant::now());
+        true
+    }
+}
+
+#[cfg(feature = "postgres_cache")]
+#[async_trait]
+impl ResponseCache for PostgresCache {
+    async fn get(&self, key: &str) -> Option<CacheEntry> {
+        let entry = sqlx::query_as::<_, (Vec<u8>, Option<String>, Option<String>, Option<DateTime<Utc>>, i32)>("
+                SELECT body, etag, last_modified, expires, pages
+                FROM esi_cache
+                WHERE key = $1
+            ")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await;
+
+        match entry {
+            Ok(x) => x.map(|(body, etag, last_modified, expires, pages)| CacheEntry {
+                body,
+                etag,
+                last_modified,
+                expires,
+                pages: pages as u32,
+            }),
+            Err(e) => {
+                tracing::error!({ key = key, error = e.to_string() }, "Error reading cache");
+                None
+            }
+        }
+    }
+
+    async fn set(&self, key: &str, entry: CacheEntry) {
+        let result = sqlx::query("
+                INSERT INTO esi_cache (key, etag, last_modified, expires, pages, body)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (key) DO UPDATE SET
+                    etag          = EXCLUDED.etag,
+                    last_modified = EXCLUDED.last_modified,
+                    expires       = EXCLUDED.expires,
+                    pages         = EXCLUDED.pages,
+                    body          = EXCLUDED.body
+            ")
+            .bind(key)
+            .bind(entry.etag)
+            .bind(entry.last_modified)
+            .bind(entry.expires)
+            .bind(entry.pages as i32)
+            .bind(entry.body)
+            .execute(&self.pool)
+            .await;
+
+        if let Err(e) = result {
+            tracing::error!({ key = key, error = e.to_string() }, "Error writing cache");
+        }
+
+        if self.cleanup_due() {
+            self.cleanup().await;
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod cache_tests {
+    use super::*;
+
+    /// Creates a response with the given status and headers
+    fn response(status: u16, headers: &[(&str, &str)], body: &str) -> Response {
+        let mut builder = http::Response::builder().status(status);
+        for (k, v) in headers {
+            builder = builder.header(*k, *v);
+        }
+        builder.body(body.to_string()).unwrap().into()
+    }
+
+    #[tokio::test]
+    async fn fresh_entry_skips_request() {
+        let cache: Option<SharedCache> = Some(Arc::new(MemoryCache::new()));
+        let expires = (Utc::now() + chrono::Duration::minutes(5)).to_rfc2822();
+
+        let entry = cached_for(&cache, "key", None, |_, _| async {
+            Ok(response(200, &[("expires", &expires)], "[1]"))
+        })
+        .await
+        .unwrap();
+        assert_eq!(entry.body, b"[1]");
+
+        let entry = cached_for(&cache, "key", None, |_, _| async {
+            panic!("The cached entry should be used");
+        })
+        .await
+        .unwrap();
+        assert_eq!(entry.body, b"[1]");
+    }
+
+    #[tokio::test]
+    async fn not_modified_served_from_cache() {
+        let cache: Option<SharedCache> = Some(Arc::new(MemoryCache::new()));
+
+        cached_for(&cache, "key", None, |_, _| async {
+            Ok(response(200, &[("etag", "\"abc\""), ("x-pages", "3")], "[1]"))
+        })
+        .await
+        .unwrap();
+
+        let entry = cached_for(&cache, "key", None, |etag, _| async move {
+            assert_eq!(etag, Some("\"abc\"".into()));
+            Ok(response(304, &[], ""))
+        })
+        .await
+        .unwrap();
+        assert_eq!(entry.body, b"[1]");
+        assert_eq!(entry.pages, 3);
+    }
+
+    #[test]
+    fn post_key_is_stable() {
+        assert_eq!(post_key("https://esi/route", b""), "https://esi/route#cbf29ce484222325");
+        assert_eq!(post_key("https://esi/route", b"a"), "https://esi/route#af63dc4c8601ec8c");
+        assert_eq!(post_key("https://esi/route", b"[1,2]"), post_key("https://esi/route", b"[1,2]"));
+        assert_ne!(post_key("https://esi/route", b"[1,2]"), post_key("https://esi/route", b"[2,1]"));
+    }
+
+    /// Creates an entry that is fresh for the given minutes
+    fn entry(minutes: i64, etag: Option<&str>) -> CacheEntry {
+        CacheEntry {
+            body:          Vec::new(),
+            etag:          etag.map(Into::into),
+            last_modified: None,
+            expires:       Some(Utc::now() + chrono::Duration::minutes(minutes)),
+            pages:         0,
+        }
+    }
+
+    #[tokio::test]
+    async fn memory_cache_evicts_least_recently_used() {
+        let cache = MemoryCache::with_capacity(10);
+        for i in 0..10 {
+            cache.set(&i.to_string(), entry(5, Some("etag"))).await;
+        }
+        assert!(cache.get("0").await.is_some());
+
+        // full, the least recently used tenth is removed
+        cache.set("10", entry(5, Some("etag"))).await;
+        assert!(cache.get("0").await.is_some());
+        assert!(cache.get("1").await.is_none());
+        assert!(cache.get("2").await.is_some());
+        assert!(cache.get("10").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn memory_cache_evicts_expired_entries_first() {
+        let cache = MemoryCache::with_capacity(10);
+        cache.set("expired", entry(-5, None)).await;
+        cache.set("revalidate", entry(-5, Some("etag"))).await;
+        for i in 0..8 {
+            cache.set(&i.to_string(), entry(5, None)).await;
+        }
+
+        cache.set("new", entry(5, None)).await;
+        assert!(cache.get("expired").await.is_none());
+        assert!(cache.get("revalidate").await.is_some());
+        assert!(cache.get("0").await.is_some());
+        assert!(cache.get("new").await.is_some());
+    }
+}
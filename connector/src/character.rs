@@ -1,7 +1,61 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::{AllianceId, CharacterId, CorporationId};
-use crate::{AssetEntry, BlueprintEntry, ConnectError, EveAuthClient, EveClient, IndustryJobEntry, ItemId, RequestClient};
+use crate::{AllianceId, CharacterId, CorporationId, TypeId};
+use crate::{AssetEntry, BlueprintEntry, ConnectError, Endpoint, EveAuthClient, EveClient, IndustryJobEntry, ItemId, RequestClient};
+use crate::{Contract, ContractBid, ContractId, ContractItem, JournalEntry, WalletTransaction};
+
+/// Public information about a character
+const CHARACTER_INFO: Endpoint = Endpoint::new("latest/characters/{character_id}/");
+/// Public information about an alliance
+const ALLIANCE_INFO: Endpoint = Endpoint::new("latest/alliances/{alliance_id}");
+/// Public information about a corporation
+const CORPORATION_INFO: Endpoint = Endpoint::new("latest/corporations/{corporation_id}");
+/// Assets of a character
+const CHARACTER_ASSETS: Endpoint = Endpoint::new("latest/characters/{character_id}/assets")
+    .scope("esi-assets.read_assets.v1")
+    .paginated();
+/// Names of assets of a character
+const CHARACTER_ASSET_NAMES: Endpoint = Endpoint::new("latest/characters/{character_id}/assets/names")
+    .scope("esi-assets.read_assets.v1");
+/// Blueprints of a character
+const CHARACTER_BLUEPRINTS: Endpoint = Endpoint::new("latest/characters/{character_id}/blueprints")
+    .scope("esi-characters.read_blueprints.v1")
+    .paginated();
+/// Industry jobs of a character
+const CHARACTER_INDUSTRY_JOBS: Endpoint = Endpoint::new("latest/characters/{character_id}/industry/jobs")
+    .scope("esi-industry.read_character_jobs.v1")
+    .query(&["include_completed"]);
+/// Trained skills of a character
+const CHARACTER_SKILLS: Endpoint = Endpoint::new("latest/characters/{character_id}/skills")
+    .scope("esi-skills.read_skills.v1");
+/// Skill queue of a character
+const CHARACTER_SKILLQUEUE: Endpoint = Endpoint::new("latest/characters/{character_id}/skillqueue")
+    .scope("esi-skills.read_skillqueue.v1");
+/// Attributes of a character
+const CHARACTER_ATTRIBUTES: Endpoint = Endpoint::new("latest/characters/{character_id}/attributes")
+    .scope("esi-skills.read_skills.v1");
+/// Wallet balance of a character
+const CHARACTER_WALLET: Endpoint = Endpoint::new("latest/characters/{character_id}/wallet")
+    .scope("esi-wallet.read_character_wallet.v1");
+/// Wallet journal of a character
+const CHARACTER_WALLET_JOURNAL: Endpoint = Endpoint::new("latest/characters/{character_id}/wallet/journal")
+    .scope("esi-wallet.read_character_wallet.v1")
+    .paginated();
+/// Market transactions of a character
+const CHARACTER_WALLET_TRANSACTIONS: Endpoint = Endpoint::new("latest/characters/{character_id}/wallet/transactions")
+    .scope("esi-wallet.read_character_wallet.v1")
+    .query(&["from_id"]);
+/// Contracts of a character
+const CHARACTER_CONTRACTS: Endpoint = Endpoint::new("latest/characters/{character_id}/contracts")
+    .scope("esi-contracts.read_character_contracts.v1")
+    .paginated();
+/// Items of a contract of a character
+const CHARACTER_CONTRACT_ITEMS: Endpoint = Endpoint::new("latest/characters/{character_id}/contracts/{contract_id}/items")
+    .scope("esi-contracts.read_character_contracts.v1");
+/// Bids of an auction of a character
+const CHARACTER_CONTRACT_BIDS: Endpoint = Endpoint::new("latest/characters/{character_id}/contracts/{contract_id}/bids")
+    .scope("esi-contracts.read_character_contracts.v1");
 
 /// Wrapper for character
 #[derive(Debug)]
@@ -37,11 +91,12 @@ impl EveCharacterService {
     /// Character information
     ///
     pub async fn info(&self, client: &EveClient) -> Result<CharacterInfo, ConnectError> {
-        let path = format!("latest/characters/{}/", self.cid);
+        let route = CHARACTER_INFO
+            .route()
+            .param("character_id", self.cid);
         client
-            .fetch::<CharacterInfo>(&path)
+            .fetch_route::<CharacterInfo>(&route)
             .await
-            .map_err(Into::into)
     }
 
     /// Gets the name of an alliance by its id
@@ -66,12 +121,13 @@ impl EveCharacterService {
             name: String,
         }
 
-        let path = format!("latest/alliances/{}", aid);
+        let route = ALLIANCE_INFO
+            .route()
+            .param("alliance_id", aid);
         client
-            .fetch::<Alliance>(&path)
+            .fetch_route::<Alliance>(&route)
             .await
             .map(|x| x.name)
-            .map_err(Into::into)
     }
 
     /// Gets the name of an corporation by its id
@@ -97,12 +153,13 @@ impl EveCharacterService {
             name: String,
         }
 
-        let path = format!("latest/corporations/{}", cid);
+        let route = CORPORATION_INFO
+            .route()
+            .param("corporation_id", cid);
         client
-            .fetch::<Corp>(&path)
+            .fetch_route::<Corp>(&route)
             .await
             .map(|x| x.name)
-            .map_err(Into::into)
     }
 
     /// Gets all assets the character owns
@@ -119,11 +176,12 @@ impl EveCharacterService {
         &self,
         client: &EveAuthClient
     ) -> Result<Vec<AssetEntry>, ConnectError> {
-        let path = format!("latest/characters/{}/assets", self.cid);
+        let route = CHARACTER_ASSETS
+            .route()
+            .param("character_id", self.cid);
         client
-            .fetch_page::<AssetEntry>(&path)
+            .fetch_route_pages::<AssetEntry>(&route)
             .await
-            .map_err(Into::into)
     }
 
     /// Gets all asset names of the assets the character owns
@@ -141,11 +199,12 @@ impl EveCharacterService {
         client: &EveAuthClient,
         iids:   Vec<ItemId>,
     ) -> Result<Vec<AssetName>, ConnectError> {
-        let path = format!("latest/characters/{}/assets/names", self.cid);
+        let route = CHARACTER_ASSET_NAMES
+            .route()
+            .param("character_id", self.cid);
         client
-            .post::<Vec<ItemId>, Vec<AssetName>>(iids, &path)
+            .post_route::<Vec<ItemId>, Vec<AssetName>>(iids, &route)
             .await
-            .map_err(Into::into)
     }
 
     /// Gets all blueprints the character owns
@@ -162,11 +221,12 @@ impl EveCharacterService {
         &self,
         client: &EveAuthClient,
     ) -> Result<Vec<BlueprintEntry>, ConnectError> {
-        let path = format!("latest/characters/{}/blueprints", self.cid);
+        let route = CHARACTER_BLUEPRINTS
+            .route()
+            .param("character_id", self.cid);
         client
-            .fetch_page::<BlueprintEntry>(&path)
+            .fetch_route_pages::<BlueprintEntry>(&route)
             .await
-            .map_err(Into::into)
     }
 
     /// Gets all industry jobs the character has running
@@ -183,11 +243,227 @@ impl EveCharacterService {
         &self,
         client: &EveAuthClient,
     ) -> Result<Vec<IndustryJobEntry>, ConnectError> {
-        let path = format!("latest/characters/{}/industry/jobs", self.cid);
+        let route = CHARACTER_INDUSTRY_JOBS
+            .route()
+            .param("character_id", self.cid);
+        client
+            .fetch_route_pages::<IndustryJobEntry>(&route)
+            .await
+    }
+
+    /// Gets all skills the character has trained
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// Trained skills and skillpoints
+    ///
+    pub async fn skills(
+        &self,
+        client: &EveAuthClient,
+    ) -> Result<CharacterSkills, ConnectError> {
+        let route = CHARACTER_SKILLS
+            .route()
+            .param("character_id", self.cid);
         client
-            .fetch_page::<IndustryJobEntry>(&path)
+            .fetch_route::<CharacterSkills>(&route)
+            .await
+    }
+
+    /// Gets the skill queue of the character
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of all queued skills, ordered by their position in the queue
+    ///
+    pub async fn skillqueue(
+        &self,
+        client: &EveAuthClient,
+    ) -> Result<Vec<SkillQueueEntry>, ConnectError> {
+        let route = CHARACTER_SKILLQUEUE
+            .route()
+            .param("character_id", self.cid);
+        let mut queue = client
+            .fetch_route::<Vec<SkillQueueEntry>>(&route)
+            .await?;
+        queue.sort_by_key(|x| x.queue_position);
+        Ok(queue)
+    }
+
+    /// Gets the attributes of the character
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// Attributes of the character
+    ///
+    pub async fn attributes(
+        &self,
+        client: &EveAuthClient,
+    ) -> Result<CharacterAttributes, ConnectError> {
+        let route = CHARACTER_ATTRIBUTES
+            .route()
+            .param("character_id", self.cid);
+        client
+            .fetch_route::<CharacterAttributes>(&route)
+            .await
+    }
+
+    /// Gets the current balance of the wallet
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// Balance of the wallet
+    ///
+    pub async fn wallet(
+        &self,
+        client: &EveAuthClient,
+    ) -> Result<f64, ConnectError> {
+        let route = CHARACTER_WALLET
+            .route()
+            .param("character_id", self.cid);
+        client
+            .fetch_route::<f64>(&route)
+            .await
+    }
+
+    /// Gets the wallet journal of the character
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of journal entries of the last 30 days
+    ///
+    pub async fn wallet_journal(
+        &self,
+        client: &EveAuthClient,
+    ) -> Result<Vec<JournalEntry>, ConnectError> {
+        let route = CHARACTER_WALLET_JOURNAL
+            .route()
+            .param("character_id", self.cid);
+        client
+            .fetch_route_pages::<JournalEntry>(&route)
+            .await
+    }
+
+    /// Gets the market transactions of the character
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of the last market transactions
+    ///
+    pub async fn wallet_transactions(
+        &self,
+        client: &EveAuthClient,
+    ) -> Result<Vec<WalletTransaction>, ConnectError> {
+        let route = CHARACTER_WALLET_TRANSACTIONS
+            .route()
+            .param("character_id", self.cid);
+        client
+            .fetch_route::<Vec<WalletTransaction>>(&route)
+            .await
+    }
+
+    /// Gets all contracts the character issued or was assigned to in the last
+    /// 30 days
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of contracts
+    ///
+    pub async fn contracts(
+        &self,
+        client: &EveAuthClient,
+    ) -> Result<Vec<Contract>, ConnectError> {
+        let route = CHARACTER_CONTRACTS
+            .route()
+            .param("character_id", self.cid);
+        client
+            .fetch_route_pages::<Contract>(&route)
+            .await
+    }
+
+    /// Gets all items of a contract
+    ///
+    /// # Params
+    ///
+    /// * `client`      -> Authenticated ESI client
+    /// * `contract_id` -> [ContractId] of the contract
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of items that are exchanged
+    ///
+    pub async fn contract_items(
+        &self,
+        client:      &EveAuthClient,
+        contract_id: ContractId,
+    ) -> Result<Vec<ContractItem>, ConnectError> {
+        let route = CHARACTER_CONTRACT_ITEMS
+            .route()
+            .param("character_id", self.cid)
+            .param("contract_id", contract_id);
+        client
+            .fetch_route::<Vec<ContractItem>>(&route)
+            .await
+    }
+
+    /// Gets all bids of an auction
+    ///
+    /// # Params
+    ///
+    /// * `client`      -> Authenticated ESI client
+    /// * `contract_id` -> [ContractId] of the auction
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of bids
+    ///
+    pub async fn contract_bids(
+        &self,
+        client:      &EveAuthClient,
+        contract_id: ContractId,
+    ) -> Result<Vec<ContractBid>, ConnectError> {
+        let route = CHARACTER_CONTRACT_BIDS
+            .route()
+            .param("character_id", self.cid)
+            .param("contract_id", contract_id);
+        client
+            .fetch_route::<Vec<ContractBid>>(&route)
             .await
-            .map_err(Into::into)
     }
 }
 
@@ -210,3 +486,224 @@ pub struct AssetName {
     /// Name of the location, for example a container or station
     pub name:    String,
 }
+
+/// All skills of a character
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CharacterSkills {
+    /// List of all trained skills
+    pub skills:         Vec<SkillEntry>,
+    /// Total number of skillpoints
+    pub total_sp:       i64,
+    /// Skillpoints that are not allocated to a skill
+    #[serde(default)]
+    pub unallocated_sp: Option<i32>,
+}
+
+impl CharacterSkills {
+    /// Gets the level of the given skill that is usable right now.
+    ///
+    /// # Params
+    ///
+    /// * `skill_id` -> [TypeId] of the skill
+    ///
+    /// # Returns
+    ///
+    /// The active level, `0` if the skill is not trained
+    ///
+    pub fn level(&self, skill_id: TypeId) -> i32 {
+        self.skills
+            .iter()
+            .find(|x| x.skill_id == skill_id)
+            .map(|x| x.active_skill_level)
+            .unwrap_or_default()
+    }
+}
+
+/// Single trained skill
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SkillEntry {
+    /// [TypeId] of the skill
+    pub skill_id:             TypeId,
+    /// Level that can be used, lower than the trained level for alpha clones
+    pub active_skill_level:   i32,
+    /// Level that was trained
+    pub trained_skill_level:  i32,
+    /// Skillpoints in the skill
+    pub skillpoints_in_skill: i64,
+}
+
+/// Single entry of the skill queue
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SkillQueueEntry {
+    /// [TypeId] of the skill
+    pub skill_id:          TypeId,
+    /// Level the skill will have after training
+    pub finished_level:    i32,
+    /// Position in the queue, starting at `0`
+    pub queue_position:    i32,
+
+    /// Time the training started, not set if the queue is paused
+    #[serde(default)]
+    pub start_date:        Option<DateTime<Utc>>,
+    /// Time the training finishes, not set if the queue is paused
+    #[serde(default)]
+    pub finish_date:       Option<DateTime<Utc>>,
+    /// Skillpoints required to reach the level
+    #[serde(default)]
+    pub level_end_sp:      Option<i32>,
+    /// Skillpoints of the previous level
+    #[serde(default)]
+    pub level_start_sp:    Option<i32>,
+    /// Skillpoints the skill had when the training started
+    #[serde(default)]
+    pub training_start_sp: Option<i32>,
+}
+
+/// Attributes of a character
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CharacterAttributes {
+    /// Charisma attribute
+    pub charisma:     i32,
+    /// Intelligence attribute
+    pub intelligence: i32,
+    /// Memory attribute
+    pub memory:       i32,
+    /// Perception attribute
+    pub perception:   i32,
+    /// Willpower attribute
+    pub willpower:    i32,
+
+    /// Number of remaps that are available
+    #[serde(default)]
+    pub bonus_remaps:                Option<i32>,
+    /// Time the attributes were last remapped
+    #[serde(default)]
+    pub last_remap_date:             Option<DateTime<Utc>>,
+    /// Time the next remap is available
+    #[serde(default)]
+    pub accrued_remap_cooldown_date: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod character_tests {
+    use super::*;
+    use crate::{ContractType, IndustryActivity};
+    use crate::testing::*;
+
+    #[tokio::test]
+    async fn public_information() {
+        let esi = MockEsi::start().await;
+        let client = esi.client().unwrap();
+        let service = EveCharacterService::new(CHARACTER_ID);
+
+        let info = service.info(&client).await.unwrap();
+        assert_eq!(info.name, "Caph Tester");
+        assert_eq!(info.corporation_id, CORPORATION_ID);
+        assert_eq!(info.alliance_id, Some(ALLIANCE_ID));
+
+        let alliance = service.alliance_name(&client, ALLIANCE_ID).await.unwrap();
+        assert_eq!(alliance, "Caph Test Alliance");
+    }
+
+    #[tokio::test]
+    async fn assets_and_names() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let service = EveCharacterService::new(CHARACTER_ID);
+
+        let assets = service.assets(&client).await.unwrap();
+        assert_eq!(assets.len(), 3);
+        assert!(assets[2].is_blueprint_copy);
+
+        let names = service
+            .asset_names(&client, vec![assets[0].item_id])
+            .await
+            .unwrap();
+        assert_eq!(names[0].name, "Minerals");
+    }
+
+    #[tokio::test]
+    async fn blueprints_and_jobs() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let service = EveCharacterService::new(CHARACTER_ID);
+
+        let blueprints = service.blueprints(&client).await.unwrap();
+        assert_eq!(blueprints.len(), 1);
+        assert_eq!(blueprints[0].material_efficiency, 10);
+
+        let jobs = service.industry_jobs(&client).await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert!(matches!(jobs[0].activity, IndustryActivity::Manufacturing));
+        assert_eq!(jobs[0].installer_id, CHARACTER_ID);
+    }
+
+    #[tokio::test]
+    async fn skills_queue_and_attributes() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let service = EveCharacterService::new(CHARACTER_ID);
+
+        let skills = service.skills(&client).await.unwrap();
+        assert_eq!(skills.total_sp, 5_000_000);
+        assert_eq!(skills.level(TypeId(3380)), 5);
+        assert_eq!(skills.level(TypeId(3388)), 4);
+        assert_eq!(skills.level(TYPE_ID), 0);
+
+        let queue = service.skillqueue(&client).await.unwrap();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0].queue_position, 0);
+        assert_eq!(queue[0].skill_id, TypeId(3388));
+        assert!(queue[0].finish_date.is_some());
+        assert!(queue[1].start_date.is_none());
+
+        let attributes = service.attributes(&client).await.unwrap();
+        assert_eq!(attributes.intelligence, 27);
+        assert_eq!(attributes.bonus_remaps, Some(1));
+    }
+
+    #[tokio::test]
+    async fn wallet() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let service = EveCharacterService::new(CHARACTER_ID);
+
+        let balance = service.wallet(&client).await.unwrap();
+        assert_eq!(balance, 9_876_543_210.98);
+
+        let journal = service.wallet_journal(&client).await.unwrap();
+        assert_eq!(journal[0].second_party_id, Some(*CORPORATION_ID));
+        assert_eq!(journal[0].date.to_rfc3339(), "2023-01-07T12:00:00+00:00");
+
+        let transactions = service.wallet_transactions(&client).await.unwrap();
+        assert_eq!(transactions[0].is_personal, Some(true));
+        assert_eq!(transactions[0].amount(), -5_000.0);
+    }
+
+    #[tokio::test]
+    async fn contracts() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let service = EveCharacterService::new(CHARACTER_ID);
+
+        let contracts = service.contracts(&client).await.unwrap();
+        assert_eq!(contracts.len(), 2);
+        assert_eq!(contracts[0].typ, ContractType::ItemExchange);
+        assert!(contracts[0].status.is_finished());
+        assert_eq!(contracts[1].typ, ContractType::Auction);
+
+        let items = service
+            .contract_items(&client, contracts[0].contract_id)
+            .await
+            .unwrap();
+        assert_eq!(items[0].type_id, TYPE_ID);
+        assert!(items[0].is_included);
+
+        let bids = service
+            .contract_bids(&client, contracts[1].contract_id)
+            .await
+            .unwrap();
+        assert_eq!(bids[0].amount, 15_000_000.0);
+    }
+}
@@ -1,15 +1,25 @@
-use crate::CharacterId;
+use crate::{CharacterId, EveJwtValidator, EveOAuthPayload, Pagination, Route};
+use crate::cache::{self, ResponseCache, SharedCache};
+#[cfg(feature = "fixtures")]
+use crate::fixture::{FixtureMode, Fixtures};
+use crate::pagination;
+use crate::retry::RetryPolicy;
+use crate::token::{SharedTokenStore, TokenState, TokenStore};
 
 use async_trait::*;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
 use reqwest::{Client, Response, StatusCode};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
 use url::Url;
 
-use crate::error::ConnectError;
+use crate::error::{ConnectError, RequestError};
 
 /// Trait for implementing new clients that interact with an API
 ///
@@ -53,29 +63,675 @@ pub trait RequestClient {
     ///
     async fn fetch_page<T>(&self, path: &str) -> Result<Vec<T>, ConnectError>
     where
-        T: DeserializeOwned + Send;
+        T: DeserializeOwned + Send + 'static;
+
+    /// Streams all items of all pages.
+    ///
+    /// After the first page, the remaining pages are requested concurrently.
+    /// The items are returned in the same order as the pages.
+    ///
+    /// # Params
+    ///
+    /// * `T`    -> Model that represents a single entry of the resulting json
+    /// * `path` -> Path of the request
+    ///
+    /// # Returns
+    ///
+    /// Stream of parsed entries, an error ends the stream
+    ///
+    fn fetch_page_stream<'a, T>(&'a self, path: &str) -> BoxStream<'a, Result<T, ConnectError>>
+    where
+        T: DeserializeOwned + Send + 'static;
 
     /// Makes a post request to the given path and returns parses the result
     /// the given struct.
     ///
     /// # Params
     ///
-    /// * `T`    -> Model that represents the resulting json
-    /// * `data` -> Request model
-    /// * `path` -> Path of the request
+    /// * `T`    -> Model that represents the resulting json
+    /// * `data` -> Request model
+    /// * `path` -> Path of the request
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if eiher the request failed or the parsing failed
+    ///
+    /// # Returns
+    ///
+    /// Parsed json data
+    ///
+    async fn post<R, T>(&self, data: R, path: &str) -> Result<T, ConnectError>
+    where
+        R: Debug + Serialize + Send + Sync,
+        T: DeserializeOwned;
+
+    /// Requests a single [Route] and parses the result.
+    ///
+    /// # Params
+    ///
+    /// * `T`     -> Model that represents the resulting json
+    /// * `route` -> Route to request
+    ///
+    /// # Errors
+    ///
+    /// - If the route is invalid or its endpoint is paginated
+    /// - If the client was not granted the scope of the endpoint, in that
+    ///   case no request is send
+    /// - If the request or the parsing failed
+    ///
+    /// # Returns
+    ///
+    /// Parsed json data
+    ///
+    async fn fetch_route<T>(&self, route: &Route) -> Result<T, ConnectError>
+    where
+        T: DeserializeOwned;
+
+    /// Requests a single [Route] and parses the result, together with the
+    /// point in time the EVE-API has new data.
+    ///
+    /// # Params
+    ///
+    /// * `T`     -> Model that represents the resulting json
+    /// * `route` -> Route to request
+    ///
+    /// # Errors
+    ///
+    /// Same as [RequestClient::fetch_route]
+    ///
+    /// # Returns
+    ///
+    /// Parsed json data and the expiry of the response, [None] if neither
+    /// the `Expires` header nor a cache duration is set
+    ///
+    async fn fetch_route_expires<T>(
+        &self,
+        route: &Route,
+    ) -> Result<(T, Option<DateTime<Utc>>), ConnectError>
+    where
+        T: DeserializeOwned;
+
+    /// Requests all pages of a [Route], endpoints without pagination are
+    /// requested once.
+    ///
+    /// # Params
+    ///
+    /// * `T`     -> Model that represents a single entry of the resulting json
+    /// * `route` -> Route to request
+    ///
+    /// # Errors
+    ///
+    /// Same as [RequestClient::fetch_route], besides that paginated
+    /// endpoints are allowed
+    ///
+    /// # Returns
+    ///
+    /// Vector of parsed json
+    ///
+    async fn fetch_route_pages<T>(&self, route: &Route) -> Result<Vec<T>, ConnectError>
+    where
+        T: DeserializeOwned + Send + 'static;
+
+    /// Streams all items of all pages of a [Route].
+    ///
+    /// # Params
+    ///
+    /// * `T`     -> Model that represents a single entry of the resulting json
+    /// * `route` -> Route to request
+    ///
+    /// # Returns
+    ///
+    /// Stream of parsed entries, an error ends the stream
+    ///
+    fn fetch_route_stream<'a, T>(&'a self, route: &Route) -> BoxStream<'a, Result<T, ConnectError>>
+    where
+        T: DeserializeOwned + Send + 'static;
+
+    /// Makes a post request to a [Route] and parses the result.
+    ///
+    /// # Params
+    ///
+    /// * `T`     -> Model that represents the resulting json
+    /// * `data`  -> Request model
+    /// * `route` -> Route to request
+    ///
+    /// # Errors
+    ///
+    /// Same as [RequestClient::fetch_route]
+    ///
+    /// # Returns
+    ///
+    /// Parsed json data
+    ///
+    async fn post_route<R, T>(&self, data: R, route: &Route) -> Result<T, ConnectError>
+    where
+        R: Debug + Serialize + Send + Sync,
+        T: DeserializeOwned;
+}
+
+/// Builder for the [EveClient] and [EveAuthClient].
+///
+/// All values that are not set fall back to the official EVE-API and the
+/// ENVs documented on the clients. Overwriting the urls allows the clients
+/// to talk to a proxy or a local test server.
+///
+/// # Usage
+///
+/// ``` rust
+/// use caph_connector::*;
+/// # std::env::set_var("EVE_USER_AGENT", "");
+///
+/// let client = EveClientBuilder::new()
+///     .esi_url("http://localhost:8080")
+///     .build()
+///     .unwrap();
+/// ```
+///
+#[derive(Clone)]
+pub struct EveClientBuilder {
+    /// Base url of the EVE-API
+    esi_url: String,
+    /// Base url of the EVE-SSO
+    sso_url: String,
+    /// User agent, falls back to the ENV `EVE_USER_AGENT`
+    user_agent: Option<String>,
+    /// Client id and secret key, fall back to the ENVs `EVE_CLIENT_ID` and
+    /// `EVE_SECRET_KEY`
+    credentials: Option<(String, String)>,
+    /// Callback after login, falls back to the ENV `EVE_CALLBACK`
+    callback: Option<String>,
+    /// Optional cache for all responses
+    cache: Option<SharedCache>,
+    /// Optional store that is notified about new refresh tokens
+    token_store: Option<SharedTokenStore>,
+    /// Policy for retrying failed requests
+    retry: RetryPolicy,
+    /// Maximum number of pages that are requested at the same time
+    page_concurrency: usize,
+    /// Optional fixtures all responses are recorded to or replayed from
+    #[cfg(feature = "fixtures")]
+    fixtures: Option<Fixtures>,
+}
+
+impl EveClientBuilder {
+    /// URL to the EVE-API
+    const EVE_API_URL: &'static str = "https://esi.evetech.net";
+    /// URL to the EVE-SSO
+    const EVE_SSO_URL: &'static str = "https://login.eveonline.com";
+    /// Path to the EVE-SSO oauth login page
+    const EVE_LOGIN_PATH: &'static str = "v2/oauth/authorize";
+    /// Path to the EVE-SSO oauth token
+    const EVE_TOKEN_PATH: &'static str = "v2/oauth/token";
+    /// Path to the public keys of the EVE-SSO
+    const EVE_JWKS_PATH: &'static str = "oauth/jwks";
+    /// Name of the ENV of the application callback
+    const ENV_CALLBACK: &'static str = "EVE_CALLBACK";
+    /// Name of the ENV of the application client id
+    const ENV_CLIENT_ID: &'static str = "EVE_CLIENT_ID";
+    /// Name of the ENV of the application secret key
+    const ENV_SECRET_KEY: &'static str = "EVE_SECRET_KEY";
+    /// Name of the ENV of the user agent
+    const ENV_USER_AGENT: &'static str = "EVE_USER_AGENT";
+    /// Default scope that is used
+    const DEFAULT_SCOPE: &'static str = "publicData";
+
+    /// Creates a new builder pointing to the official EVE-API.
+    ///
+    /// # Returns
+    ///
+    /// New builder instance
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the base url of the EVE-API.
+    ///
+    /// # Params
+    ///
+    /// * `url` -> Base url, for example `https://esi.evetech.net`
+    ///
+    /// # Returns
+    ///
+    /// The modified builder
+    ///
+    pub fn esi_url(mut self, url: impl Into<String>) -> Self {
+        self.esi_url = url.into().trim_end_matches('/').into();
+        self
+    }
+
+    /// Sets the base url of the EVE-SSO.
+    ///
+    /// # Params
+    ///
+    /// * `url` -> Base url, for example `https://login.eveonline.com`
+    ///
+    /// # Returns
+    ///
+    /// The modified builder
+    ///
+    pub fn sso_url(mut self, url: impl Into<String>) -> Self {
+        self.sso_url = url.into().trim_end_matches('/').into();
+        self
+    }
+
+    /// Sets the user agent, instead of reading the ENV `EVE_USER_AGENT`.
+    ///
+    /// # Params
+    ///
+    /// * `user_agent` -> User agent that is send with every request
+    ///
+    /// # Returns
+    ///
+    /// The modified builder
+    ///
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sets the application credentials, instead of reading the ENVs
+    /// `EVE_CLIENT_ID` and `EVE_SECRET_KEY`.
+    ///
+    /// # Params
+    ///
+    /// * `client_id`  -> Client ID of the application
+    /// * `secret_key` -> Secret key of the application
+    ///
+    /// # Returns
+    ///
+    /// The modified builder
+    ///
+    pub fn credentials(
+        mut self,
+        client_id: impl Into<String>,
+        secret_key: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some((client_id.into(), secret_key.into()));
+        self
+    }
+
+    /// Sets the callback after login, instead of reading the ENV
+    /// `EVE_CALLBACK`.
+    ///
+    /// # Params
+    ///
+    /// * `callback` -> Url to callback after authentication
+    ///
+    /// # Returns
+    ///
+    /// The modified builder
+    ///
+    pub fn callback(mut self, callback: impl Into<String>) -> Self {
+        self.callback = Some(callback.into());
+        self
+    }
+
+    /// Sets the cache that is used for all requests of the clients.
+    ///
+    /// # Params
+    ///
+    /// * `cache` -> Cache to use
+    ///
+    /// # Returns
+    ///
+    /// The modified builder
+    ///
+    pub fn cache<C>(mut self, cache: C) -> Self
+    where
+        C: ResponseCache + 'static,
+    {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Sets the store that is notified when the [EveAuthClient] receives a
+    /// new `refresh_token`.
+    ///
+    /// # Params
+    ///
+    /// * `store` -> Store to use
+    ///
+    /// # Returns
+    ///
+    /// The modified builder
+    ///
+    pub fn token_store<S>(mut self, store: S) -> Self
+    where
+        S: TokenStore + 'static,
+    {
+        self.token_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Sets the policy for retrying failed requests.
+    ///
+    /// # Params
+    ///
+    /// * `retry` -> Policy to use
+    ///
+    /// # Returns
+    ///
+    /// The modified builder
+    ///
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sets how many pages of a paginated endpoint are requested at the
+    /// same time.
+    ///
+    /// # Params
+    ///
+    /// * `concurrency` -> Maximum number of concurrent requests, at least 1
+    ///
+    /// # Returns
+    ///
+    /// The modified builder
+    ///
+    pub fn page_concurrency(mut self, concurrency: usize) -> Self {
+        self.page_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Records all responses to or replays them from the given fixtures.
+    ///
+    /// While fixtures are set, the cache is not used.
+    ///
+    /// # Params
+    ///
+    /// * `fixtures` -> Fixtures to record to or replay from
+    ///
+    /// # Returns
+    ///
+    /// The modified builder
+    ///
+    #[cfg(feature = "fixtures")]
+    pub fn fixtures(mut self, fixtures: Fixtures) -> Self {
+        self.fixtures = Some(fixtures);
+        self
+    }
+
+    /// Consutructs a new [EveClient].
+    ///
+    /// # Errors
+    ///
+    /// The function will return an error if no user agent is set and the ENV
+    /// `EVE_USER_AGENT` is not set.
+    /// Besides that it will return an error if the client could not be
+    /// constructed.
+    ///
+    /// # Returns
+    ///
+    /// New instance of the [EveClient]
+    ///
+    pub fn build(self) -> Result<EveClient, ConnectError> {
+        let client = self.http_client()?;
+
+        Ok(EveClient {
+            client: client,
+            esi_url: self.esi_url,
+            cache: self.cache,
+            retry: self.retry,
+            page_concurrency: self.page_concurrency,
+            #[cfg(feature = "fixtures")]
+            fixtures: self.fixtures,
+        })
+    }
+
+    /// Consutructs a new [EveAuthClient].
+    ///
+    /// # Params
+    ///
+    /// * `refresh_token` -> Refresh token from the EVE-API
+    ///
+    /// # Errors
+    ///
+    /// The function will return an error if no user agent is set and the ENV
+    /// `EVE_USER_AGENT` is not set.
+    /// Besides that it will return an error if the client could not be
+    /// constructed.
+    ///
+    /// # Returns
+    ///
+    /// New instance of the [EveAuthClient]
+    ///
+    pub fn build_auth(self, refresh_token: String) -> Result<EveAuthClient, ConnectError> {
+        let client = Client::builder()
+            .user_agent(self.resolve_user_agent()?)
+            .pool_idle_timeout(None)
+            .build()
+            .map_err(ConnectError::CouldNotConstructClient)?;
+
+        Ok(EveAuthClient {
+            client: client,
+            tokens: Arc::new(Mutex::new(TokenState::new(refresh_token))),
+            token: self.clone(),
+            token_store: self.token_store,
+            esi_url: self.esi_url,
+            cache: self.cache,
+            retry: self.retry,
+            page_concurrency: self.page_concurrency,
+            #[cfg(feature = "fixtures")]
+            fixtures: self.fixtures,
+        })
+    }
+
+    /// Gets the initial access token.
+    ///
+    /// [More information](https://docs.esi.evetech.net/docs/sso/web_based_sso_flow.html)
+    ///
+    /// # Params
+    ///
+    /// * `code` -> Code send by the EVE-API as query parameter
+    ///
+    /// # Errors
+    ///
+    /// If the retrieving of an `access_token` fails the function will return
+    /// an error
+    ///
+    /// # Returns
+    ///
+    /// New token object
+    ///
+    pub async fn access_token(&self, code: &str) -> Result<EveOAuthToken, ConnectError> {
+        let mut map = HashMap::new();
+        map.insert("grant_type", "authorization_code");
+        map.insert("code", code);
+
+        self.get_token(&self.http_client()?, map).await
+    }
+
+    /// Generates a url for authenticationg a character against the EVE-SSO.
+    ///
+    /// # Params
+    ///
+    /// * `state` -> Unique key, used for extra security
+    /// * `scope` -> Required scope, musst be a lost of space seperated entries
+    ///
+    /// # Errors
+    ///
+    /// The function will return an error if either the callback or the
+    /// credentials are neither set nor available as ENV.
+    ///
+    /// # Returns
+    ///
+    /// Url to redirect the user to
+    ///
+    pub fn auth_uri(&self, state: &str, scope: Option<&str>) -> Result<Url, ConnectError> {
+        let scope = if let Some(x) = scope {
+            x
+        } else {
+            Self::DEFAULT_SCOPE
+        };
+
+        let mut url = Url::parse(&format!("{}/{}", self.sso_url, Self::EVE_LOGIN_PATH))
+            .map_err(|_| ConnectError::UrlParseError)?;
+
+        let callback = if let Some(x) = self.callback.clone() {
+            x
+        } else {
+            std::env::var(Self::ENV_CALLBACK).map_err(|_| ConnectError::env_callback())?
+        };
+        let (client_id, _) = self.resolve_credentials()?;
+
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("redirect_uri", &callback)
+            .append_pair("client_id", &client_id)
+            .append_pair("scope", scope)
+            .append_pair("state", state);
+        Ok(url)
+    }
+
+    /// Loads the public keys of the configured EVE-SSO for validating access
+    /// tokens.
+    ///
+    /// # Errors
+    ///
+    /// - If the credentials are neither set nor available as ENV
+    /// - If no user agent is set and the ENV `EVE_USER_AGENT` is not set
+    /// - If the keys could not be downloaded or parsed
+    ///
+    /// # Returns
+    ///
+    /// Validator for the tokens issued to this application
+    ///
+    pub async fn jwt_validator(&self) -> Result<EveJwtValidator, ConnectError> {
+        let (client_id, _) = self.resolve_credentials()?;
+        let url = format!("{}/{}", self.sso_url, Self::EVE_JWKS_PATH);
+        EveJwtValidator::from_url(&url, client_id, self.http_client()?).await
+    }
+
+    /// Makes a request to the token interface and sets necessary headers to
+    /// retrieve a new `access_token`.
+    ///
+    /// Failed requests are retried according to the [RetryPolicy] of the
+    /// builder.
+    ///
+    /// # Params
+    ///
+    /// * `client` -> Client with the configured user agent
+    /// * `form`   -> Form containing `grant_type` and `code` or `refresh_token`
     ///
     /// # Errors
     ///
-    /// Returns an error if eiher the request failed or the parsing failed
+    /// If the request fails
     ///
     /// # Returns
     ///
-    /// Parsed json data
+    /// New token object
     ///
-    async fn post<R, T>(&self, data: R, path: &str) -> Result<T, ConnectError>
-    where
-        R: Debug + Serialize + Send + Sync,
-        T: DeserializeOwned;
+    async fn get_token(
+        &self,
+        client: &Client,
+        form:   HashMap<&str, &str>,
+    ) -> Result<EveOAuthToken, ConnectError> {
+        let (client_id, secret_key) = self.resolve_credentials()?;
+        let url = format!("{}/{}", self.sso_url, Self::EVE_TOKEN_PATH);
+
+        let response = self.retry.send(&url, || {
+            client
+                .post(&url)
+                .basic_auth(&client_id, Some(&secret_key))
+                .header("Content-Type", "application/x-www-form-urlencoded")
+                .form(&form)
+        })
+        .await?;
+
+        response
+            .json::<EveOAuthToken>()
+            .await
+            .map_err(|e| ConnectError::RequestFailed(RequestError::new(&url, e)))
+    }
+
+    /// Creates a new HTTP client that sends the configured user agent.
+    ///
+    /// # Errors
+    ///
+    /// If no user agent is set and the ENV `EVE_USER_AGENT` is not set, or
+    /// if the client could not be constructed
+    ///
+    /// # Returns
+    ///
+    /// New HTTP client
+    ///
+    fn http_client(&self) -> Result<Client, ConnectError> {
+        Client::builder()
+            .user_agent(self.resolve_user_agent()?)
+            .build()
+            .map_err(ConnectError::CouldNotConstructClient)
+    }
+
+    /// Gets the user agent, either the configured or from the ENV.
+    ///
+    /// # Errors
+    ///
+    /// If no user agent is set and the ENV `EVE_USER_AGENT` is not set
+    ///
+    /// # Returns
+    ///
+    /// User agent to use
+    ///
+    fn resolve_user_agent(&self) -> Result<String, ConnectError> {
+        if let Some(x) = self.user_agent.clone() {
+            Ok(x)
+        } else {
+            std::env::var(Self::ENV_USER_AGENT).map_err(|_| ConnectError::env_user_agent())
+        }
+    }
+
+    /// Gets the client id and secret key, either the configured or from the
+    /// ENVs.
+    ///
+    /// # Errors
+    ///
+    /// If no credentials are set and either the ENV `EVE_CLIENT_ID` or
+    /// `EVE_SECRET_KEY` is not set
+    ///
+    /// # Returns
+    ///
+    /// Tuple of client id and secret key
+    ///
+    fn resolve_credentials(&self) -> Result<(String, String), ConnectError> {
+        if let Some(x) = self.credentials.clone() {
+            return Ok(x);
+        }
+
+        let client_id =
+            std::env::var(Self::ENV_CLIENT_ID).map_err(|_| ConnectError::env_client_id())?;
+        let secret_key =
+            std::env::var(Self::ENV_SECRET_KEY).map_err(|_| ConnectError::env_secret_key())?;
+        Ok((client_id, secret_key))
+    }
+}
+
+impl Default for EveClientBuilder {
+    fn default() -> Self {
+        Self {
+            esi_url: Self::EVE_API_URL.into(),
+            sso_url: Self::EVE_SSO_URL.into(),
+            user_agent: None,
+            credentials: None,
+            callback: None,
+            cache: None,
+            token_store: None,
+            retry: RetryPolicy::default(),
+            page_concurrency: pagination::DEFAULT_PAGE_CONCURRENCY,
+            #[cfg(feature = "fixtures")]
+            fixtures: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for EveClientBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EveClientBuilder")
+            .field("esi_url", &self.esi_url)
+            .field("sso_url", &self.sso_url)
+            .finish()
+    }
 }
 
 /// Client for communicating with the EVE-API.
@@ -86,16 +742,38 @@ pub trait RequestClient {
 ///
 /// * `EVE_USER_AGENT` -> Name of the user agent that is send with every request
 ///
+/// # Caching
+///
+/// By default every call results in a request. With [EveClient::with_cache]
+/// responses are cached until the `Expires` header of the response is
+/// reached, after that the entry is revalidated using its `ETag`.
+///
+/// # Retries
+///
+/// Failed requests are retried using the default [RetryPolicy], a different
+/// policy can be set with [EveClient::with_retry_policy].
+///
 #[derive(Clone)]
-pub struct EveClient(Client);
+pub struct EveClient {
+    /// Client for communicating with EVE
+    client: Client,
+    /// Base url of the EVE-API
+    esi_url: String,
+    /// Optional cache for all responses
+    cache: Option<SharedCache>,
+    /// Policy for retrying failed requests
+    retry: RetryPolicy,
+    /// Maximum number of pages that are requested at the same time
+    page_concurrency: usize,
+    /// Optional fixtures all responses are recorded to or replayed from
+    #[cfg(feature = "fixtures")]
+    fixtures: Option<Fixtures>,
+}
 
 impl EveClient {
-    /// URL to the EVE-API
-    const EVE_API_URL: &'static str = "https://esi.evetech.net";
-    /// Name of the ENV of the user agent
-    const ENV_USER_AGENT: &'static str = "EVE_USER_AGENT";
-
-    /// Consutructs a new [EveClient].
+    /// Consutructs a new [EveClient] for the official EVE-API.
+    ///
+    /// Use [EveClientBuilder] for configuring the client.
     ///
     /// # Requirements
     ///
@@ -113,15 +791,40 @@ impl EveClient {
     /// New instance of the [EveClient]
     ///
     pub fn new() -> Result<Self, ConnectError> {
-        let user_agent =
-            std::env::var(Self::ENV_USER_AGENT).map_err(|_| ConnectError::env_user_agent())?;
+        EveClientBuilder::default().build()
+    }
 
-        let client = Client::builder()
-            .user_agent(user_agent)
-            .build()
-            .map_err(ConnectError::CouldNotConstructClient)?;
+    /// Sets the cache that is used for all requests of the client.
+    ///
+    /// # Params
+    ///
+    /// * `cache` -> Cache to use
+    ///
+    /// # Returns
+    ///
+    /// The client using the given cache
+    ///
+    pub fn with_cache<C>(mut self, cache: C) -> Self
+    where
+        C: ResponseCache + 'static,
+    {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
 
-        Ok(Self(client))
+    /// Sets the policy for retrying failed requests.
+    ///
+    /// # Params
+    ///
+    /// * `retry` -> Policy to use
+    ///
+    /// # Returns
+    ///
+    /// The client using the given policy
+    ///
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
     }
 
     /// Deconstructs the struct and returns the underlying [reqwest::Client].
@@ -131,83 +834,193 @@ impl EveClient {
     /// Underlying [reqwest::Client]
     ///
     pub fn into_inner(self) -> Client {
-        self.0
+        self.client
     }
 
-    /// Sends a request to the given path.
+    /// Sends a request to the given url.
     ///
-    /// If a request failes with a non successfull status, it will retry the
-    /// request again, up to 3 times.
+    /// Failed requests are retried according to the [RetryPolicy] of the
+    /// client.
     ///
     /// # Params
     ///
-    /// * `path` -> Path for the request
+    /// * `url`           -> Full url for the request
+    /// * `etag`          -> Cached `ETag`, send as `If-None-Match`
+    /// * `last_modified` -> Cached `Last-Modified`, send as `If-Modified-Since`
     ///
     /// # Errors
     ///
-    /// The function errors if too many request failed, or if there is a general
-    /// error with the requesting library.
+    /// The function errors if the EVE-API returns an error that is not
+    /// retried, all retries failed, or if there is a general error with the
+    /// requesting library.
     ///
     /// # Returns
     ///
     /// Response of the request, ready to work with
     ///
     #[tracing::instrument(level = "debug")]
-    async fn send(&self, path: &str) -> Result<Response, ConnectError> {
-        let path = format!("{}/{}", Self::EVE_API_URL, path);
-        let mut retry_counter = 0usize;
+    async fn send(
+        &self,
+        url: &str,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> Result<Response, ConnectError> {
+        self.retry
+            .send(url, || {
+                let mut request = self.client.get(url);
+                if let Some(x) = etag.as_ref() {
+                    request = request.header("If-None-Match", x);
+                }
+                if let Some(x) = last_modified.as_ref() {
+                    request = request.header("If-Modified-Since", x);
+                }
+                request
+            })
+            .await
+    }
 
-        loop {
-            if retry_counter == 3 {
-                tracing::error!("Too many retries requesting {}.", &path);
-                return Err(ConnectError::TooManyRetries(path));
-            }
+    /// Sends a POST request with the given json body to the given url.
+    ///
+    /// Failed requests are retried according to the [RetryPolicy] of the
+    /// client.
+    ///
+    /// # Params
+    ///
+    /// * `url`           -> Full url for the request
+    /// * `body`          -> Serialized json body
+    /// * `etag`          -> Cached `ETag`, send as `If-None-Match`
+    /// * `last_modified` -> Cached `Last-Modified`, send as `If-Modified-Since`
+    ///
+    /// # Errors
+    ///
+    /// Same as [EveClient::send]
+    ///
+    /// # Returns
+    ///
+    /// Response of the request, ready to work with
+    ///
+    #[tracing::instrument(level = "debug", skip(body))]
+    async fn send_post(
+        &self,
+        url: &str,
+        body: &[u8],
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> Result<Response, ConnectError> {
+        self.retry
+            .send(url, || {
+                let mut request = self
+                    .client
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .body(body.to_vec());
+                if let Some(x) = etag.as_ref() {
+                    request = request.header("If-None-Match", x);
+                }
+                if let Some(x) = last_modified.as_ref() {
+                    request = request.header("If-Modified-Since", x);
+                }
+                request
+            })
+            .await
+    }
 
-            let response = self
-                .0
-                .get(&path)
-                .send()
-                .await
-                .map_err(ConnectError::ReqwestError)?;
-
-            if !response.status().is_success() {
-                retry_counter += 1;
-                tracing::warn!(
-                    r#"Fetch resulted in non successful status code.
-                       Statuscode was {}. Retrying {}."#,
-                    response.status(),
-                    retry_counter
-                );
-                continue;
-            }
+    /// Gets the response for the given url, either from the cache or by
+    /// sending a request.
+    ///
+    /// # Params
+    ///
+    /// * `url`      -> Full url for the request
+    /// * `duration` -> Time a new response is cached, [None] for `Expires`
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request fails
+    ///
+    /// # Returns
+    ///
+    /// Cached or new response
+    ///
+    async fn send_cached(
+        &self,
+        url:      &str,
+        duration: Option<Duration>,
+    ) -> Result<cache::CacheEntry, ConnectError> {
+        #[cfg(feature = "fixtures")]
+        if let Some(x) = self.fixtures.as_ref() {
+            return x.resolve("GET", url, || async {
+                cache::CacheEntry::from_response(self.send(url, None, None).await?).await
+            })
+            .await;
+        }
+
+        cache::cached_for(&self.cache, url, duration, |etag, last_modified| {
+            self.send(url, etag, last_modified)
+        })
+        .await
+    }
 
-            return Ok(response);
+    /// Gets the response for a POST request, either from the cache or by
+    /// sending the request.
+    ///
+    /// # Params
+    ///
+    /// * `url`      -> Full url for the request
+    /// * `body`     -> Serialized json body
+    /// * `duration` -> Time a new response is cached, [None] for `Expires`
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request fails
+    ///
+    /// # Returns
+    ///
+    /// Cached or new response
+    ///
+    async fn send_post_cached(
+        &self,
+        url:      &str,
+        body:     &[u8],
+        duration: Option<Duration>,
+    ) -> Result<cache::CacheEntry, ConnectError> {
+        let key = cache::post_key(url, body);
+
+        #[cfg(feature = "fixtures")]
+        if let Some(x) = self.fixtures.as_ref() {
+            return x.resolve("POST", &key, || async {
+                cache::CacheEntry::from_response(self.send_post(url, body, None, None).await?).await
+            })
+            .await;
         }
+
+        cache::cached_for(&self.cache, &key, duration, |etag, last_modified| {
+            self.send_post(url, body, etag, last_modified)
+        })
+        .await
     }
 
-    /// Extract the page header from the give [reqwest::Response].
+    /// Builds the url of a [Route].
+    ///
+    /// The client has no token, so endpoints that require a scope are
+    /// rejected.
     ///
     /// # Params
     ///
-    /// * `response` -> Respone to get the header from
+    /// * `route` -> Route to request
+    ///
+    /// # Errors
+    ///
+    /// If the route is invalid or requires a scope
     ///
     /// # Returns
     ///
-    /// - If the header is not present a 0 is returned
-    /// - If the header exists, it will try to parse it, if that fails a 0 is
-    ///   is returned
+    /// Full url of the route
     ///
-    #[tracing::instrument(level = "debug")]
-    fn page_count(&self, response: &Response) -> u16 {
-        let headers = response.headers();
-        if let Some(x) = headers.get("x-pages") {
-            x.to_str()
-                .unwrap_or_default()
-                .parse::<u16>()
-                .unwrap_or_default()
-        } else {
-            0u16
+    fn route_url(&self, route: &Route) -> Result<String, ConnectError> {
+        if let Some(scope) = route.endpoint().required_scope() {
+            return Err(ConnectError::MissingScope(scope.into()));
         }
+        Ok(format!("{}/{}", self.esi_url, route.path()?))
     }
 }
 
@@ -218,51 +1031,114 @@ impl RequestClient for EveClient {
     where
         T: DeserializeOwned,
     {
-        let json = self
-            .send(path)
-            .await?
-            .json::<T>()
-            .await
-            .map_err(ConnectError::ReqwestError)?;
-        Ok(json)
+        let url = format!("{}/{}", self.esi_url, path);
+        let entry = self.send_cached(&url, None).await?;
+        serde_json::from_slice::<T>(&entry.body).map_err(ConnectError::ParseError)
     }
 
     #[tracing::instrument(level = "debug")]
     async fn fetch_page<T>(&self, path: &str) -> Result<Vec<T>, ConnectError>
     where
-        T: DeserializeOwned + Send,
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.fetch_page_stream(path).try_collect().await
+    }
+
+    fn fetch_page_stream<'a, T>(&'a self, path: &str) -> BoxStream<'a, Result<T, ConnectError>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let url = format!("{}/{}", self.esi_url, path);
+        pagination::stream(url, self.page_concurrency, move |url| async move {
+            self.send_cached(&url, None).await
+        })
+    }
+
+    #[tracing::instrument(level = "debug")]
+    async fn post<R, T>(&self, data: R, path: &str) -> Result<T, ConnectError>
+    where
+        R: Debug + Serialize + Send + Sync,
+        T: DeserializeOwned,
     {
-        let response = self.send(path).await?;
+        let url = format!("{}/{}", self.esi_url, path);
+        let body = serde_json::to_vec(&data).map_err(ConnectError::ParseError)?;
 
-        let pages = self.page_count(&response);
+        let entry = self.send_post_cached(&url, &body, None).await?;
+        serde_json::from_slice::<T>(&entry.body).map_err(ConnectError::ParseError)
+    }
 
-        let mut fetched_data: Vec<T> = Vec::new();
-        let data = response
-            .json::<Vec<T>>()
+    #[tracing::instrument(level = "debug")]
+    async fn fetch_route<T>(&self, route: &Route) -> Result<T, ConnectError>
+    where
+        T: DeserializeOwned,
+    {
+        self
+            .fetch_route_expires::<T>(route)
             .await
-            .map_err(ConnectError::ReqwestError)?;
-        fetched_data.extend(data);
-
-        for page in 2..=pages {
-            let next_page = self
-                .send(&format!("{}?page={}", path, page))
-                .await?
-                .json::<Vec<T>>()
-                .await
-                .map_err(ConnectError::ReqwestError)?;
-
-            fetched_data.extend(next_page);
+            .map(|(x, _)| x)
+    }
+
+    #[tracing::instrument(level = "debug")]
+    async fn fetch_route_expires<T>(
+        &self,
+        route: &Route,
+    ) -> Result<(T, Option<DateTime<Utc>>), ConnectError>
+    where
+        T: DeserializeOwned,
+    {
+        if route.endpoint().pagination() == Pagination::Pages {
+            return Err(ConnectError::InvalidRoute(
+                format!("{} is paginated", route.endpoint().path())
+            ));
         }
 
-        Ok(fetched_data)
+        let url = self.route_url(route)?;
+        let entry = self.send_cached(&url, route.endpoint().cache_duration()).await?;
+        serde_json::from_slice::<T>(&entry.body)
+            .map(|x| (x, entry.expires))
+            .map_err(ConnectError::ParseError)
     }
 
-    async fn post<R, T>(&self, _data: R, _path: &str) -> Result<T, ConnectError>
+    #[tracing::instrument(level = "debug")]
+    async fn fetch_route_pages<T>(&self, route: &Route) -> Result<Vec<T>, ConnectError>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        match route.endpoint().pagination() {
+            Pagination::Pages => self.fetch_route_stream(route).try_collect().await,
+            Pagination::None  => self.fetch_route::<Vec<T>>(route).await,
+        }
+    }
+
+    fn fetch_route_stream<'a, T>(&'a self, route: &Route) -> BoxStream<'a, Result<T, ConnectError>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let route = route.clone();
+        let duration = route.endpoint().cache_duration();
+
+        stream::once(async move { self.route_url(&route) })
+            .flat_map(move |url| match url {
+                Ok(url) => pagination::stream(url, self.page_concurrency, move |url| async move {
+                    self.send_cached(&url, duration).await
+                }),
+                Err(e) => stream::iter(vec![Err(e)]).boxed(),
+            })
+            .boxed()
+    }
+
+    #[tracing::instrument(level = "debug")]
+    async fn post_route<R, T>(&self, data: R, route: &Route) -> Result<T, ConnectError>
     where
         R: Debug + Serialize + Send + Sync,
         T: DeserializeOwned,
     {
-        unimplemented!()
+        let url = self.route_url(route)?;
+        let body = serde_json::to_vec(&data).map_err(ConnectError::ParseError)?;
+
+        let duration = route.endpoint().cache_duration();
+        let entry = self.send_post_cached(&url, &body, duration).await?;
+        serde_json::from_slice::<T>(&entry.body).map_err(ConnectError::ParseError)
     }
 }
 
@@ -295,35 +1171,49 @@ impl std::fmt::Debug for EveClient {
 /// * `ÈVE_CLIENT_ID`  -> Client ID of the application
 /// * `EVE_SECRET_KEY` -> Secret key of the application
 ///
+/// # Caching
+///
+/// Same as for the [EveClient], a cache can be set with
+/// [EveAuthClient::with_cache].
+///
+/// # Retries
+///
+/// Failed requests are retried using the default [RetryPolicy], a different
+/// policy can be set with [EveAuthClient::with_retry_policy].
+///
+/// # Tokens
+///
+/// The `access_token` is refreshed shortly before it expires. Clones of the
+/// client share their tokens, so concurrent requests only refresh once.
+/// If the EVE-SSO rotates the `refresh_token`, the [TokenStore] set with
+/// [EveAuthClient::with_token_store] is notified.
+///
 #[derive(Clone)]
 pub struct EveAuthClient {
     /// Client for communicating with EVE
     client: Client,
-    /// Token to get a new `access_token`
-    refresh_token: String,
-    /// Token needed to get data that is behind auth
-    access_token: Arc<Mutex<Option<String>>>,
+    /// Current `refresh_token` and `access_token`, the lock is held while a
+    /// new `access_token` is requested
+    tokens: Arc<Mutex<TokenState>>,
+    /// Configuration for requesting new tokens from the EVE-SSO
+    token: EveClientBuilder,
+    /// Optional store that is notified about new refresh tokens
+    token_store: Option<SharedTokenStore>,
+    /// Base url of the EVE-API
+    esi_url: String,
+    /// Optional cache for all GET responses
+    cache: Option<SharedCache>,
+    /// Policy for retrying failed requests
+    retry: RetryPolicy,
+    /// Maximum number of pages that are requested at the same time
+    page_concurrency: usize,
+    /// Optional fixtures all responses are recorded to or replayed from
+    #[cfg(feature = "fixtures")]
+    fixtures: Option<Fixtures>,
 }
 
 impl EveAuthClient {
-    /// URL to the EVE-API
-    const EVE_API_URL: &'static str = "https://esi.evetech.net";
-    /// URL to the EVE-API oauth login page
-    const EVE_LOGIN_URL: &'static str = "https://login.eveonline.com/v2/oauth/authorize";
-    /// URL to the EVE-API oauth token
-    const EVE_TOKEN_URL: &'static str = "https://login.eveonline.com/v2/oauth/token";
-    /// Name of the ENV of the application callback
-    const ENV_CALLBACK: &'static str = "EVE_CALLBACK";
-    /// Name of the ENV of the application client id
-    const ENV_CLIENT_ID: &'static str = "EVE_CLIENT_ID";
-    /// Name of the ENV of the application secret key
-    const ENV_SECRET_KEY: &'static str = "EVE_SECRET_KEY";
-    /// Name of the ENV of the user agent
-    const ENV_USER_AGENT: &'static str = "EVE_USER_AGENT";
-    /// Default scope that is used
-    const DEFAULT_SCOPE: &'static str = "publicData";
-
-    /// Gets the initial access token,
+    /// Gets the initial access token from the official EVE-SSO.
     ///
     /// [More information](https://docs.esi.evetech.net/docs/sso/web_based_sso_flow.html)
     ///
@@ -331,100 +1221,100 @@ impl EveAuthClient {
     ///
     /// * `code` -> Code send by the EVE-API as query parameter
     ///
-    /// # Panics
-    ///
-    /// Panics if the [Mutex] is not exclusive.
-    ///
     /// # Errors
     ///
     /// If the retrieving of an `access_token` fails the function will return
     /// an error
     ///
     pub async fn access_token(code: &str) -> Result<EveOAuthToken, ConnectError> {
-        let mut map = HashMap::new();
-        map.insert("grant_type", "authorization_code");
-        map.insert("code", code);
-
-        let token = Self::get_token(map).await?;
-        Ok(token)
+        EveClientBuilder::default().access_token(code).await
     }
 
-    /// Makes a request to the token interface and sets necessary headers to
-    /// retrieve a new `access_token`.
+    /// Consutructs a new [EveAuthClient] for the official EVE-API.
+    ///
+    /// Use [EveClientBuilder] for configuring the client.
+    ///
+    /// # Requirements
+    ///
+    /// The ENV `EVE_USER_AGENT` must be set.
     ///
     /// # Params
     ///
-    /// * `form` -> Form containing `grant_type` and `code` or `refres_token`.
-    ///             See the EVE SSO-Flow documentation for more information
+    /// * `refresh_token` -> Refresh token from the EVE-API
     ///
     /// # Errors
     ///
-    /// If the request fails
+    /// The function will return an error if the ENV `EVE_USER_AGENT` is not set.
+    /// Besides that it will return an error if the client could not be
+    /// constructed.
+    /// See the returned error for more details.
     ///
     /// # Returns
     ///
-    /// New token object
+    /// New instance of the [EveAuthClient]
     ///
-    async fn get_token(form: HashMap<&str, &str>) -> Result<EveOAuthToken, ConnectError> {
-        let client_id =
-            std::env::var(Self::ENV_CLIENT_ID).map_err(|_| ConnectError::env_client_id())?;
-        let secret_key =
-            std::env::var(Self::ENV_SECRET_KEY).map_err(|_| ConnectError::env_secret_key())?;
+    pub fn new(refresh_token: String) -> Result<Self, ConnectError> {
+        EveClientBuilder::default().build_auth(refresh_token)
+    }
 
-        Client::new()
-            .post(Self::EVE_TOKEN_URL)
-            .basic_auth(client_id, Some(secret_key))
-            .header("Content-Type", "application/x-www-form-urlencoded")
-            .header("Host", "login.eveonline.com")
-            .form(&form)
-            .send()
-            .await
-            .map_err(ConnectError::ReqwestError)?
-            .json::<EveOAuthToken>()
-            .await
-            .map_err(ConnectError::ReqwestError)
+    /// Sets the cache that is used for all GET requests of the client.
+    ///
+    /// # Params
+    ///
+    /// * `cache` -> Cache to use
+    ///
+    /// # Returns
+    ///
+    /// The client using the given cache
+    ///
+    pub fn with_cache<C>(mut self, cache: C) -> Self
+    where
+        C: ResponseCache + 'static,
+    {
+        self.cache = Some(Arc::new(cache));
+        self
     }
 
-    /// Consutructs a new [EveAuthClient].
+    /// Sets the policy for retrying failed requests.
     ///
-    /// # Requirements
+    /// # Params
     ///
-    /// The ENV `EVE_USER_AGENT` must be set.
+    /// * `retry` -> Policy to use
+    ///
+    /// # Returns
+    ///
+    /// The client using the given policy
+    ///
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sets the store that is notified when the client receives a new
+    /// `refresh_token`.
     ///
     /// # Params
     ///
-    /// * `refresh_token` -> Refresh token from the EVE-API
-    ///
-    /// # Errors
-    ///
-    /// The function will return an error if the ENV `EVE_USER_AGENT` is not set.
-    /// Besides that it will return an error if the client could not be
-    /// constructed.
-    /// See the returned error for more details.
+    /// * `store` -> Store to use
     ///
     /// # Returns
     ///
-    /// New instance of the [EveAuthClient]
+    /// The client using the given store
     ///
-    pub fn new(refresh_token: String) -> Result<Self, ConnectError> {
-        let user_agent =
-            std::env::var(Self::ENV_USER_AGENT).map_err(|_| ConnectError::env_user_agent())?;
-
-        let client = Client::builder()
-            .user_agent(user_agent)
-            .pool_idle_timeout(None)
-            .build()
-            .map_err(ConnectError::CouldNotConstructClient)?;
-
-        Ok(Self {
-            client: client,
-            refresh_token: refresh_token,
-            access_token: Arc::new(Mutex::new(None)),
-        })
+    pub fn with_token_store<S>(mut self, store: S) -> Self
+    where
+        S: TokenStore + 'static,
+    {
+        self.token_store = Some(Arc::new(store));
+        self
     }
 
     /// Consutructs a new [EveAuthClient] with an existing `access_token`.
     ///
+    /// The expiry of the `access_token` is read from its `exp` claim, so that
+    /// it is refreshed in time. If the claim could not be read, the token is
+    /// used until the EVE-API rejects it.
+    ///
     /// # Requirements
     ///
     /// The ENV `EVE_USER_AGENT` must be set.
@@ -434,10 +1324,6 @@ impl EveAuthClient {
     /// * `access_token`  -> Access token fromt he EVE-API
     /// * `refresh_token` -> Refresh token from the EVE-API
     ///
-    /// # Panics
-    ///
-    /// Panics if the [Mutex] is not exclusive.
-    ///
     /// # Errors
     ///
     /// The function will return an error if the ENV `EVE_USER_AGENT` is not set.
@@ -449,16 +1335,15 @@ impl EveAuthClient {
     ///
     /// New instance of the [EveAuthClient]
     ///
-    #[allow(clippy::unwrap_in_result)]
     pub fn with_access_token(
         access_token: String,
         refresh_token: String,
     ) -> Result<Self, ConnectError> {
-        let s = Self::new(refresh_token)?;
-        #[allow(clippy::unwrap_used)]
-        {
-            *s.access_token.lock().unwrap() = Some(access_token);
-        }
+        let mut tokens = TokenState::new(refresh_token.clone());
+        tokens.set_access_token(access_token, None);
+
+        let mut s = Self::new(refresh_token)?;
+        s.tokens = Arc::new(Mutex::new(tokens));
         Ok(s)
     }
 
@@ -472,7 +1357,7 @@ impl EveAuthClient {
     /// # Errors
     ///
     /// The function will return an error if either the ENV `EVE_CALLBACK`,
-    /// the ENV `EVE_CLIENT_ID` or ENV `EVE_CALLBACK` are not set.
+    /// the ENV `EVE_CLIENT_ID` or ENV `EVE_SECRET_KEY` are not set.
     ///
     /// # Usage
     ///
@@ -490,67 +1375,141 @@ impl EveAuthClient {
     ///
     #[tracing::instrument(level = "debug")]
     pub fn auth_uri(state: &str, scope: Option<&str>) -> Result<Url, ConnectError> {
-        let scope = if let Some(x) = scope {
-            x
-        } else {
-            Self::DEFAULT_SCOPE
-        };
-
-        let mut url = Url::parse(Self::EVE_LOGIN_URL).map_err(|_| ConnectError::UrlParseError)?;
-
-        let callback =
-            std::env::var(Self::ENV_CALLBACK).map_err(|_| ConnectError::env_callback())?;
-        let client_id =
-            std::env::var(Self::ENV_CLIENT_ID).map_err(|_| ConnectError::env_client_id())?;
-        let _ = std::env::var(Self::ENV_SECRET_KEY).map_err(|_| ConnectError::env_secret_key())?;
-
-        url.query_pairs_mut()
-            .append_pair("response_type", "code")
-            .append_pair("redirect_uri", &callback)
-            .append_pair("client_id", &client_id)
-            .append_pair("scope", scope)
-            .append_pair("state", state);
-        Ok(url)
+        EveClientBuilder::default().auth_uri(state, scope)
     }
 
-    /// Gets a new `access_token` using the `refresh_token`.
+    /// Gets a valid `access_token`, requesting a new one using the
+    /// `refresh_token` if necessary.
+    ///
+    /// A new `access_token` is requested if there is none, if it expires
+    /// soon or if it equals `rejected`. The lock on the tokens is held while
+    /// requesting, so concurrent requests wait for a single refresh and
+    /// use its result. The refresh is send with the client of the
+    /// [EveAuthClient], so it has the same user agent and connection pool.
+    ///
+    /// If the EVE-SSO returns a new `refresh_token`, it replaces the old one
+    /// and the [TokenStore] is notified.
     ///
     /// [More information](https://docs.esi.evetech.net/docs/sso/refreshing_access_tokens.html)
     ///
+    /// # Params
+    ///
+    /// * `rejected` -> `access_token` that the EVE-API did not accept
+    ///
     /// # Errors
     ///
     /// If the retrieving of an `access_token` fails the function will return
     /// an error
     ///
-    async fn refresh_token(&self) -> Result<(), ConnectError> {
+    /// # Returns
+    ///
+    /// A valid `access_token`
+    ///
+    async fn access_token_or_refresh(&self, rejected: Option<&str>) -> Result<String, ConnectError> {
+        let mut tokens = self.tokens.lock().await;
+        if let Some(x) = tokens.valid_access_token() {
+            if Some(x) != rejected {
+                return Ok(x.into());
+            }
+        }
+
         let mut map = HashMap::new();
         map.insert("grant_type", "refresh_token");
-        map.insert("refresh_token", &self.refresh_token);
+        map.insert("refresh_token", tokens.refresh_token.as_str());
+        let token = self.token.get_token(&self.client, map).await?;
+
+        tokens.set_access_token(token.access_token.clone(), Some(token.expires_in));
+        if token.refresh_token != tokens.refresh_token {
+            let previous = std::mem::replace(
+                &mut tokens.refresh_token,
+                token.refresh_token.clone()
+            );
+
+            if let Some(store) = self.token_store.as_ref() {
+                store.store(&previous, &token.refresh_token).await;
+            }
+        }
 
-        let token = Self::get_token(map).await?;
+        Ok(token.access_token)
+    }
 
-        #[allow(clippy::unwrap_used)]
-        {
-            *self.access_token.lock().unwrap() = Some(token.access_token);
+    /// Sends the request created by `request` with the current
+    /// `access_token`.
+    ///
+    /// If the EVE-API answers with [StatusCode::UNAUTHORIZED] or says that
+    /// the token is expired, a new `access_token` is requested and the
+    /// request is send once more. All other errors, for example a
+    /// [StatusCode::FORBIDDEN] because of missing roles, are returned
+    /// directly, as a new `access_token` would not change the answer.
+    ///
+    /// # Params
+    ///
+    /// * `path`    -> Full url of the request
+    /// * `scope`   -> Scope the `access_token` needs
+    /// * `request` -> Creates the request for the given `access_token`
+    ///
+    /// # Errors
+    ///
+    /// Same as [RetryPolicy::send], additionally fails if no new
+    /// `access_token` could be retrieved or if it misses the scope.
+    ///
+    /// # Returns
+    ///
+    /// Response of the request, ready to work with
+    ///
+    async fn send_authenticated<F>(
+        &self,
+        path:    &str,
+        scope:   Option<&str>,
+        request: F,
+    ) -> Result<Response, ConnectError>
+    where
+        F: Fn(&str) -> reqwest::RequestBuilder,
+    {
+        let mut token = self.access_token_or_refresh(None).await?;
+        if let Some(scope) = scope {
+            self.ensure_scope(scope).await?;
         }
+        let mut refreshed = false;
 
-        Ok(())
+        loop {
+            let response = self.retry.send(path, || request(&token)).await;
+
+            match response {
+                Err(ConnectError::EsiError(e))
+                    if !refreshed &&
+                       (e.status == StatusCode::UNAUTHORIZED ||
+                        e.error.to_lowercase().contains("token is expired")) => {
+                    refreshed = true;
+                    token = self.access_token_or_refresh(Some(&token)).await?;
+                }
+                Err(e) => {
+                    let character_id = self.tokens.lock().await.character_id;
+                    return Err(e.with_character_id(character_id));
+                }
+                x => return x,
+            }
+        }
     }
 
     /// Sends a GET request to the given path setting the current `access_token`
     /// as `bearer_auth`.
     ///
-    /// If a request failes with a non successfull status, it will retry the
-    /// request again, up to 3 times.
+    /// Failed requests are retried according to the [RetryPolicy] of the
+    /// client.
     ///
     /// # Params
     ///
-    /// * `path` -> Path for the request
+    /// * `path`          -> Path for the request
+    /// * `scope`         -> Scope the `access_token` needs
+    /// * `etag`          -> Cached `ETag`, send as `If-None-Match`
+    /// * `last_modified` -> Cached `Last-Modified`, send as `If-Modified-Since`
     ///
     /// # Errors
     ///
-    /// The function errors if too many request failed, or if there is a general
-    /// error with the requesting library.
+    /// The function errors if the EVE-API returns an error that is not
+    /// retried, all retries failed, or if there is a general error with the
+    /// requesting library.
     ///
     /// If the EVE-API returns [StatusCode::UNAUTHORIZED] it will attempt to
     /// retriev a new `access_token`. If that fails an error is returned.
@@ -560,72 +1519,43 @@ impl EveAuthClient {
     /// Response of the request, ready to work with
     ///
     #[tracing::instrument(level = "debug")]
-    async fn send(&self, path: &str) -> Result<Response, ConnectError> {
-        let access_token = {
-            #[allow(clippy::unwrap_used)]
-            self.access_token.lock().unwrap().clone()
-        };
-        let access_token = if access_token.is_none() {
-            self.refresh_token().await?;
-            #[allow(clippy::unwrap_used)]
-            self.access_token.lock().unwrap().clone()
-        } else {
-            access_token
-        };
-
-        let mut retry_counter = 0usize;
-
-        loop {
-            if retry_counter == 3 {
-                tracing::error!("Too many retries requesting {}.", path);
-                return Err(ConnectError::TooManyRetries(path.into()));
-            }
-
-            let token = access_token
-                .as_ref()
-                .expect("We check but somehow the access_token is still None");
-            let response = self
-                .client
-                .get(path)
-                .bearer_auth(token)
-                .send()
-                .await
-                .map_err(ConnectError::ReqwestError)?;
-
-            if response.status() == StatusCode::FORBIDDEN ||
-               response.status() == StatusCode::UNAUTHORIZED {
-                self.refresh_token().await?;
-                continue;
+    async fn send(
+        &self,
+        path: &str,
+        scope: Option<&str>,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> Result<Response, ConnectError> {
+        self.send_authenticated(path, scope, |token| {
+            let mut request = self.client.get(path).bearer_auth(token);
+            if let Some(x) = etag.as_ref() {
+                request = request.header("If-None-Match", x);
             }
-
-            if !response.status().is_success() {
-                retry_counter += 1;
-                tracing::error!(
-                    { retry = retry_counter, status = response.status().as_u16() },
-                    "Fetch resulted in non successful status code.",
-                );
-                continue;
+            if let Some(x) = last_modified.as_ref() {
+                request = request.header("If-Modified-Since", x);
             }
-
-            return Ok(response);
-        }
+            request
+        })
+        .await
     }
 
     /// Sends a POST request to the given path setting the current
     /// `access_token` as `bearer_auth`.
     ///
-    /// If a request failes with a non successfull status, it will retry the
-    /// request again, up to 3 times.
+    /// Failed requests are retried according to the [RetryPolicy] of the
+    /// client.
     ///
     /// # Params
     ///
-    /// * `data` -> Data to send in the body
-    /// * `path` -> Path for the request
+    /// * `data`  -> Data to send in the body
+    /// * `path`  -> Path for the request
+    /// * `scope` -> Scope the `access_token` needs
     ///
     /// # Errors
     ///
-    /// The function errors if too many request failed, or if there is a general
-    /// error with the requesting library.
+    /// The function errors if the EVE-API returns an error that is not
+    /// retried, all retries failed, or if there is a general error with the
+    /// requesting library.
     ///
     /// If the EVE-API returns [StatusCode::UNAUTHORIZED] it will attempt to
     /// retriev a new `access_token`. If that fails an error is returned.
@@ -635,83 +1565,224 @@ impl EveAuthClient {
     /// Response of the request, ready to work with
     ///
     #[tracing::instrument(level = "debug")]
-    async fn send_post<R>(&self, data: R, path: &str) -> Result<Response, ConnectError>
+    async fn send_post<R>(
+        &self,
+        data:  R,
+        path:  &str,
+        scope: Option<&str>,
+    ) -> Result<Response, ConnectError>
     where
         R: Debug + Serialize + Send + Sync,
     {
-        let access_token = {
-            #[allow(clippy::unwrap_used)]
-            self.access_token.lock().unwrap().clone()
-        };
-        let access_token = if access_token.is_none() {
-            self.refresh_token().await?;
-            #[allow(clippy::unwrap_used)]
-            self.access_token.lock().unwrap().clone()
-        } else {
-            access_token
+        self.send_authenticated(path, scope, |token| {
+            self.client.post(path).json(&data).bearer_auth(token)
+        })
+        .await
+    }
+
+    /// Gets the response for the given url, either from the cache or by
+    /// sending a request.
+    ///
+    /// # Params
+    ///
+    /// * `url`      -> Full url for the request
+    /// * `scope`    -> Scope the `access_token` needs
+    /// * `duration` -> Time a new response is cached, [None] for `Expires`
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request fails
+    ///
+    /// # Returns
+    ///
+    /// Cached or new response
+    ///
+    async fn send_cached(
+        &self,
+        url:      &str,
+        scope:    Option<&str>,
+        duration: Option<Duration>,
+    ) -> Result<cache::CacheEntry, ConnectError> {
+        #[cfg(feature = "fixtures")]
+        if let Some(x) = self.fixtures.as_ref() {
+            let key = self.fixture_key(x, url).await?;
+            return x.resolve("GET", &key, || async {
+                cache::CacheEntry::from_response(self.send(url, scope, None, None).await?).await
+            })
+            .await;
+        }
+
+        // Responses depend on the roles of the character, so they are only
+        // shared with clients of the same character
+        let uncached: Option<SharedCache> = None;
+        let (cache, key) = match self.cache_character().await? {
+            Some(x) => (&self.cache, format!("{}#{}", url, x)),
+            None    => (&uncached, url.to_string()),
         };
 
-        let mut retry_counter = 0usize;
+        cache::cached_for(cache, &key, duration, |etag, last_modified| {
+            self.send(url, scope, etag, last_modified)
+        })
+        .await
+    }
 
-        loop {
-            if retry_counter == 3 {
-                tracing::error!("Too many retries requesting {}.", path);
-                return Err(ConnectError::TooManyRetries(path.into()));
-            }
+    /// Gets the character the cached responses of this client belong to.
+    ///
+    /// If the client has no `access_token` yet, one is requested so that
+    /// the character is known before the cache is read.
+    ///
+    /// # Errors
+    ///
+    /// If no `access_token` could be retrieved
+    ///
+    /// # Returns
+    ///
+    /// - [None] if there is no cache or the character can not be read from
+    ///   the `access_token`, in that case the cache must not be used
+    /// - The character of the `access_token`
+    ///
+    async fn cache_character(&self) -> Result<Option<CharacterId>, ConnectError> {
+        if self.cache.is_none() {
+            return Ok(None);
+        }
 
-            let token = access_token
-                .as_ref()
-                .expect("We check but somehow the access_token is still None");
-            let response = self
-                .client
-                .post(path)
-                .json(&data)
-                .bearer_auth(token)
-                .send()
-                .await
-                .map_err(ConnectError::ReqwestError)?;
-
-            if response.status() == StatusCode::UNAUTHORIZED {
-                self.refresh_token().await?;
-                continue;
-            }
+        self.access_token_or_refresh(None).await?;
+        Ok(self.tokens.lock().await.character_id)
+    }
 
-            if !response.status().is_success() {
-                retry_counter += 1;
-                tracing::error!(
-                    { retry = retry_counter, status = response.status().as_u16() },
-                    "Fetch resulted in non successful status code.",
-                );
-                continue;
+    /// Creates the key of a request in the fixtures.
+    ///
+    /// Like in the cache, responses are stored per character. While
+    /// recording the character is read from the `access_token`, while
+    /// replaying the character of the fixtures is used, as no
+    /// `access_token` is requested.
+    ///
+    /// # Params
+    ///
+    /// * `fixtures` -> Fixtures the request is resolved with
+    /// * `key`      -> Full url of the request, including the body hash for POST
+    ///
+    /// # Errors
+    ///
+    /// If no `access_token` could be retrieved while recording
+    ///
+    /// # Returns
+    ///
+    /// Key in the format `key#character_id`, or the given key if the
+    /// character is not known
+    ///
+    #[cfg(feature = "fixtures")]
+    async fn fixture_key(&self, fixtures: &Fixtures, key: &str) -> Result<String, ConnectError> {
+        let character_id = match fixtures.mode() {
+            FixtureMode::Record => {
+                self.access_token_or_refresh(None).await?;
+                self.tokens.lock().await.character_id
             }
+            FixtureMode::Replay => fixtures
+                .replay_character()
+                .or(self.tokens.lock().await.character_id),
+        };
 
-            return Ok(response);
+        Ok(match character_id {
+            Some(x) => format!("{}#{}", key, x),
+            None    => key.to_string(),
+        })
+    }
+
+    /// Sends a POST request and parses the response.
+    ///
+    /// POST requests are never cached, but they are recorded and replayed
+    /// like all other requests if fixtures are set.
+    ///
+    /// # Params
+    ///
+    /// * `data`  -> Data to send in the body
+    /// * `url`   -> Full url for the request
+    /// * `scope` -> Scope the `access_token` needs
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request fails or the response could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// Parsed response
+    ///
+    async fn post_json<R, T>(
+        &self,
+        data:  R,
+        url:   &str,
+        scope: Option<&str>,
+    ) -> Result<T, ConnectError>
+    where
+        R: Debug + Serialize + Send + Sync,
+        T: DeserializeOwned,
+    {
+        #[cfg(feature = "fixtures")]
+        if let Some(x) = self.fixtures.as_ref() {
+            let body = serde_json::to_vec(&data).map_err(ConnectError::ParseError)?;
+            let key = self.fixture_key(x, &cache::post_key(url, &body)).await?;
+            let entry = x.resolve("POST", &key, || async {
+                cache::CacheEntry::from_response(self.send_post(data, url, scope).await?).await
+            })
+            .await?;
+            return serde_json::from_slice::<T>(&entry.body).map_err(ConnectError::ParseError);
+        }
+
+        self
+            .send_post(data, url, scope)
+            .await?
+            .json::<T>()
+            .await
+            .map_err(|e| ConnectError::RequestFailed(RequestError::new(url, e)))
+    }
+
+    /// Checks that the `access_token` was granted the given scope.
+    ///
+    /// If there is no `access_token` yet or its scopes can not be read, the
+    /// EVE-API decides.
+    ///
+    /// # Params
+    ///
+    /// * `scope` -> Required scope
+    ///
+    /// # Errors
+    ///
+    /// If the scope was not granted
+    ///
+    async fn ensure_scope(&self, scope: &str) -> Result<(), ConnectError> {
+        let tokens = self.tokens.lock().await;
+        match tokens.scopes.as_ref() {
+            Some(x) if !x.iter().any(|x| x == scope) => {
+                Err(ConnectError::MissingScope(scope.into()))
+            }
+            _ => Ok(()),
         }
     }
 
-    /// Extract the page header from the give [reqwest::Response].
+    /// Builds the url of a [Route].
+    ///
+    /// If the scopes of the `access_token` are already known, a route
+    /// whose scope was not granted is rejected right away. Otherwise the
+    /// scope is checked as soon as the `access_token` is retrieved.
     ///
     /// # Params
     ///
-    /// * `response` -> Respone to get the header from
+    /// * `route` -> Route to request
+    ///
+    /// # Errors
+    ///
+    /// If the route is invalid or the scope was not granted
     ///
     /// # Returns
     ///
-    /// - If the header is not present a 0 is returned
-    /// - If the header exists, it will try to parse it, if that fails a 0 is
-    ///   is returned
+    /// Full url of the route
     ///
-    #[tracing::instrument(level = "debug")]
-    fn page_count(&self, response: &Response) -> u8 {
-        let headers = response.headers();
-        if let Some(x) = headers.get("x-pages") {
-            x.to_str()
-                .unwrap_or_default()
-                .parse::<u8>()
-                .unwrap_or_default()
-        } else {
-            0u8
+    async fn route_url(&self, route: &Route) -> Result<String, ConnectError> {
+        if let Some(scope) = route.endpoint().required_scope() {
+            self.ensure_scope(scope).await?;
         }
+        Ok(format!("{}/{}", self.esi_url, route.path()?))
     }
 }
 
@@ -728,45 +1799,27 @@ impl RequestClient for EveAuthClient {
     where
         T: DeserializeOwned,
     {
-        let path = format!("{}/{}", Self::EVE_API_URL, path);
-        let json = self
-            .send(&path)
-            .await?
-            .json::<T>()
-            .await
-            .map_err(ConnectError::ReqwestError)?;
-        Ok(json)
+        let url = format!("{}/{}", self.esi_url, path);
+        let entry = self.send_cached(&url, None, None).await?;
+        serde_json::from_slice::<T>(&entry.body).map_err(ConnectError::ParseError)
     }
 
     #[tracing::instrument(level = "debug")]
     async fn fetch_page<T>(&self, path: &str) -> Result<Vec<T>, ConnectError>
     where
-        T: DeserializeOwned + Send,
+        T: DeserializeOwned + Send + 'static,
     {
-        let path = format!("{}/{}", Self::EVE_API_URL, path);
-        let response = self.send(&path).await?;
-
-        let pages = self.page_count(&response);
-
-        let mut fetched_data: Vec<T> = Vec::new();
-        let data = response
-            .json::<Vec<T>>()
-            .await
-            .map_err(ConnectError::ReqwestError)?;
-        fetched_data.extend(data);
-
-        for page in 2..=pages {
-            let next_page = self
-                .send(&format!("{}?page={}", path, page))
-                .await?
-                .json::<Vec<T>>()
-                .await
-                .map_err(ConnectError::ReqwestError)?;
-
-            fetched_data.extend(next_page);
-        }
+        self.fetch_page_stream(path).try_collect().await
+    }
 
-        Ok(fetched_data)
+    fn fetch_page_stream<'a, T>(&'a self, path: &str) -> BoxStream<'a, Result<T, ConnectError>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let url = format!("{}/{}", self.esi_url, path);
+        pagination::stream(url, self.page_concurrency, move |url| async move {
+            self.send_cached(&url, None, None).await
+        })
     }
 
     #[tracing::instrument(level = "debug")]
@@ -775,44 +1828,81 @@ impl RequestClient for EveAuthClient {
         R: Debug + Serialize + Send + Sync,
         T: DeserializeOwned,
     {
-        let path = format!("{}/{}", Self::EVE_API_URL, path);
-        let json = self
-            .send_post(data, &path)
-            .await?
-            .json::<T>()
+        let url = format!("{}/{}", self.esi_url, path);
+        self.post_json(data, &url, None).await
+    }
+
+    #[tracing::instrument(level = "debug")]
+    async fn fetch_route<T>(&self, route: &Route) -> Result<T, ConnectError>
+    where
+        T: DeserializeOwned,
+    {
+        self
+            .fetch_route_expires::<T>(route)
             .await
-            .map_err(ConnectError::ReqwestError)?;
-        Ok(json)
+            .map(|(x, _)| x)
     }
-}
 
-/// Decoded access token
-#[derive(Debug, Deserialize)]
-pub struct EveOAuthPayload {
-    /// List of all permissions that where granted
-    pub scp: Scp,
-    /// User identification
-    pub sub: String,
-}
+    #[tracing::instrument(level = "debug")]
+    async fn fetch_route_expires<T>(
+        &self,
+        route: &Route,
+    ) -> Result<(T, Option<DateTime<Utc>>), ConnectError>
+    where
+        T: DeserializeOwned,
+    {
+        if route.endpoint().pagination() == Pagination::Pages {
+            return Err(ConnectError::InvalidRoute(
+                format!("{} is paginated", route.endpoint().path())
+            ));
+        }
 
-/// Parses the scp field in the payload
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-pub enum Scp {
-    /// The permission is a single String
-    Str(String),
-    /// The permission is a list of strings
-    StrArray(Vec<String>),
-}
+        let url = self.route_url(route).await?;
+        let endpoint = route.endpoint();
+        let entry = self.send_cached(&url, endpoint.required_scope(), endpoint.cache_duration()).await?;
+        serde_json::from_slice::<T>(&entry.body)
+            .map(|x| (x, entry.expires))
+            .map_err(ConnectError::ParseError)
+    }
 
-impl Scp {
-    /// Gets the inner string or vec of string and returns it as vec
-    pub fn into_vec(self) -> Vec<String> {
-        match self {
-            Self::Str(x) => vec![x],
-            Self::StrArray(x) => x,
+    #[tracing::instrument(level = "debug")]
+    async fn fetch_route_pages<T>(&self, route: &Route) -> Result<Vec<T>, ConnectError>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        match route.endpoint().pagination() {
+            Pagination::Pages => self.fetch_route_stream(route).try_collect().await,
+            Pagination::None  => self.fetch_route::<Vec<T>>(route).await,
         }
     }
+
+    fn fetch_route_stream<'a, T>(&'a self, route: &Route) -> BoxStream<'a, Result<T, ConnectError>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let route = route.clone();
+        let scope = route.endpoint().required_scope();
+        let duration = route.endpoint().cache_duration();
+
+        stream::once(async move { self.route_url(&route).await })
+            .flat_map(move |url| match url {
+                Ok(url) => pagination::stream(url, self.page_concurrency, move |url| async move {
+                    self.send_cached(&url, scope, duration).await
+                }),
+                Err(e) => stream::iter(vec![Err(e)]).boxed(),
+            })
+            .boxed()
+    }
+
+    #[tracing::instrument(level = "debug")]
+    async fn post_route<R, T>(&self, data: R, route: &Route) -> Result<T, ConnectError>
+    where
+        R: Debug + Serialize + Send + Sync,
+        T: DeserializeOwned,
+    {
+        let url = self.route_url(route).await?;
+        self.post_json(data, &url, route.endpoint().required_scope()).await
+    }
 }
 
 /// Parsed version of the response from EVE after a successfull login.
@@ -830,6 +1920,24 @@ pub struct EveOAuthToken {
 }
 
 impl EveOAuthToken {
+    /// Validates the access token and returns its payload.
+    ///
+    /// # Params
+    ///
+    /// * `validator` -> Validator holding the keys of the EVE-SSO
+    ///
+    /// # Errors
+    ///
+    /// If the token is not valid, see [EveJwtValidator::validate]
+    ///
+    /// # Returns
+    ///
+    /// Validated payload of the access token
+    ///
+    pub fn validate(&self, validator: &EveJwtValidator) -> Result<EveOAuthPayload, ConnectError> {
+        validator.validate(&self.access_token)
+    }
+
     /// Extracts the payload from the access token
     ///
     /// # Errors
@@ -840,11 +1948,13 @@ impl EveOAuthToken {
     ///
     /// Payload of the access token
     ///
+    #[deprecated(note = "The token is not verified, use `EveOAuthToken::validate` instead")]
     pub fn payload(&self) -> Result<EveOAuthPayload, ConnectError> {
         let payload = self.access_token.to_string();
         let payload = payload.split('.').collect::<Vec<_>>();
         let payload = payload.get(1).copied().unwrap_or_default();
-        let decoded = base64::decode(payload).map_err(ConnectError::OAuthPayloadDecode)?;
+        let decoded = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+            .map_err(ConnectError::OAuthPayloadDecode)?;
         serde_json::from_slice(&decoded).map_err(ConnectError::OAuthParseError)
     }
 
@@ -859,13 +1969,214 @@ impl EveOAuthToken {
     ///
     /// The character id
     ///
+    #[deprecated(note = "The token is not verified, use `EveOAuthToken::validate` instead")]
+    #[allow(deprecated)]
     pub fn character_id(&self) -> Result<CharacterId, ConnectError> {
-        let character_id = self
-            .payload()?
-            .sub
-            .replace("CHARACTER:EVE:", "")
-            .parse::<i32>()
-            .map_err(ConnectError::OAuthParseCharacterId)?;
-        Ok(character_id.into())
+        self.payload()?.character_id()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod client_tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::{EveCharacterService, EveCorporationService, MemoryCache};
+    use hyper::Method;
+
+    #[tokio::test]
+    async fn token_exchange_uses_sso_url() {
+        let esi = MockEsi::start().await;
+        let builder = esi.builder();
+
+        let validator = builder.jwt_validator().await.unwrap();
+        let token = builder.access_token("code").await.unwrap();
+        assert_eq!(token.validate(&validator).unwrap().character_id().unwrap(), CHARACTER_ID);
+        assert_eq!(token.refresh_token, REFRESH_TOKEN);
+
+        let url = builder.auth_uri("state", None).unwrap();
+        assert!(url.as_str().starts_with(&format!("{}/v2/oauth/authorize", esi.url())));
+    }
+
+    #[tokio::test]
+    async fn esi_errors_are_typed() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        esi.mock(
+            Method::GET,
+            &format!("/latest/characters/{}/industry/jobs", CHARACTER_ID),
+            MockResponse::error(403, "Character does not have required role(s)"),
+        );
+
+        let error = EveCharacterService::new(CHARACTER_ID)
+            .industry_jobs(&client)
+            .await
+            .unwrap_err();
+        match error {
+            ConnectError::EsiError(e) => {
+                assert_eq!(e.status, StatusCode::FORBIDDEN);
+                assert_eq!(e.error, "Character does not have required role(s)");
+            }
+            e => panic!("Unexpected error {:?}", e),
+        }
+
+        // missing roles are not fixed by a new token
+        let requests = esi.requests();
+        assert_eq!(requests.iter().filter(|x| x.starts_with("POST /v2/oauth/token")).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_token_is_refreshed_once() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        esi.mock(
+            Method::GET,
+            &format!("/latest/characters/{}/industry/jobs", CHARACTER_ID),
+            MockResponse::error(403, "token is expired"),
+        );
+
+        EveCharacterService::new(CHARACTER_ID)
+            .industry_jobs(&client)
+            .await
+            .unwrap_err();
+
+        // the first request and the retry with a refreshed token
+        let requests = esi.requests();
+        assert_eq!(requests.iter().filter(|x| x.starts_with("POST /v2/oauth/token")).count(), 2);
+    }
+
+    /// Response of the token endpoint with the given refresh token and lifetime
+    fn token_response(refresh_token: &str, expires_in: i32) -> MockResponse {
+        MockResponse::json(
+            serde_json::json!({
+                "access_token":  sign(&claims(CHARACTER_ID)),
+                "token_type":    "Bearer",
+                "expires_in":    expires_in,
+                "refresh_token": refresh_token,
+            })
+            .to_string(),
+        )
+    }
+
+    /// Remembers every call
+    #[derive(Clone, Default)]
+    struct RecordingStore(Arc<std::sync::Mutex<Vec<(String, String)>>>);
+
+    #[async_trait]
+    impl TokenStore for RecordingStore {
+        async fn store(&self, previous: &str, refresh_token: &str) {
+            self.0.lock().unwrap().push((previous.into(), refresh_token.into()));
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_refresh_once() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let service = EveCharacterService::new(CHARACTER_ID);
+
+        let (a, b, c) = tokio::join!(
+            service.industry_jobs(&client),
+            service.blueprints(&client),
+            service.assets(&client),
+        );
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+
+        let requests = esi.requests();
+        assert_eq!(requests.iter().filter(|x| x.starts_with("POST /v2/oauth/token")).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn cached_responses_are_per_character() {
+        let esi = MockEsi::start().await;
+        let cache = MemoryCache::new();
+        let service = EveCorporationService::new(CORPORATION_ID);
+        let path = format!("/latest/corporations/{}/structures", CORPORATION_ID);
+        let expires = (chrono::Utc::now() + chrono::Duration::minutes(5)).to_rfc2822();
+        esi.mock(
+            Method::GET,
+            &path,
+            MockResponse::json("[]").header("expires", &expires),
+        );
+        let path = format!("GET {}", path);
+
+        let director = esi
+            .builder()
+            .cache(cache.clone())
+            .build_auth(REFRESH_TOKEN.into())
+            .unwrap();
+        service.structures(&director).await.unwrap();
+        service.structures(&director).await.unwrap();
+        assert_eq!(esi.requests().iter().filter(|x| x.starts_with(&path)).count(), 1);
+
+        // another character of the same corporation
+        esi.mock(
+            Method::POST,
+            "/v2/oauth/token",
+            MockResponse::json(
+                serde_json::json!({
+                    "access_token":  sign(&claims(CharacterId(2_117_000_002))),
+                    "token_type":    "Bearer",
+                    "expires_in":    1199,
+                    "refresh_token": "member_refresh_token",
+                })
+                .to_string(),
+            ),
+        );
+        let member = esi
+            .builder()
+            .cache(cache)
+            .build_auth("member_refresh_token".into())
+            .unwrap();
+        service.structures(&member).await.unwrap();
+        assert_eq!(esi.requests().iter().filter(|x| x.starts_with(&path)).count(), 2);
+    }
+
+    #[tokio::test]
+    async fn expiring_token_is_refreshed() {
+        let esi = MockEsi::start().await;
+        esi.mock(Method::POST, "/v2/oauth/token", token_response(REFRESH_TOKEN, 30));
+        let client = esi.auth_client().unwrap();
+        let service = EveCharacterService::new(CHARACTER_ID);
+
+        service.industry_jobs(&client).await.unwrap();
+        service.industry_jobs(&client).await.unwrap();
+
+        let requests = esi.requests();
+        assert_eq!(requests.iter().filter(|x| x.starts_with("POST /v2/oauth/token")).count(), 2);
+    }
+
+    #[test]
+    fn access_token_expiry_is_read_from_token() {
+        let mut claims = claims(CHARACTER_ID);
+        let mut tokens = TokenState::new(REFRESH_TOKEN.into());
+        tokens.set_access_token(sign(&claims), None);
+        assert!(tokens.valid_access_token().is_some());
+
+        claims["exp"] = serde_json::json!(chrono::Utc::now().timestamp() + 30);
+        tokens.set_access_token(sign(&claims), None);
+        assert!(tokens.valid_access_token().is_none());
+    }
+
+    #[tokio::test]
+    async fn rotated_refresh_token_is_stored() {
+        let esi = MockEsi::start().await;
+        esi.mock(Method::POST, "/v2/oauth/token", token_response("rotated_refresh_token", 1199));
+        let store = RecordingStore::default();
+        let client = esi
+            .builder()
+            .token_store(store.clone())
+            .build_auth(REFRESH_TOKEN.into())
+            .unwrap();
+        let service = EveCharacterService::new(CHARACTER_ID);
+
+        service.industry_jobs(&client).await.unwrap();
+        service.blueprints(&client).await.unwrap();
+
+        assert_eq!(
+            *store.0.lock().unwrap(),
+            vec![(REFRESH_TOKEN.to_string(), "rotated_refresh_token".to_string())]
+        );
+        assert_eq!(client.tokens.lock().await.refresh_token, "rotated_refresh_token");
     }
 }
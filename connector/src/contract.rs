@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{CharacterId, ContractId, CorporationId, LocationId, TypeId};
+
+/// Contract of a character or corporation
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Contract {
+    /// Unique ID of the contract
+    pub contract_id:           ContractId,
+    /// Kind of contract
+    #[serde(rename = "type")]
+    pub typ:                   ContractType,
+    /// Current status of the contract
+    pub status:                ContractStatus,
+    /// Who can accept the contract
+    pub availability:          ContractAvailability,
+
+    /// Character that created the contract
+    pub issuer_id:             CharacterId,
+    /// Corporation of the issuer
+    pub issuer_corporation_id: CorporationId,
+    /// Character or corporation the contract is assigned to, `0` if public
+    pub assignee_id:           i32,
+    /// Character or corporation that accepted the contract, `0` if not yet
+    /// accepted
+    pub acceptor_id:           i32,
+    /// True if the contract was issued on behalf of the corporation
+    pub for_corporation:       bool,
+
+    /// Time the contract was created
+    pub date_issued:           DateTime<Utc>,
+    /// Time the contract expires
+    pub date_expired:          DateTime<Utc>,
+    /// Time the contract was accepted
+    #[serde(default)]
+    pub date_accepted:         Option<DateTime<Utc>>,
+    /// Time the contract was completed
+    #[serde(default)]
+    pub date_completed:        Option<DateTime<Utc>>,
+
+    /// Title of the contract
+    #[serde(default)]
+    pub title:                 Option<String>,
+    /// Price of the contract, for item exchanges and auctions
+    #[serde(default)]
+    pub price:                 Option<f64>,
+    /// Buyout price of an auction
+    #[serde(default)]
+    pub buyout:                Option<f64>,
+    /// Reward of a courier contract
+    #[serde(default)]
+    pub reward:                Option<f64>,
+    /// Collateral of a courier contract
+    #[serde(default)]
+    pub collateral:            Option<f64>,
+    /// Volume of all items in the contract
+    #[serde(default)]
+    pub volume:                Option<f64>,
+    /// Number of days to complete a courier contract
+    #[serde(default)]
+    pub days_to_complete:      Option<i32>,
+    /// Location the items are handed over
+    #[serde(default)]
+    pub start_location_id:     Option<LocationId>,
+    /// Destination of a courier contract
+    #[serde(default)]
+    pub end_location_id:       Option<LocationId>,
+}
+
+/// Kinds of contracts
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractType {
+    /// Items are exchanged for ISK or other items
+    ItemExchange,
+    /// Items are sold to the highest bidder
+    Auction,
+    /// Items are transported to another location
+    Courier,
+    /// Items are lent
+    Loan,
+    /// Kind that is not known
+    #[serde(other)]
+    Unknown,
+}
+
+/// Possible states of a contract
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractStatus {
+    /// Waiting to be accepted
+    Outstanding,
+    /// Accepted courier contract that is not delivered yet
+    InProgress,
+    /// Completed, the issuer already collected the result
+    FinishedIssuer,
+    /// Completed, the contractor already collected the result
+    FinishedContractor,
+    /// Completed
+    Finished,
+    /// Cancelled by the issuer
+    Cancelled,
+    /// Rejected by the assignee
+    Rejected,
+    /// Courier contract that was not delivered in time
+    Failed,
+    /// Deleted by the issuer
+    Deleted,
+    /// Reversed by the game masters
+    Reversed,
+    /// State that is not known
+    #[serde(other)]
+    Unknown,
+}
+
+impl ContractStatus {
+    /// Checks if the contract was completed.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the items were handed over
+    ///
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            Self::Finished | Self::FinishedIssuer | Self::FinishedContractor
+        )
+    }
+}
+
+/// Who can accept a contract
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractAvailability {
+    /// Everyone
+    Public,
+    /// A single character
+    Personal,
+    /// Members of a corporation
+    Corporation,
+    /// Members of an alliance
+    Alliance,
+}
+
+/// Item of a contract
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ContractItem {
+    /// Unique ID of the item within the contract
+    pub record_id:    i64,
+    /// [TypeId] of the item
+    pub type_id:      TypeId,
+    /// Number of items
+    pub quantity:     i32,
+    /// True if the issuer gives the item, false if the issuer asks for it
+    pub is_included:  bool,
+    /// True if the item is not stackable, for example an assembled ship
+    pub is_singleton: bool,
+
+    /// `-1` for originals and `-2` for copies of blueprints
+    #[serde(default)]
+    pub raw_quantity: Option<i32>,
+}
+
+/// Bid on an auction
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ContractBid {
+    /// Unique ID of the bid
+    pub bid_id:    i32,
+    /// Character that placed the bid
+    pub bidder_id: CharacterId,
+    /// ISK amount of the bid
+    pub amount:    f64,
+    /// Time the bid was placed
+    pub date_bid:  DateTime<Utc>,
+}
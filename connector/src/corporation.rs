@@ -1,9 +1,70 @@
 use crate::{
-    AllianceId, AssetEntry, BlueprintEntry, ConnectError, CorporationId, EveAuthClient, EveClient,
-    IndustryJobEntry, LocationId, RequestClient,
+    AllianceId, AssetEntry, BlueprintEntry, ConnectError, Contract, ContractBid, ContractId,
+    ContractItem, CorporationId, Endpoint, EveAuthClient, EveClient,
+    IndustryJobEntry, JournalEntry, LocationId, MiningExtraction, MiningLedgerEntry,
+    MiningObserver, RequestClient, SystemId, TypeId, WalletEntry, WalletTransaction,
 };
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
 use serde::{Deserialize, Serialize};
 
+/// Public information about a corporation
+const CORPORATION_INFO: Endpoint = Endpoint::new("latest/corporations/{corporation_id}/");
+/// Assets of a corporation
+const CORPORATION_ASSETS: Endpoint = Endpoint::new("latest/corporations/{corporation_id}/assets")
+    .scope("esi-assets.read_corporation_assets.v1")
+    .paginated();
+/// Blueprints of a corporation
+const CORPORATION_BLUEPRINTS: Endpoint = Endpoint::new("latest/corporations/{corporation_id}/blueprints")
+    .scope("esi-corporations.read_blueprints.v1")
+    .paginated();
+/// Industry jobs of a corporation
+const CORPORATION_INDUSTRY_JOBS: Endpoint = Endpoint::new("latest/corporations/{corporation_id}/industry/jobs")
+    .scope("esi-industry.read_corporation_jobs.v1")
+    .paginated()
+    .query(&["include_completed"]);
+/// Wallet journal of a division of a corporation
+const CORPORATION_WALLET_JOURNAL: Endpoint = Endpoint::new("latest/corporations/{corporation_id}/wallets/{division}/journal")
+    .scope("esi-wallet.read_corporation_wallets.v1")
+    .paginated();
+/// Market transactions of a division of a corporation
+const CORPORATION_WALLET_TRANSACTIONS: Endpoint = Endpoint::new("latest/corporations/{corporation_id}/wallets/{division}/transactions")
+    .scope("esi-wallet.read_corporation_wallets.v1")
+    .query(&["from_id"]);
+/// Balances of all wallet divisions of a corporation
+const CORPORATION_WALLETS: Endpoint = Endpoint::new("latest/corporations/{corporation_id}/wallets")
+    .scope("esi-wallet.read_corporation_wallets.v1");
+/// Structures of a corporation
+const CORPORATION_STRUCTURES: Endpoint = Endpoint::new("latest/corporations/{corporation_id}/structures")
+    .scope("esi-corporations.read_structures.v1")
+    .paginated();
+/// Contracts of a corporation
+const CORPORATION_CONTRACTS: Endpoint = Endpoint::new("latest/corporations/{corporation_id}/contracts")
+    .scope("esi-contracts.read_corporation_contracts.v1")
+    .paginated();
+/// Items of a contract of a corporation
+const CORPORATION_CONTRACT_ITEMS: Endpoint = Endpoint::new("latest/corporations/{corporation_id}/contracts/{contract_id}/items")
+    .scope("esi-contracts.read_corporation_contracts.v1")
+    .paginated();
+/// Bids of an auction of a corporation
+const CORPORATION_CONTRACT_BIDS: Endpoint = Endpoint::new("latest/corporations/{corporation_id}/contracts/{contract_id}/bids")
+    .scope("esi-contracts.read_corporation_contracts.v1")
+    .paginated();
+/// Moon extractions of a corporation
+const CORPORATION_MINING_EXTRACTIONS: Endpoint = Endpoint::new("latest/corporation/{corporation_id}/mining/extractions")
+    .scope("esi-industry.read_corporation_mining.v1");
+/// Mining observers of a corporation
+const CORPORATION_MINING_OBSERVERS: Endpoint = Endpoint::new("latest/corporation/{corporation_id}/mining/observers")
+    .scope("esi-industry.read_corporation_mining.v1")
+    .paginated();
+/// Mining ledger of an observer of a corporation
+const CORPORATION_MINING_LEDGER: Endpoint = Endpoint::new("latest/corporation/{corporation_id}/mining/observers/{observer_id}")
+    .scope("esi-industry.read_corporation_mining.v1")
+    .paginated();
+/// Names of assets of a corporation
+const CORPORATION_ASSET_NAMES: Endpoint = Endpoint::new("latest/corporations/{corporation_id}/assets/names")
+    .scope("esi-assets.read_corporation_assets.v1");
+
 /// Wrapper for corporations
 pub struct EveCorporationService {
     /// Corporation id this client belongs to
@@ -11,6 +72,9 @@ pub struct EveCorporationService {
 }
 
 impl EveCorporationService {
+    /// All wallet divisions of a corporation, `1` is the master wallet
+    pub const WALLET_DIVISIONS: [u8; 7] = [1, 2, 3, 4, 5, 6, 7];
+
     /// Creates a new instance of the service
     ///
     /// # Params
@@ -37,11 +101,12 @@ impl EveCorporationService {
     /// Corporation information
     ///
     pub async fn info(&self, client: &EveClient) -> Result<CorporationInfo, ConnectError> {
-        let path = format!("latest/corporations/{}/", self.cid);
+        let route = CORPORATION_INFO
+            .route()
+            .param("corporation_id", self.cid);
         client
-            .fetch::<CorporationInfo>(&path)
+            .fetch_route::<CorporationInfo>(&route)
             .await
-            .map_err(Into::into)
     }
 
     /// Gets all assets the corporation owns
@@ -55,11 +120,31 @@ impl EveCorporationService {
     /// List of Blueprints
     ///
     pub async fn assets(&self, client: &EveAuthClient) -> Result<Vec<AssetEntry>, ConnectError> {
-        let path = format!("latest/corporations/{}/assets", self.cid);
+        let route = CORPORATION_ASSETS
+            .route()
+            .param("corporation_id", self.cid);
         client
-            .fetch_page::<AssetEntry>(&path)
+            .fetch_route_pages::<AssetEntry>(&route)
             .await
-            .map_err(Into::into)
+    }
+
+    /// Streams all assets the corporation owns.
+    ///
+    /// Same as [EveCorporationService::assets], but the assets are returned
+    /// as soon as their page is loaded, instead of collecting all pages.
+    ///
+    /// # Returns
+    ///
+    /// Stream of assets, an error ends the stream
+    ///
+    pub fn assets_stream<'a>(
+        &self,
+        client: &'a EveAuthClient,
+    ) -> BoxStream<'a, Result<AssetEntry, ConnectError>> {
+        let route = CORPORATION_ASSETS
+            .route()
+            .param("corporation_id", self.cid);
+        client.fetch_route_stream::<AssetEntry>(&route)
     }
 
     /// Gets all blueprints the corporation owns
@@ -76,11 +161,12 @@ impl EveCorporationService {
         &self,
         client: &EveAuthClient,
     ) -> Result<Vec<BlueprintEntry>, ConnectError> {
-        let path = format!("latest/corporations/{}/blueprints", self.cid);
+        let route = CORPORATION_BLUEPRINTS
+            .route()
+            .param("corporation_id", self.cid);
         client
-            .fetch_page::<BlueprintEntry>(&path)
+            .fetch_route_pages::<BlueprintEntry>(&route)
             .await
-            .map_err(Into::into)
     }
 
     /// Gets all industry jobs the corporation has running
@@ -97,11 +183,12 @@ impl EveCorporationService {
         &self,
         client: &EveAuthClient,
     ) -> Result<Vec<IndustryJobEntry>, ConnectError> {
-        let path = format!("latest/corporations/{}/industry/jobs", self.cid);
+        let route = CORPORATION_INDUSTRY_JOBS
+            .route()
+            .param("corporation_id", self.cid);
         let mut data = client
-            .fetch_page::<IndustryJobEntry>(&path)
-            .await
-            .map_err(Into::into)?;
+            .fetch_route_pages::<IndustryJobEntry>(&route)
+            .await?;
 
         for x in data.iter_mut() {
             x.corporation_id = Some(self.cid);
@@ -109,7 +196,12 @@ impl EveCorporationService {
         Ok(data)
     }
 
-    /// Gets a list of last transactions of the master wallet.
+    /// Gets the journal of a single wallet division.
+    ///
+    /// # Params
+    ///
+    /// * `client`   -> Authenticated ESI client
+    /// * `division` -> Wallet division, see [EveCorporationService::WALLET_DIVISIONS]
     ///
     /// # Errors
     ///
@@ -117,17 +209,49 @@ impl EveCorporationService {
     ///
     /// # Returns
     ///
-    /// List of transactions
+    /// List of journal entries of the last 30 days
     ///
     pub async fn wallet_journal(
         &self,
-        client: &EveAuthClient,
+        client:   &EveAuthClient,
+        division: u8,
     ) -> Result<Vec<JournalEntry>, ConnectError> {
-        let path = format!("latest/corporations/{}/wallets/1/journal", self.cid);
+        let route = CORPORATION_WALLET_JOURNAL
+            .route()
+            .param("corporation_id", self.cid)
+            .param("division", division);
         client
-            .fetch_page::<JournalEntry>(&path)
+            .fetch_route_pages::<JournalEntry>(&route)
+            .await
+    }
+
+    /// Gets the market transactions of a single wallet division.
+    ///
+    /// # Params
+    ///
+    /// * `client`   -> Authenticated ESI client
+    /// * `division` -> Wallet division, see [EveCorporationService::WALLET_DIVISIONS]
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of the last market transactions
+    ///
+    pub async fn wallet_transactions(
+        &self,
+        client:   &EveAuthClient,
+        division: u8,
+    ) -> Result<Vec<WalletTransaction>, ConnectError> {
+        let route = CORPORATION_WALLET_TRANSACTIONS
+            .route()
+            .param("corporation_id", self.cid)
+            .param("division", division);
+        client
+            .fetch_route::<Vec<WalletTransaction>>(&route)
             .await
-            .map_err(Into::into)
     }
 
     /// Gets a list of all wallets and their current balance
@@ -141,11 +265,192 @@ impl EveCorporationService {
     /// List of balance
     ///
     pub async fn wallets(&self, client: &EveAuthClient) -> Result<Vec<WalletEntry>, ConnectError> {
-        let path = format!("latest/corporations/{}/wallets", self.cid);
+        let route = CORPORATION_WALLETS
+            .route()
+            .param("corporation_id", self.cid);
+        client
+            .fetch_route_pages::<WalletEntry>(&route)
+            .await
+    }
+
+    /// Gets all structures the corporation owns
+    ///
+    /// Requires the character to have the role `Station_Manager`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of all structures with their fuel, state and services
+    ///
+    pub async fn structures(
+        &self,
+        client: &EveAuthClient,
+    ) -> Result<Vec<CorporationStructure>, ConnectError> {
+        let route = CORPORATION_STRUCTURES
+            .route()
+            .param("corporation_id", self.cid);
         client
-            .fetch_page::<WalletEntry>(&path)
+            .fetch_route_pages::<CorporationStructure>(&route)
+            .await
+    }
+
+    /// Gets all contracts the corporation issued or was assigned to in the last
+    /// 30 days
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of contracts
+    ///
+    pub async fn contracts(
+        &self,
+        client: &EveAuthClient,
+    ) -> Result<Vec<Contract>, ConnectError> {
+        let route = CORPORATION_CONTRACTS
+            .route()
+            .param("corporation_id", self.cid);
+        client
+            .fetch_route_pages::<Contract>(&route)
+            .await
+    }
+
+    /// Gets all items of a contract
+    ///
+    /// # Params
+    ///
+    /// * `client`      -> Authenticated ESI client
+    /// * `contract_id` -> [ContractId] of the contract
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of items that are exchanged
+    ///
+    pub async fn contract_items(
+        &self,
+        client:      &EveAuthClient,
+        contract_id: ContractId,
+    ) -> Result<Vec<ContractItem>, ConnectError> {
+        let route = CORPORATION_CONTRACT_ITEMS
+            .route()
+            .param("corporation_id", self.cid)
+            .param("contract_id", contract_id);
+        client
+            .fetch_route_pages::<ContractItem>(&route)
+            .await
+    }
+
+    /// Gets all bids of an auction
+    ///
+    /// # Params
+    ///
+    /// * `client`      -> Authenticated ESI client
+    /// * `contract_id` -> [ContractId] of the auction
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of bids
+    ///
+    pub async fn contract_bids(
+        &self,
+        client:      &EveAuthClient,
+        contract_id: ContractId,
+    ) -> Result<Vec<ContractBid>, ConnectError> {
+        let route = CORPORATION_CONTRACT_BIDS
+            .route()
+            .param("corporation_id", self.cid)
+            .param("contract_id", contract_id);
+        client
+            .fetch_route_pages::<ContractBid>(&route)
+            .await
+    }
+
+    /// Gets all moon extractions that are running or arrived in the last
+    /// days
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of moon extractions
+    ///
+    pub async fn mining_extractions(
+        &self,
+        client: &EveAuthClient,
+    ) -> Result<Vec<MiningExtraction>, ConnectError> {
+        let route = CORPORATION_MINING_EXTRACTIONS
+            .route()
+            .param("corporation_id", self.cid);
+        client
+            .fetch_route::<Vec<MiningExtraction>>(&route)
+            .await
+    }
+
+    /// Gets all structures that record mined ore
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of mining observers
+    ///
+    pub async fn mining_observers(
+        &self,
+        client: &EveAuthClient,
+    ) -> Result<Vec<MiningObserver>, ConnectError> {
+        let route = CORPORATION_MINING_OBSERVERS
+            .route()
+            .param("corporation_id", self.cid);
+        client
+            .fetch_route_pages::<MiningObserver>(&route)
+            .await
+    }
+
+    /// Gets the ore every character mined at the given observer in the last
+    /// 30 days
+    ///
+    /// # Params
+    ///
+    /// * `client`      -> Authenticated ESI client
+    /// * `observer_id` -> [LocationId] of the observer
+    ///
+    /// # Errors
+    ///
+    /// Fails when the server returns an error or parsing the response fails
+    ///
+    /// # Returns
+    ///
+    /// List of mined ore per character and day
+    ///
+    pub async fn mining_ledger(
+        &self,
+        client:      &EveAuthClient,
+        observer_id: LocationId,
+    ) -> Result<Vec<MiningLedgerEntry>, ConnectError> {
+        let route = CORPORATION_MINING_LEDGER
+            .route()
+            .param("corporation_id", self.cid)
+            .param("observer_id", observer_id);
+        client
+            .fetch_route_pages::<MiningLedgerEntry>(&route)
             .await
-            .map_err(Into::into)
     }
 
     /// Gets a list of names for the given [LocationId].
@@ -174,34 +479,131 @@ impl EveCorporationService {
         client: &EveAuthClient,
         lid: Vec<LocationId>,
     ) -> Result<Vec<ItemLocation>, ConnectError> {
-        let path = format!("latest/corporations/{}/assets/names", self.cid);
+        let route = CORPORATION_ASSET_NAMES
+            .route()
+            .param("corporation_id", self.cid);
         client
-            .post::<Vec<LocationId>, Vec<ItemLocation>>(lid, &path)
+            .post_route::<Vec<LocationId>, Vec<ItemLocation>>(lid, &route)
             .await
-            .map_err(Into::into)
     }
 }
 
-/// Represents a transaction entry
-#[derive(Debug, Deserialize)]
-pub struct JournalEntry {
-    /// ISK amount
-    pub amount: f32,
-    /// Date the transaction was performed
-    pub date: String,
-    /// Information about the transaction
-    pub description: String,
-    /// Unique ID
-    pub id: u64,
+/// Structure owned by a corporation
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CorporationStructure {
+    /// Id of the structure
+    pub structure_id:         LocationId,
+    /// [TypeId] of the structure, for example a Raitaru
+    pub type_id:              TypeId,
+    /// System the structure is located in
+    pub system_id:            SystemId,
+    /// Owner of the structure
+    pub corporation_id:       CorporationId,
+    /// Id of the ACL profile of the structure
+    pub profile_id:           i32,
+    /// Current state of the structure
+    pub state:                StructureState,
+
+    /// Name of the structure
+    #[serde(default)]
+    pub name:                 Option<String>,
+    /// Time the fuel runs out, not set if the structure has no fuel
+    #[serde(default)]
+    pub fuel_expires:         Option<DateTime<Utc>>,
+    /// All service modules of the structure
+    #[serde(default)]
+    pub services:             Vec<StructureService>,
+
+    /// Time the current state started
+    #[serde(default)]
+    pub state_timer_start:    Option<DateTime<Utc>>,
+    /// Time the current state ends
+    #[serde(default)]
+    pub state_timer_end:      Option<DateTime<Utc>>,
+    /// Hour of the day, in UTC, the structure leaves reinforcement
+    #[serde(default)]
+    pub reinforce_hour:       Option<i32>,
+    /// Reinforcement hour that becomes active at `next_reinforce_apply`
+    #[serde(default)]
+    pub next_reinforce_hour:  Option<i32>,
+    /// Time the `next_reinforce_hour` becomes active
+    #[serde(default)]
+    pub next_reinforce_apply: Option<DateTime<Utc>>,
+    /// Time the structure finishes unanchoring
+    #[serde(default)]
+    pub unanchors_at:         Option<DateTime<Utc>>,
 }
 
-/// Represents a wallet entry
-#[derive(Debug, Deserialize)]
-pub struct WalletEntry {
-    /// Current balance of the division
-    pub balance: f32,
-    /// Devision number, eg: 1 is the master wallet
-    pub division: u8,
+impl CorporationStructure {
+    /// Checks if the structure has the given service online.
+    ///
+    /// # Params
+    ///
+    /// * `name` -> Name of the service, for example `Manufacturing (Standard)`
+    ///
+    /// # Returns
+    ///
+    /// `true` if the service is online
+    ///
+    pub fn service_online(&self, name: &str) -> bool {
+        self.services
+            .iter()
+            .any(|x| x.name == name && x.state == StructureServiceState::Online)
+    }
+}
+
+/// Possible states of a structure
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StructureState {
+    /// Anchoring finished, the structure can be attacked
+    AnchorVulnerable,
+    /// The structure is anchoring
+    Anchoring,
+    /// Armor is reinforced
+    ArmorReinforce,
+    /// Armor can be attacked
+    ArmorVulnerable,
+    /// The structure was just deployed and can be attacked
+    DeployVulnerable,
+    /// The structure is being fitted and can not be attacked
+    FittingInvulnerable,
+    /// Hull is reinforced
+    HullReinforce,
+    /// Hull can be attacked
+    HullVulnerable,
+    /// Deprecated online state
+    OnlineDeprecated,
+    /// The structure is onlining and can be attacked
+    OnliningVulnerable,
+    /// Shield can be attacked, the regular state of an online structure
+    ShieldVulnerable,
+    /// The structure is unanchored
+    Unanchored,
+    /// State that is not known
+    #[serde(other)]
+    Unknown,
+}
+
+/// Service module of a structure
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StructureService {
+    /// Name of the service
+    pub name:  String,
+    /// State of the service
+    pub state: StructureServiceState,
+}
+
+/// Possible states of a structure service
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StructureServiceState {
+    /// The service is running
+    Online,
+    /// The service is turned off
+    Offline,
+    /// The service is being removed
+    Cleanup,
 }
 
 /// General information about the character
@@ -221,3 +623,140 @@ pub struct ItemLocation {
     /// Name of the location, for example a container or station
     pub name: String,
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod corporation_tests {
+    use super::*;
+    use crate::{IndustryActivity, JournalContextType};
+    use crate::testing::*;
+
+    #[tokio::test]
+    async fn info() {
+        let esi = MockEsi::start().await;
+        let client = esi.client().unwrap();
+
+        let info = EveCorporationService::new(CORPORATION_ID)
+            .info(&client)
+            .await
+            .unwrap();
+        assert_eq!(info.name, "Caph Test Corporation");
+        assert_eq!(info.alliance_id, Some(ALLIANCE_ID));
+    }
+
+    #[tokio::test]
+    async fn assets_blueprints_and_jobs() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let service = EveCorporationService::new(CORPORATION_ID);
+
+        let assets = service.assets(&client).await.unwrap();
+        assert_eq!(assets[0].quantity, 50000);
+
+        let names = service
+            .location_name(&client, vec![STRUCTURE_ID])
+            .await
+            .unwrap();
+        assert_eq!(names[0].item_id, STRUCTURE_ID);
+
+        let blueprints = service.blueprints(&client).await.unwrap();
+        assert_eq!(blueprints[0].runs, -1);
+
+        let jobs = service.industry_jobs(&client).await.unwrap();
+        assert!(matches!(jobs[0].activity, IndustryActivity::Copying));
+        assert_eq!(jobs[0].cost, None);
+    }
+
+    #[tokio::test]
+    async fn wallets() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let service = EveCorporationService::new(CORPORATION_ID);
+
+        let wallets = service.wallets(&client).await.unwrap();
+        assert_eq!(wallets.len(), 2);
+        assert_eq!(wallets[0].balance, 1_234_567_890.12);
+
+        let journal = service.wallet_journal(&client, 1).await.unwrap();
+        assert_eq!(journal[0].amount, -1000.0);
+        assert_eq!(journal[0].ref_type, "player_donation");
+        assert_eq!(journal[0].first_party_id, Some(*CHARACTER_ID));
+        assert_eq!(journal[1].amount, 1_500_000_000.37);
+        assert_eq!(journal[1].context_id_type, Some(JournalContextType::MarketTransactionId));
+        assert_eq!(journal[1].tax, Some(12_345.67));
+
+        let journal = service.wallet_journal(&client, 7).await.unwrap();
+        assert!(journal.is_empty());
+
+        let transactions = service.wallet_transactions(&client, 1).await.unwrap();
+        assert_eq!(transactions[0].journal_ref_id, 20_000_000_002);
+        assert_eq!(transactions[0].amount(), 1_500_000_000.0);
+    }
+
+    #[tokio::test]
+    async fn structures() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let service = EveCorporationService::new(CORPORATION_ID);
+
+        let structures = service.structures(&client).await.unwrap();
+        assert_eq!(structures.len(), 2);
+
+        assert_eq!(structures[0].structure_id, STRUCTURE_ID);
+        assert_eq!(structures[0].type_id, TypeId(35825));
+        assert_eq!(structures[0].state, StructureState::ShieldVulnerable);
+        assert!(structures[0].fuel_expires.is_some());
+        assert!(structures[0].service_online("Manufacturing (Standard)"));
+        assert!(!structures[0].service_online("Invention"));
+        assert!(!structures[0].service_online("Reprocessing"));
+
+        assert_eq!(structures[1].state, StructureState::ArmorReinforce);
+        assert!(structures[1].fuel_expires.is_none());
+        assert!(structures[1].services.is_empty());
+        assert!(structures[1].state_timer_end.is_some());
+    }
+
+    #[tokio::test]
+    async fn mining() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let service = EveCorporationService::new(CORPORATION_ID);
+
+        let extractions = service.mining_extractions(&client).await.unwrap();
+        assert_eq!(extractions[0].structure_id, STRUCTURE_ID);
+        assert!(extractions[0].chunk_arrival_time < extractions[0].natural_decay_time);
+
+        let observers = service.mining_observers(&client).await.unwrap();
+        assert_eq!(observers[0].observer_id, STRUCTURE_ID);
+
+        let ledger = service
+            .mining_ledger(&client, observers[0].observer_id)
+            .await
+            .unwrap();
+        assert_eq!(ledger.len(), 2);
+        assert_eq!(ledger[0].character_id, CHARACTER_ID);
+        assert_eq!(ledger[0].quantity, 120_000);
+    }
+
+    #[tokio::test]
+    async fn contracts() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let service = EveCorporationService::new(CORPORATION_ID);
+
+        let contracts = service.contracts(&client).await.unwrap();
+        assert!(contracts[0].for_corporation);
+
+        let items = service
+            .contract_items(&client, contracts[0].contract_id)
+            .await
+            .unwrap();
+        assert_eq!(items[0].quantity, 10);
+
+        let bids = service
+            .contract_bids(&client, contracts[0].contract_id)
+            .await
+            .unwrap();
+        assert!(bids.is_empty());
+    }
+}
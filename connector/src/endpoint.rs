@@ -0,0 +1,400 @@
+use std::time::Duration;
+use url::form_urlencoded;
+
+use crate::ConnectError;
+
+/// Query parameters that every endpoint of the EVE-API accepts
+const COMMON_QUERY: &[&str] = &["datasource", "language"];
+
+/// How an endpoint splits up its result
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pagination {
+    /// The whole result is returned with a single request
+    None,
+    /// The result is split into pages, the number of pages is send in the
+    /// `X-Pages` header and every page is requested with `?page=`
+    Pages,
+}
+
+/// Declaration of a single endpoint of the EVE-API.
+///
+/// Endpoints are declared once as constants and turned into a [Route] for
+/// every request.
+///
+/// # Usage
+///
+/// ``` rust
+/// use caph_connector::*;
+///
+/// const ASSETS: Endpoint = Endpoint::new("latest/characters/{character_id}/assets")
+///     .scope("esi-assets.read_assets.v1")
+///     .paginated();
+///
+/// let path = ASSETS
+///     .route()
+///     .param("character_id", 2117000001)
+///     .path()
+///     .unwrap();
+/// assert_eq!(path, "latest/characters/2117000001/assets");
+/// ```
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Endpoint {
+    /// Path of the endpoint, parameters are written as `{name}`
+    path:       &'static str,
+    /// Scope the token needs for requesting the endpoint
+    scope:      Option<&'static str>,
+    /// How the endpoint splits up its result
+    pagination: Pagination,
+    /// Time a response is served from the cache, regardless of its `Expires`
+    /// header
+    cache:      Option<Duration>,
+    /// Query parameters the endpoint accepts
+    query:      &'static [&'static str],
+}
+
+impl Endpoint {
+    /// Declares a new public endpoint without pagination.
+    ///
+    /// # Params
+    ///
+    /// * `path` -> Path template, for example `latest/universe/types/{type_id}`
+    ///
+    /// # Returns
+    ///
+    /// New endpoint
+    ///
+    pub const fn new(path: &'static str) -> Self {
+        Self {
+            path,
+            scope:      None,
+            pagination: Pagination::None,
+            cache:      None,
+            query:      &[],
+        }
+    }
+
+    /// Sets the scope that is required for the endpoint.
+    ///
+    /// # Params
+    ///
+    /// * `scope` -> Required scope, for example `esi-assets.read_assets.v1`
+    ///
+    /// # Returns
+    ///
+    /// The modified endpoint
+    ///
+    pub const fn scope(mut self, scope: &'static str) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    /// Marks the endpoint as split into pages.
+    ///
+    /// # Returns
+    ///
+    /// The modified endpoint
+    ///
+    pub const fn paginated(mut self) -> Self {
+        self.pagination = Pagination::Pages;
+        self
+    }
+
+    /// Sets how long responses are served from the cache of the client.
+    ///
+    /// Without a duration the `Expires` header of the response is used.
+    ///
+    /// # Params
+    ///
+    /// * `duration` -> Time a response is considered fresh
+    ///
+    /// # Returns
+    ///
+    /// The modified endpoint
+    ///
+    pub const fn cache_for(mut self, duration: Duration) -> Self {
+        self.cache = Some(duration);
+        self
+    }
+
+    /// Sets the query parameters the endpoint accepts, besides `datasource`
+    /// and `language`.
+    ///
+    /// # Params
+    ///
+    /// * `query` -> Names of the query parameters
+    ///
+    /// # Returns
+    ///
+    /// The modified endpoint
+    ///
+    pub const fn query(mut self, query: &'static [&'static str]) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Gets the path template of the endpoint.
+    ///
+    /// # Returns
+    ///
+    /// Path with placeholders
+    ///
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+
+    /// Gets the scope that is required for the endpoint.
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the endpoint is public
+    /// - Required scope
+    ///
+    pub fn required_scope(&self) -> Option<&'static str> {
+        self.scope
+    }
+
+    /// Gets how the endpoint splits up its result.
+    ///
+    /// # Returns
+    ///
+    /// Pagination style of the endpoint
+    ///
+    pub fn pagination(&self) -> Pagination {
+        self.pagination
+    }
+
+    /// Gets how long responses are served from the cache.
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the `Expires` header of the response is used
+    /// - Time a response is considered fresh
+    ///
+    pub fn cache_duration(&self) -> Option<Duration> {
+        self.cache
+    }
+
+    /// Starts a new request to the endpoint.
+    ///
+    /// # Returns
+    ///
+    /// Route without any parameters
+    ///
+    pub fn route(&self) -> Route {
+        Route {
+            endpoint: *self,
+            params:   Vec::new(),
+            query:    Vec::new(),
+        }
+    }
+}
+
+/// Single request to an [Endpoint] with all its parameters.
+///
+/// Requested with [crate::RequestClient::fetch_route] and its siblings.
+///
+#[derive(Clone, Debug)]
+pub struct Route {
+    /// Endpoint that is requested
+    endpoint: Endpoint,
+    /// Values of the path parameters
+    params:   Vec<(&'static str, String)>,
+    /// Query parameters in the order they were added
+    query:    Vec<(&'static str, String)>,
+}
+
+impl Route {
+    /// Sets a parameter of the path.
+    ///
+    /// # Params
+    ///
+    /// * `name`  -> Name of the placeholder, without braces
+    /// * `value` -> Value that replaces the placeholder
+    ///
+    /// # Returns
+    ///
+    /// The modified route
+    ///
+    pub fn param(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.params.push((name, value.to_string()));
+        self
+    }
+
+    /// Adds a query parameter.
+    ///
+    /// # Params
+    ///
+    /// * `name`  -> Name of the query parameter
+    /// * `value` -> Value of the query parameter
+    ///
+    /// # Returns
+    ///
+    /// The modified route
+    ///
+    pub fn query(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.query.push((name, value.to_string()));
+        self
+    }
+
+    /// Gets the requested endpoint.
+    ///
+    /// # Returns
+    ///
+    /// Declaration of the endpoint
+    ///
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    /// Builds the path including the encoded query.
+    ///
+    /// # Errors
+    ///
+    /// - If a parameter is not part of the path template
+    /// - If a placeholder of the path template has no value
+    /// - If a query parameter is not accepted by the endpoint
+    ///
+    /// # Returns
+    ///
+    /// Path that can be appended to the base url of the EVE-API
+    ///
+    pub fn path(&self) -> Result<String, ConnectError> {
+        let template = self.endpoint.path;
+
+        let mut path = template.to_string();
+        for (name, value) in self.params.iter() {
+            let placeholder = format!("{{{}}}", name);
+            if !path.contains(&placeholder) {
+                return Err(ConnectError::InvalidRoute(
+                    format!("{} has no parameter {}", template, name)
+                ));
+            }
+            path = path.replace(&placeholder, value);
+        }
+        if path.contains('{') {
+            return Err(ConnectError::InvalidRoute(
+                format!("{} is missing a parameter", template)
+            ));
+        }
+
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        for (name, value) in self.query.iter() {
+            if !self.endpoint.query.contains(name) && !COMMON_QUERY.contains(name) {
+                return Err(ConnectError::InvalidRoute(
+                    format!("{} does not accept the query {}", template, name)
+                ));
+            }
+            query.append_pair(name, value);
+        }
+
+        let query = query.finish();
+        if query.is_empty() {
+            Ok(path)
+        } else {
+            Ok(format!("{}?{}", path, query))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod endpoint_tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::{EveCharacterService, RequestClient};
+    use hyper::Method;
+
+    /// Endpoint with a path parameter and a query
+    const ORDERS: Endpoint = Endpoint::new("latest/markets/{region_id}/orders")
+        .paginated()
+        .query(&["order_type", "type_id"]);
+
+    #[test]
+    fn path_with_params_and_query() {
+        let path = ORDERS
+            .route()
+            .param("region_id", REGION_ID)
+            .query("order_type", "all")
+            .query("type_id", 34)
+            .query("language", "en")
+            .path()
+            .unwrap();
+        assert_eq!(path, format!("latest/markets/{}/orders?order_type=all&type_id=34&language=en", REGION_ID));
+    }
+
+    #[test]
+    fn invalid_routes() {
+        assert!(ORDERS.route().path().is_err());
+        assert!(ORDERS.route().param("system_id", 1).path().is_err());
+        assert!(ORDERS.route().param("region_id", 1).query("include_completed", true).path().is_err());
+    }
+
+    #[tokio::test]
+    async fn pages_of_routes_with_query() {
+        let esi = MockEsi::start().await;
+        let path = format!("/latest/markets/{}/orders?order_type=all", REGION_ID);
+        esi.mock(Method::GET, &path, MockResponse::json("[1]").header("x-pages", "2"));
+        esi.mock(
+            Method::GET,
+            &format!("{}&page=2", path),
+            MockResponse::json("[2]").header("x-pages", "2"),
+        );
+        let client = esi.client().unwrap();
+
+        let route = ORDERS
+            .route()
+            .param("region_id", REGION_ID)
+            .query("order_type", "all");
+        let numbers = client.fetch_route_pages::<u32>(&route).await.unwrap();
+        assert_eq!(numbers, vec![1, 2]);
+
+        // all pages are requested with fetch_route_pages
+        assert!(client.fetch_route::<Vec<u32>>(&route).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn missing_scope_is_rejected_before_the_request() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+
+        const NOTIFICATIONS: Endpoint = Endpoint::new("latest/characters/{character_id}/notifications")
+            .scope("esi-characters.read_notifications.v1");
+        let route = NOTIFICATIONS.route().param("character_id", CHARACTER_ID);
+        match client.fetch_route::<Vec<u32>>(&route).await {
+            Err(ConnectError::MissingScope(x)) => assert_eq!(x, "esi-characters.read_notifications.v1"),
+            x => panic!("Unexpected result {:?}", x),
+        }
+
+        // granted scopes are requested as usual
+        EveCharacterService::new(CHARACTER_ID)
+            .assets(&client)
+            .await
+            .unwrap();
+
+        let requests = esi.requests();
+        assert!(!requests.iter().any(|x| x.contains("/notifications")));
+
+        // the public client has no scopes at all
+        let client = esi.client().unwrap();
+        assert!(matches!(
+            client.fetch_route::<Vec<u32>>(&route).await,
+            Err(ConnectError::MissingScope(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn cache_duration_overrides_expires() {
+        let esi = MockEsi::start().await;
+        let client = esi.builder().cache(crate::MemoryCache::new()).build().unwrap();
+        esi.mock(Method::GET, "/latest/status", MockResponse::json("1"));
+
+        const STATUS: Endpoint = Endpoint::new("latest/status")
+            .cache_for(Duration::from_secs(60));
+        client.fetch_route::<u32>(&STATUS.route()).await.unwrap();
+        client.fetch_route::<u32>(&STATUS.route()).await.unwrap();
+
+        let requests = esi.requests();
+        assert_eq!(requests.iter().filter(|x| x.starts_with("GET /latest/status")).count(), 1);
+    }
+}
@@ -1,7 +1,16 @@
+use reqwest::StatusCode;
+use std::fmt;
+
+use crate::{CharacterId, RetryPolicy};
+
 /// Holds all possible errors that can occur in this library.
 ///
 /// Besides that it contains helper functions for easier construction of errors.
 ///
+/// Errors of requests carry the details of the failed request, they can be
+/// read with [ConnectError::status], [ConnectError::esi_message],
+/// [ConnectError::endpoint] and [ConnectError::character_id].
+///
 #[derive(Debug)]
 pub enum ConnectError {
     /// An ENV was not set, contains which variable is missing
@@ -10,11 +19,18 @@ pub enum ConnectError {
     CouldNotConstructClient(reqwest::Error),
     /// Could not parse the given url
     UrlParseError,
-    /// The request to the given URL failed 3 times in a row
-    TooManyRetries(String),
+    /// A [crate::Route] could not be turned into a path, contains the reason
+    InvalidRoute(String),
+    /// The token of the client was not granted the scope the endpoint
+    /// requires, contains the scope
+    MissingScope(String),
+    /// The EVE-API answered with a non successful status code
+    EsiError(EsiError),
+    /// The request could not be send or the response could not be read
+    RequestFailed(RequestError),
 
-    /// Generic reqwest error
-    ReqwestError(reqwest::Error),
+    /// The response could not be parsed into the requested model
+    ParseError(serde_json::Error),
 
     /// The payload could not be decoded
     OAuthPayloadDecode(base64::DecodeError),
@@ -23,6 +39,17 @@ pub enum ConnectError {
     /// Failed to parse the character id
     OAuthParseCharacterId(std::num::ParseIntError),
 
+    /// Downloading the public keys of the EVE-SSO failed
+    JwksDownloadFailed(reqwest::Error),
+    /// Reading the public keys from disk failed
+    JwksReadError(std::io::Error),
+    /// The public keys could not be parsed
+    JwksParseError(serde_json::Error),
+    /// The token was signed with a key that is not known, contains the key id
+    JwtUnknownKey(Option<String>),
+    /// The token is invalid, for example expired or a wrong signature
+    JwtInvalid(jsonwebtoken::errors::Error),
+
     /// Downloading the SDE.zip file failed
     SdeDownloadFailed(reqwest::Error),
     /// The requested file could not be found in the zip
@@ -33,6 +60,14 @@ pub enum ConnectError {
     SdeReadError(std::io::Error),
     /// Loading the SDE.zip file failed
     SdeZipLoadError(zip::result::ZipError),
+
+    /// There is no recorded response for the request, contains the path of
+    /// the missing fixture file
+    FixtureNotFound(String),
+    /// A fixture file could not be read or written
+    FixtureIoError(std::io::Error),
+    /// A fixture file could not be parsed or serialized
+    FixtureParseError(serde_json::Error),
 }
 
 impl ConnectError {
@@ -59,4 +94,359 @@ impl ConnectError {
     pub fn env_secret_key() -> Self {
         Self::EnvNotSet("ENV 'EVE_SECRET_KEY' is not set!".into())
     }
+
+    /// Gets the HTTP status of the failed request.
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the error is not related to a response
+    /// - Status of the response
+    ///
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::EsiError(e)      => Some(e.status),
+            Self::RequestFailed(e) => e.source.status(),
+            _                      => None,
+        }
+    }
+
+    /// Gets the error message that was send by the EVE-API.
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the EVE-API did not respond with an error
+    /// - Message of the EVE-API
+    ///
+    pub fn esi_message(&self) -> Option<&str> {
+        match self {
+            Self::EsiError(e) => Some(&e.error),
+            _                 => None,
+        }
+    }
+
+    /// Gets the endpoint of the failed request.
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the error is not related to a request
+    /// - Path of the request, without host and query
+    ///
+    pub fn endpoint(&self) -> Option<&str> {
+        match self {
+            Self::EsiError(e)      => Some(&e.endpoint),
+            Self::RequestFailed(e) => Some(&e.endpoint),
+            _                      => None,
+        }
+    }
+
+    /// Gets the character whose token was used for the failed request.
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the request was not authenticated
+    /// - [CharacterId] of the character
+    ///
+    pub fn character_id(&self) -> Option<CharacterId> {
+        match self {
+            Self::EsiError(e)      => e.character_id,
+            Self::RequestFailed(e) => e.character_id,
+            _                      => None,
+        }
+    }
+
+    /// Checks if sending the request again may succeed.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the EVE-API is not available, overloaded or timed out
+    ///
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::EsiError(e)      => e.is_retryable(),
+            Self::RequestFailed(e) => e.is_retryable(),
+            _                      => false,
+        }
+    }
+
+    /// Sets the character whose token was used for the request.
+    ///
+    /// # Params
+    ///
+    /// * `character_id` -> Character of the token
+    ///
+    /// # Returns
+    ///
+    /// The same error, with the character set if it is related to a request
+    ///
+    pub(crate) fn with_character_id(mut self, character_id: Option<CharacterId>) -> Self {
+        match self {
+            Self::EsiError(ref mut e)      => e.character_id = character_id,
+            Self::RequestFailed(ref mut e) => e.character_id = character_id,
+            _                              => (),
+        }
+        self
+    }
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EnvNotSet(x)               => write!(f, "{}", x),
+            Self::CouldNotConstructClient(_) => write!(f, "could not construct the http client"),
+            Self::UrlParseError              => write!(f, "could not parse the url"),
+            Self::InvalidRoute(x)            => write!(f, "invalid route: {}", x),
+            Self::MissingScope(x)            => write!(f, "the token is missing the scope {}", x),
+            Self::EsiError(e)                => write!(f, "{}", e),
+            Self::RequestFailed(e)           => write!(f, "{}", e),
+            Self::ParseError(_)              => write!(f, "could not parse the response"),
+            Self::OAuthPayloadDecode(_)      => write!(f, "could not decode the token payload"),
+            Self::OAuthParseError(_)         => write!(f, "could not parse the token payload"),
+            Self::OAuthParseCharacterId(_)   => write!(f, "could not parse the character id of the token"),
+            Self::JwksDownloadFailed(_)      => write!(f, "could not download the keys of the EVE-SSO"),
+            Self::JwksReadError(_)           => write!(f, "could not read the keys of the EVE-SSO"),
+            Self::JwksParseError(_)          => write!(f, "could not parse the keys of the EVE-SSO"),
+            Self::JwtUnknownKey(x)           => write!(f, "the token is signed with the unknown key {:?}", x),
+            Self::JwtInvalid(_)              => write!(f, "the token is invalid"),
+            Self::SdeDownloadFailed(_)       => write!(f, "could not download the SDE"),
+            Self::SdeFileNotFound(x)         => write!(f, "the file {} is not in the SDE", x),
+            Self::SdeParseError(_)           => write!(f, "could not parse the SDE file"),
+            Self::SdeReadError(_)            => write!(f, "could not read the SDE"),
+            Self::SdeZipLoadError(_)         => write!(f, "could not load the SDE zip"),
+            Self::FixtureNotFound(x)         => write!(f, "there is no fixture {}", x),
+            Self::FixtureIoError(_)          => write!(f, "could not access the fixture"),
+            Self::FixtureParseError(_)       => write!(f, "could not parse the fixture"),
+        }
+    }
+}
+
+impl std::error::Error for ConnectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::CouldNotConstructClient(e) => Some(e),
+            Self::RequestFailed(e)           => Some(&e.source),
+            Self::ParseError(e)              => Some(e),
+            Self::OAuthPayloadDecode(e)      => Some(e),
+            Self::OAuthParseError(e)         => Some(e),
+            Self::OAuthParseCharacterId(e)   => Some(e),
+            Self::JwksDownloadFailed(e)      => Some(e),
+            Self::JwksReadError(e)           => Some(e),
+            Self::JwksParseError(e)          => Some(e),
+            Self::JwtInvalid(e)              => Some(e),
+            Self::SdeDownloadFailed(e)       => Some(e),
+            Self::SdeParseError(e)           => Some(e),
+            Self::SdeReadError(e)            => Some(e),
+            Self::SdeZipLoadError(e)         => Some(e),
+            Self::FixtureIoError(e)          => Some(e),
+            Self::FixtureParseError(e)       => Some(e),
+            _                                => None,
+        }
+    }
+}
+
+/// Error response of the EVE-API
+#[derive(Debug)]
+pub struct EsiError {
+    /// Url of the failed request
+    pub url: String,
+    /// Path of the failed request, without host and query
+    pub endpoint: String,
+    /// Status code of the response
+    pub status: reqwest::StatusCode,
+    /// Error message send by the EVE-API, if the body could not be parsed
+    /// the raw body is used
+    pub error: String,
+    /// Character whose token was used, [None] for public requests
+    pub character_id: Option<CharacterId>,
+}
+
+impl EsiError {
+    /// Reads the error message from the given response.
+    ///
+    /// The EVE-API sends errors in the format `{"error": "message"}`.
+    ///
+    /// # Params
+    ///
+    /// * `url`      -> Url of the request
+    /// * `response` -> Response with a non successful status
+    ///
+    /// # Returns
+    ///
+    /// New error instance
+    ///
+    pub async fn from_response(url: &str, response: reqwest::Response) -> Self {
+        /// Error body of the EVE-API
+        #[derive(serde::Deserialize)]
+        struct Body {
+            /// Error message
+            error: String,
+        }
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let error = serde_json::from_str::<Body>(&body)
+            .map(|x| x.error)
+            .unwrap_or(body);
+
+        Self {
+            url: url.into(),
+            endpoint: endpoint(url),
+            status,
+            error,
+            character_id: None,
+        }
+    }
+
+    /// Checks if sending the request again may succeed.
+    ///
+    /// # Returns
+    ///
+    /// `true` for all statuses that are retried by the [RetryPolicy]
+    ///
+    pub fn is_retryable(&self) -> bool {
+        RetryPolicy::is_retryable(self.status)
+    }
+}
+
+impl fmt::Display for EsiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EVE-API responded with {} for {}", self.status, self.endpoint)?;
+        if let Some(x) = self.character_id {
+            write!(f, " (character {})", x)?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+/// A request that did not get a response, or whose response could not be
+/// read
+#[derive(Debug)]
+pub struct RequestError {
+    /// Url of the failed request
+    pub url: String,
+    /// Path of the failed request, without host and query
+    pub endpoint: String,
+    /// Character whose token was used, [None] for public requests
+    pub character_id: Option<CharacterId>,
+    /// Underlying error
+    pub source: reqwest::Error,
+}
+
+impl RequestError {
+    /// Creates a new error for the given request.
+    ///
+    /// # Params
+    ///
+    /// * `url`    -> Url of the request
+    /// * `source` -> Error that occurred
+    ///
+    /// # Returns
+    ///
+    /// New error instance
+    ///
+    pub fn new(url: &str, source: reqwest::Error) -> Self {
+        Self {
+            url: url.into(),
+            endpoint: endpoint(url),
+            character_id: None,
+            source,
+        }
+    }
+
+    /// Checks if sending the request again may succeed.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the request timed out or no connection could be established
+    ///
+    pub fn is_retryable(&self) -> bool {
+        self.source.is_timeout() || self.source.is_connect()
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request to {} failed", self.endpoint)?;
+        if let Some(x) = self.character_id {
+            write!(f, " (character {})", x)?;
+        }
+        Ok(())
+    }
+}
+
+/// Extracts the endpoint from the given url.
+///
+/// # Params
+///
+/// * `url` -> Full url of a request
+///
+/// # Returns
+///
+/// Path of the url, or the url itself if it can not be parsed
+///
+fn endpoint(url: &str) -> String {
+    url::Url::parse(url).map_or_else(|_| url.into(), |x| x.path().to_string())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod error_tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::{EveCharacterService, EveMarketService, RequestClient};
+    use hyper::Method;
+    use std::error::Error;
+
+    #[tokio::test]
+    async fn esi_errors_carry_request_details() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let path = format!("/latest/characters/{}/blueprints", CHARACTER_ID);
+        esi.mock(Method::GET, &path, MockResponse::error(403, "token not valid for scope(s)"));
+
+        let error = EveCharacterService::new(CHARACTER_ID)
+            .blueprints(&client)
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::FORBIDDEN));
+        assert_eq!(error.esi_message(), Some("token not valid for scope(s)"));
+        assert_eq!(error.endpoint(), Some(path.as_str()));
+        assert_eq!(error.character_id(), Some(CHARACTER_ID));
+        assert!(!error.is_retryable());
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "EVE-API responded with 403 Forbidden for {} (character {}): token not valid for scope(s)",
+                path,
+                CHARACTER_ID
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn upstream_errors_are_retryable() {
+        let esi = MockEsi::start().await;
+        let client = esi.client().unwrap();
+        esi.mock(Method::GET, "/latest/markets/prices", MockResponse::error(502, "Bad Gateway"));
+
+        let error = EveMarketService.prices(&client).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::BAD_GATEWAY));
+        assert_eq!(error.character_id(), None);
+        assert!(error.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn unreachable_api_is_retryable() {
+        let client = crate::EveClientBuilder::new()
+            .esi_url("http://127.0.0.1:1")
+            .user_agent("caph_test")
+            .retry_policy(crate::RetryPolicy::no_retry())
+            .build()
+            .unwrap();
+
+        let error = client.fetch::<()>("latest/status").await.unwrap_err();
+        assert!(matches!(error, ConnectError::RequestFailed(_)));
+        assert_eq!(error.endpoint(), Some("/latest/status"));
+        assert!(error.is_retryable());
+        assert!(error.source().is_some());
+    }
 }
@@ -0,0 +1,467 @@
+//! Records responses of the EVE-API to files and replays them.
+//!
+//! Only available with the feature `fixtures`.
+//!
+//! In [FixtureMode::Record] every request is send to the EVE-API and the
+//! response is written into the fixture directory, existing files are
+//! overwritten. In [FixtureMode::Replay] the responses are read from the
+//! directory and no request is send at all, not even for getting an
+//! `access_token`. A request without a fixture fails with
+//! [ConnectError::FixtureNotFound].
+//!
+//! The cache of the client is not used in both modes.
+//!
+//! Responses of an [crate::EveAuthClient] depend on the character, so like
+//! in the cache they are stored per character. While recording the
+//! character is read from the `access_token`, for replaying it has to be
+//! set with [Fixtures::character].
+//!
+//! # Usage
+//!
+//! ``` rust,ignore
+//! use caph_connector::*;
+//!
+//! // records once against the real EVE-API
+//! let client = EveClientBuilder::new()
+//!     .fixtures(Fixtures::record("./fixtures/esi"))
+//!     .build_auth(refresh_token)?;
+//!
+//! // replays the recorded responses, for example in CI
+//! let client = EveClientBuilder::new()
+//!     .fixtures(Fixtures::replay("./fixtures/esi").character(character_id))
+//!     .build_auth(String::new())?;
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+use crate::{CacheEntry, CharacterId, ConnectError};
+
+/// Whether responses are recorded or replayed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FixtureMode {
+    /// Requests are send and their responses written to files
+    Record,
+    /// Responses are read from files, nothing is send
+    Replay,
+}
+
+/// Directory of recorded responses and what to do with it
+#[derive(Clone, Debug)]
+pub struct Fixtures {
+    /// Directory the fixture files are located in
+    dir:       PathBuf,
+    /// Whether responses are recorded or replayed
+    mode:      FixtureMode,
+    /// Character whose responses are replayed by an authenticated client
+    character: Option<CharacterId>,
+}
+
+impl Fixtures {
+    /// Name of the ENV that selects the mode, `record` or `replay`
+    const ENV_MODE:      &'static str = "EVE_FIXTURES";
+    /// Name of the ENV that contains the fixture directory
+    const ENV_DIR:       &'static str = "EVE_FIXTURE_DIR";
+    /// Name of the ENV that contains the character to replay
+    const ENV_CHARACTER: &'static str = "EVE_FIXTURE_CHARACTER";
+
+    /// Records all responses into the given directory.
+    ///
+    /// # Params
+    ///
+    /// * `dir` -> Directory for the fixture files, created if necessary
+    ///
+    /// # Returns
+    ///
+    /// New instance
+    ///
+    pub fn record(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir:       dir.into(),
+            mode:      FixtureMode::Record,
+            character: None,
+        }
+    }
+
+    /// Replays all responses from the given directory.
+    ///
+    /// # Params
+    ///
+    /// * `dir` -> Directory containing the fixture files
+    ///
+    /// # Returns
+    ///
+    /// New instance
+    ///
+    pub fn replay(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir:       dir.into(),
+            mode:      FixtureMode::Replay,
+            character: None,
+        }
+    }
+
+    /// Sets the character whose responses an authenticated client replays.
+    ///
+    /// Only used in [FixtureMode::Replay], while recording the character is
+    /// read from the `access_token`.
+    ///
+    /// # Params
+    ///
+    /// * `character_id` -> Character the responses were recorded for
+    ///
+    /// # Returns
+    ///
+    /// The modified fixtures
+    ///
+    pub fn character(mut self, character_id: CharacterId) -> Self {
+        self.character = Some(character_id);
+        self
+    }
+
+    /// Reads the mode from the ENV `EVE_FIXTURES`, the directory from the
+    /// ENV `EVE_FIXTURE_DIR` and the optional character to replay from the
+    /// ENV `EVE_FIXTURE_CHARACTER`.
+    ///
+    /// # Returns
+    ///
+    /// - [None] if `EVE_FIXTURES` is neither `record` nor `replay`, or
+    ///   `EVE_FIXTURE_DIR` is not set
+    /// - Configured fixtures
+    ///
+    pub fn from_env() -> Option<Self> {
+        let dir = std::env::var(Self::ENV_DIR).ok()?;
+        let fixtures = match std::env::var(Self::ENV_MODE).ok()?.as_str() {
+            "record" => Self::record(dir),
+            "replay" => Self::replay(dir),
+            _        => return None,
+        };
+
+        let character = std::env::var(Self::ENV_CHARACTER)
+            .ok()
+            .and_then(|x| x.parse::<CharacterId>().ok());
+        Some(Self { character, ..fixtures })
+    }
+
+    /// Gets whether responses are recorded or replayed.
+    ///
+    /// # Returns
+    ///
+    /// Configured mode
+    ///
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    /// Gets the directory of the fixture files.
+    ///
+    /// # Returns
+    ///
+    /// Configured directory
+    ///
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Gets the character whose responses are replayed.
+    ///
+    /// # Returns
+    ///
+    /// Configured character, [None] if not set
+    ///
+    pub fn replay_character(&self) -> Option<CharacterId> {
+        self.character
+    }
+
+    /// Resolves a request either from its fixture or by sending it and
+    /// recording the response.
+    ///
+    /// # Params
+    ///
+    /// * `method` -> Method of the request, part of the file name
+    /// * `key`    -> Full url of the request, with body hash and character if needed
+    /// * `send`   -> Sends the request
+    ///
+    /// # Errors
+    ///
+    /// - If there is no fixture for the request while replaying
+    /// - If the fixture could not be read or written
+    /// - If the request fails while recording
+    ///
+    /// # Returns
+    ///
+    /// Recorded or new response
+    ///
+    pub(crate) async fn resolve<F, Fut>(
+        &self,
+        method: &str,
+        key:    &str,
+        send:   F,
+    ) -> Result<CacheEntry, ConnectError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<CacheEntry, ConnectError>>,
+    {
+        let path = self.dir.join(Self::file_name(method, key));
+
+        match self.mode {
+            FixtureMode::Replay => {
+                if !path.exists() {
+                    return Err(ConnectError::FixtureNotFound(path.display().to_string()));
+                }
+
+                let content = std::fs::read(&path).map_err(ConnectError::FixtureIoError)?;
+                let fixture = serde_json::from_slice::<Fixture>(&content)
+                    .map_err(ConnectError::FixtureParseError)?;
+                Ok(fixture.into_entry())
+            }
+            FixtureMode::Record => {
+                let entry = send().await?;
+
+                let fixture = Fixture::from_entry(key, &entry);
+                let content = serde_json::to_vec_pretty(&fixture)
+                    .map_err(ConnectError::FixtureParseError)?;
+                std::fs::create_dir_all(&self.dir).map_err(ConnectError::FixtureIoError)?;
+                std::fs::write(&path, content).map_err(ConnectError::FixtureIoError)?;
+                Ok(entry)
+            }
+        }
+    }
+
+    /// Creates the file name for a request.
+    ///
+    /// The host is not part of the name, so that fixtures recorded against
+    /// the EVE-API can be replayed by a client with a different url.
+    ///
+    /// # Params
+    ///
+    /// * `method` -> Method of the request
+    /// * `key`    -> Full url of the request
+    ///
+    /// # Returns
+    ///
+    /// File name, for example `GET_latest_markets_prices.json` or
+    /// `GET_latest_characters_1_assets_1.json` for authenticated requests
+    ///
+    fn file_name(method: &str, key: &str) -> String {
+        let request = Url::parse(key).map_or_else(
+            |_| key.to_string(),
+            |x| {
+                let mut request = x.path().to_string();
+                if let Some(query) = x.query() {
+                    request.push('_');
+                    request.push_str(query);
+                }
+                if let Some(hash) = x.fragment() {
+                    request.push('_');
+                    request.push_str(hash);
+                }
+                request
+            },
+        );
+
+        let request = request
+            .chars()
+            .map(|x| if x.is_ascii_alphanumeric() { x } else { '_' })
+            .collect::<String>();
+        format!("{}_{}.json", method, request.trim_matches('_'))
+    }
+}
+
+/// Content of a fixture file
+#[derive(Debug, Deserialize, Serialize)]
+struct Fixture {
+    /// Url the response was recorded from
+    url:   String,
+    /// Value of the `X-Pages` header
+    #[serde(default)]
+    pages: u32,
+    /// Body of the response, stored as json if possible
+    body:  FixtureBody,
+}
+
+/// Body of a recorded response
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum FixtureBody {
+    /// Body that is valid json, stored as is so that it can be read and
+    /// edited
+    Json(serde_json::Value),
+    /// Any other body, stored as text
+    Text(String),
+}
+
+impl Fixture {
+    /// Creates a fixture from a response.
+    ///
+    /// # Params
+    ///
+    /// * `url`   -> Url the response belongs to
+    /// * `entry` -> Response of the EVE-API
+    ///
+    /// # Returns
+    ///
+    /// New fixture
+    ///
+    fn from_entry(url: &str, entry: &CacheEntry) -> Self {
+        let body = serde_json::from_slice::<serde_json::Value>(&entry.body).map_or_else(
+            |_| FixtureBody::Text(String::from_utf8_lossy(&entry.body).into()),
+            FixtureBody::Json,
+        );
+
+        Self {
+            url: url.into(),
+            pages: entry.pages,
+            body,
+        }
+    }
+
+    /// Converts the fixture back into a response.
+    ///
+    /// # Returns
+    ///
+    /// Response that never expires
+    ///
+    fn into_entry(self) -> CacheEntry {
+        let body = match self.body {
+            FixtureBody::Json(x) => x.to_string().into_bytes(),
+            FixtureBody::Text(x) => x.into_bytes(),
+        };
+
+        CacheEntry {
+            body,
+            etag:          None,
+            last_modified: None,
+            expires:       None,
+            pages:         self.pages,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod fixture_tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::{EveCharacterService, EveClientBuilder, EveMarketService, ItemId, OrderType, RetryPolicy};
+    use hyper::Method;
+
+    /// Creates an empty directory for a single test
+    fn fixture_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("caph_fixtures_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Builder that can not reach any server and replays the responses of
+    /// the given character
+    fn offline_builder(dir: &Path, character_id: CharacterId) -> EveClientBuilder {
+        EveClientBuilder::new()
+            .esi_url("http://127.0.0.1:1")
+            .sso_url("http://127.0.0.1:1")
+            .user_agent("caph_connector tests")
+            .credentials("caph_client_id", "caph_secret_key")
+            .retry_policy(RetryPolicy::no_retry())
+            .fixtures(Fixtures::replay(dir).character(character_id))
+    }
+
+    /// Mocks the token endpoint to issue tokens for the given character
+    fn mock_token(esi: &MockEsi, character_id: CharacterId) {
+        esi.mock(
+            Method::POST,
+            "/v2/oauth/token",
+            MockResponse::json(
+                serde_json::json!({
+                    "access_token":  sign(&claims(character_id)),
+                    "token_type":    "Bearer",
+                    "expires_in":    1199,
+                    "refresh_token": REFRESH_TOKEN,
+                })
+                .to_string(),
+            ),
+        );
+    }
+
+    #[test]
+    fn file_names_do_not_contain_the_host() {
+        assert_eq!(
+            Fixtures::file_name("GET", "https://esi.evetech.net/latest/markets/10000002/orders?order_type=all&page=2"),
+            "GET_latest_markets_10000002_orders_order_type_all_page_2.json"
+        );
+        assert_eq!(
+            Fixtures::file_name("POST", "http://127.0.0.1:1/latest/universe/names#a1b2"),
+            "POST_latest_universe_names_a1b2.json"
+        );
+    }
+
+    #[tokio::test]
+    async fn recorded_responses_are_replayed_offline() {
+        let dir = fixture_dir("replay");
+        let esi = MockEsi::start().await;
+        let path = format!("/latest/markets/{}/orders?order_type=all", REGION_ID);
+        esi.mock(Method::GET, &path, MockResponse::json("[]").header("x-pages", "2"));
+        esi.mock(Method::GET, &format!("{}&page=2", path), MockResponse::json("[]").header("x-pages", "2"));
+
+        let service = EveCharacterService::new(CHARACTER_ID);
+        let recorded = {
+            let client = esi.builder().fixtures(Fixtures::record(&dir)).build_auth(REFRESH_TOKEN.into()).unwrap();
+            let assets = service.assets(&client).await.unwrap();
+            let names = service.asset_names(&client, vec![ItemId(1)]).await.unwrap();
+
+            let client = esi.builder().fixtures(Fixtures::record(&dir)).build().unwrap();
+            EveMarketService.orders(&client, REGION_ID, OrderType::All, None).await.unwrap();
+            (assets.len(), names.len())
+        };
+        assert!(std::fs::read_dir(&dir).unwrap().count() >= 4);
+        let requests = esi.requests().len();
+
+        let client = offline_builder(&dir, CHARACTER_ID).build_auth(String::new()).unwrap();
+        let assets = service.assets(&client).await.unwrap();
+        let names = service.asset_names(&client, vec![ItemId(1)]).await.unwrap();
+        assert_eq!((assets.len(), names.len()), recorded);
+
+        let client = offline_builder(&dir, CHARACTER_ID).build().unwrap();
+        EveMarketService.orders(&client, REGION_ID, OrderType::All, None).await.unwrap();
+
+        // nothing reached the server while replaying
+        assert_eq!(esi.requests().len(), requests);
+
+        match service.blueprints(&offline_builder(&dir, CHARACTER_ID).build_auth(String::new()).unwrap()).await {
+            Err(ConnectError::FixtureNotFound(x)) => assert!(x.ends_with(&format!("blueprints_{}.json", CHARACTER_ID))),
+            x => panic!("Unexpected result {:?}", x),
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn responses_are_recorded_per_character() {
+        let dir = fixture_dir("characters");
+        let esi = MockEsi::start().await;
+        let other = CharacterId(2_117_000_002);
+        let service = EveCharacterService::new(CHARACTER_ID);
+        let path = format!("/latest/characters/{}/assets", CHARACTER_ID);
+
+        // both characters request the same route, but get different answers
+        esi.mock(Method::GET, &path, MockResponse::json(r#"[{"is_singleton": true, "item_id": 1, "location_flag": "Hangar", "location_id": 1, "location_type": "station", "quantity": 1, "type_id": 1}]"#));
+        let client = esi.builder().fixtures(Fixtures::record(&dir)).build_auth(REFRESH_TOKEN.into()).unwrap();
+        assert_eq!(service.assets(&client).await.unwrap().len(), 1);
+
+        mock_token(&esi, other);
+        esi.mock(Method::GET, &path, MockResponse::json("[]"));
+        let client = esi.builder().fixtures(Fixtures::record(&dir)).build_auth(REFRESH_TOKEN.into()).unwrap();
+        assert!(service.assets(&client).await.unwrap().is_empty());
+
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        let client = offline_builder(&dir, CHARACTER_ID).build_auth(String::new()).unwrap();
+        assert_eq!(service.assets(&client).await.unwrap().len(), 1);
+        let client = offline_builder(&dir, other).build_auth(String::new()).unwrap();
+        assert!(service.assets(&client).await.unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{CorporationId, IndustryActivity, LocationId, RegionId, SystemId, TypeId};
+use crate::{ConnectError, Endpoint, RequestClient};
+
+/// Cost indices of all systems
+const SYSTEMS: Endpoint = Endpoint::new("latest/industry/systems");
+/// All public industry facilities
+const FACILITIES: Endpoint = Endpoint::new("latest/industry/facilities");
+
+/// Wrapper for the public industry endpoints
+#[derive(Debug, Default)]
+pub struct EveIndustryService;
+
+impl EveIndustryService {
+    /// Gets the cost indices of all systems.
+    ///
+    /// The EVE API caches the result for one hour.
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    ///
+    /// # Returns
+    ///
+    /// Cost indices of every system that had industry activity
+    ///
+    pub async fn systems(
+        &self,
+        client: &impl RequestClient,
+    ) -> Result<Vec<IndustrySystem>, ConnectError> {
+        client
+            .fetch_route::<Vec<IndustrySystem>>(&SYSTEMS.route())
+            .await
+    }
+
+    /// Gets the cost indices of all systems and the point in time the
+    /// EVE API updates them.
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    ///
+    /// # Returns
+    ///
+    /// Cost indices of every system that had industry activity and the
+    /// `Expires` header of the response, if it is set
+    ///
+    pub async fn systems_expires(
+        &self,
+        client: &impl RequestClient,
+    ) -> Result<(Vec<IndustrySystem>, Option<DateTime<Utc>>), ConnectError> {
+        client
+            .fetch_route_expires::<Vec<IndustrySystem>>(&SYSTEMS.route())
+            .await
+    }
+
+    /// Gets all public industry facilities.
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    ///
+    /// # Returns
+    ///
+    /// List of all public facilities
+    ///
+    pub async fn facilities(
+        &self,
+        client: &impl RequestClient,
+    ) -> Result<Vec<IndustryFacility>, ConnectError> {
+        client
+            .fetch_route::<Vec<IndustryFacility>>(&FACILITIES.route())
+            .await
+    }
+}
+
+/// Cost indices of a single system
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IndustrySystem {
+    /// [SystemId] of the system
+    pub solar_system_id: SystemId,
+    /// Cost index for every activity
+    pub cost_indices:    Vec<CostIndex>,
+}
+
+impl IndustrySystem {
+    /// Gets the cost index of the given activity.
+    ///
+    /// # Params
+    ///
+    /// * `activity` -> Activity to get the index for
+    ///
+    /// # Returns
+    ///
+    /// The cost index, or `0` if the system has none for the activity
+    ///
+    pub fn cost_index(&self, activity: IndustryActivity) -> f32 {
+        self.cost_indices
+            .iter()
+            .find(|x| x.activity == activity)
+            .map(|x| x.cost_index)
+            .unwrap_or_default()
+    }
+}
+
+/// Cost index of a single activity
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CostIndex {
+    /// Activity the index belongs to
+    #[serde(deserialize_with = "IndustryActivity::from_name")]
+    pub activity:   IndustryActivity,
+    /// The cost index
+    pub cost_index: f32,
+}
+
+/// Represents a public industry facility
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IndustryFacility {
+    /// Id of the station or structure
+    pub facility_id:     LocationId,
+    /// Owner of the facility
+    pub owner_id:        CorporationId,
+    /// Region the facility is located in
+    pub region_id:       RegionId,
+    /// System the facility is located in
+    pub solar_system_id: SystemId,
+    /// Tax of the facility
+    #[serde(default)]
+    pub tax:             Option<f32>,
+    /// [TypeId] of the station or structure
+    pub type_id:         TypeId,
+}
+
+impl IndustryActivity {
+    /// Parses the activity names used by the industry endpoints.
+    ///
+    /// # Errors
+    ///
+    /// If the value is not a string
+    ///
+    /// # Returns
+    ///
+    /// The matching activity, [IndustryActivity::Unknown] for unknown names
+    ///
+    fn from_name<'de, D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match String::deserialize(d)?.as_str() {
+            "manufacturing"                   => Self::Manufacturing,
+            "researching_material_efficiency" => Self::MaterialEfficiencyResearch,
+            "researching_time_efficiency"     => Self::TimeEfficiencyResearch,
+            "copying"                         => Self::Copying,
+            "invention"                       => Self::Invention,
+            "reaction"                        => Self::Reactions,
+            _                                 => Self::Unknown,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod industry_tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[tokio::test]
+    async fn systems_and_facilities() {
+        let esi = MockEsi::start().await;
+        let client = esi.client().unwrap();
+
+        let systems = EveIndustryService.systems(&client).await.unwrap();
+        assert_eq!(systems[0].solar_system_id, SYSTEM_ID);
+        assert_eq!(systems[0].cost_index(IndustryActivity::Manufacturing), 0.0865);
+        assert_eq!(systems[0].cost_index(IndustryActivity::Reactions), 0.0);
+
+        let facilities = EveIndustryService.facilities(&client).await.unwrap();
+        assert_eq!(facilities[0].facility_id, LocationId(*STATION_ID));
+    }
+}
@@ -0,0 +1,642 @@
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use jsonwebtoken::jwk::JwkSet;
+use serde::{Deserialize, Deserializer};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::{CharacterId, ConnectError};
+
+/// Validates access tokens issued by the EVE-SSO.
+///
+/// The signature is checked against the public keys of the EVE-SSO, besides
+/// that the issuer, the audience and the expiry of the token are checked.
+///
+/// # Required ENV
+///
+/// Only required when using [EveJwtValidator::from_env]
+///
+/// * `EVE_CLIENT_ID`  -> Client ID provided by EVE when creating the application
+/// * `EVE_USER_AGENT` -> User agent for downloading the keys, not required if `EVE_JWKS_FILE` is set
+///
+/// # Optional ENV
+///
+/// * `EVE_JWKS_FILE` -> Path to a local copy of the keys, preferred over the url
+/// * `EVE_JWKS_URL`  -> Url of the keys, defaults to `https://login.eveonline.com/oauth/jwks`
+///
+/// # Key rotation
+///
+/// The EVE-SSO rotates its keys from time to time. Validators that loaded
+/// their keys from a file or url reload them with
+/// [EveJwtValidator::validate_or_reload] as soon as a token is signed with
+/// an unknown key. Clones of a validator share the keys.
+///
+#[derive(Clone, Debug)]
+pub struct EveJwtValidator {
+    /// Public keys of the EVE-SSO
+    keys:      Arc<RwLock<JwkSet>>,
+    /// Client id of the application, must be part of the audience
+    client_id: String,
+    /// Where the keys were loaded from, [None] if they were given directly
+    source:    Option<JwksSource>,
+    /// Last time the keys were reloaded
+    reloaded:  Arc<RwLock<Option<Instant>>>,
+}
+
+/// Origin of the keys of an [EveJwtValidator]
+#[derive(Clone, Debug)]
+enum JwksSource {
+    /// Local copy of the keys
+    File(PathBuf),
+    /// Url the keys are downloaded from and the client used for it
+    Url(String, reqwest::Client),
+}
+
+impl EveJwtValidator {
+    /// Default url of the keys
+    pub const JWKS_URL: &'static str = "https://login.eveonline.com/oauth/jwks";
+    /// Expected audience besides the client id
+    const AUDIENCE: &'static str = "EVE Online";
+    /// Allowed issuers of the token, the EVE-SSO uses both
+    const ISSUERS: &'static [&'static str] = &[
+        "login.eveonline.com",
+        "https://login.eveonline.com",
+    ];
+
+    /// ENV for the client id
+    const ENV_CLIENT_ID:  &'static str = "EVE_CLIENT_ID";
+    /// ENV for a local copy of the keys
+    const ENV_JWKS_FILE:  &'static str = "EVE_JWKS_FILE";
+    /// ENV for the url of the keys
+    const ENV_JWKS_URL:   &'static str = "EVE_JWKS_URL";
+    /// ENV for the user agent
+    const ENV_USER_AGENT: &'static str = "EVE_USER_AGENT";
+
+    /// Timeout for downloading the keys
+    const JWKS_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Minimum time between two reloads of the keys, so that tokens with
+    /// made up keys do not result in a request each
+    const RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Creates a new validator from the given key set.
+    ///
+    /// # Params
+    ///
+    /// * `jwks`      -> Json containing the public keys
+    /// * `client_id` -> Client id of the application
+    ///
+    /// # Errors
+    ///
+    /// If the keys could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// New validator instance
+    ///
+    pub fn new(
+        jwks:      &str,
+        client_id: impl Into<String>,
+    ) -> Result<Self, ConnectError> {
+        Ok(Self {
+            keys:      Arc::new(RwLock::new(Self::parse_keys(jwks)?)),
+            client_id: client_id.into(),
+            source:    None,
+            reloaded:  Arc::new(RwLock::new(None)),
+        })
+    }
+
+    /// Creates a new validator with keys loaded from the given file.
+    ///
+    /// # Params
+    ///
+    /// * `path`      -> Path to the json file containing the public keys
+    /// * `client_id` -> Client id of the application
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read or parsed
+    ///
+    /// # Returns
+    ///
+    /// New validator instance
+    ///
+    pub fn from_file(
+        path:      impl AsRef<Path>,
+        client_id: impl Into<String>,
+    ) -> Result<Self, ConnectError> {
+        let jwks = std::fs::read_to_string(path.as_ref())
+            .map_err(ConnectError::JwksReadError)?;
+        Ok(Self {
+            source: Some(JwksSource::File(path.as_ref().into())),
+            ..Self::new(&jwks, client_id)?
+        })
+    }
+
+    /// Creates a new validator with keys downloaded from the given url.
+    ///
+    /// The given client is also used when the keys are reloaded, it should
+    /// send the user agent of the application.
+    ///
+    /// # Params
+    ///
+    /// * `url`       -> Url of the public keys
+    /// * `client_id` -> Client id of the application
+    /// * `client`    -> HTTP client used for downloading the keys
+    ///
+    /// # Errors
+    ///
+    /// If the keys could not be downloaded or parsed
+    ///
+    /// # Returns
+    ///
+    /// New validator instance
+    ///
+    pub async fn from_url(
+        url:       &str,
+        client_id: impl Into<String>,
+        client:    reqwest::Client,
+    ) -> Result<Self, ConnectError> {
+        let source = JwksSource::Url(url.into(), client);
+        let jwks = Self::load_keys(&source).await?;
+        Ok(Self {
+            source: Some(source),
+            ..Self::new(&jwks, client_id)?
+        })
+    }
+
+    /// Creates a new validator using the ENVs documented on the struct.
+    ///
+    /// # Errors
+    ///
+    /// - If the ENV `EVE_CLIENT_ID` is not set
+    /// - If the keys are downloaded and the ENV `EVE_USER_AGENT` is not set
+    /// - If the keys could not be loaded
+    ///
+    /// # Returns
+    ///
+    /// New validator instance
+    ///
+    pub async fn from_env() -> Result<Self, ConnectError> {
+        let client_id = std::env::var(Self::ENV_CLIENT_ID)
+            .map_err(|_| ConnectError::env_client_id())?;
+
+        if let Ok(path) = std::env::var(Self::ENV_JWKS_FILE) {
+            Self::from_file(path, client_id)
+        } else {
+            let url = std::env::var(Self::ENV_JWKS_URL)
+                .unwrap_or_else(|_| Self::JWKS_URL.into());
+            let user_agent = std::env::var(Self::ENV_USER_AGENT)
+                .map_err(|_| ConnectError::env_user_agent())?;
+            let client = reqwest::Client::builder()
+                .user_agent(user_agent)
+                .build()
+                .map_err(ConnectError::CouldNotConstructClient)?;
+            Self::from_url(&url, client_id, client).await
+        }
+    }
+
+    /// Validates the given access token and returns its payload.
+    ///
+    /// # Params
+    ///
+    /// * `token` -> Access token issued by the EVE-SSO
+    ///
+    /// # Errors
+    ///
+    /// - If the token is not signed by a known key
+    /// - If the signature, issuer, audience or expiry is invalid
+    /// - If the payload could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// Validated payload of the token
+    ///
+    pub fn validate(&self, token: &str) -> Result<EveOAuthPayload, ConnectError> {
+        let header = jsonwebtoken::decode_header(token)
+            .map_err(ConnectError::JwtInvalid)?;
+        let key = {
+            let keys = self.keys
+                .read()
+                .unwrap_or_else(PoisonError::into_inner);
+            let jwk = header
+                .kid
+                .as_ref()
+                .and_then(|x| keys.find(x))
+                .ok_or_else(|| ConnectError::JwtUnknownKey(header.kid.clone()))?;
+            DecodingKey::from_jwk(jwk)
+                .map_err(ConnectError::JwtInvalid)?
+        };
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_issuer(Self::ISSUERS);
+        validation.set_audience(&[&self.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let payload = jsonwebtoken::decode::<EveOAuthPayload>(token, &key, &validation)
+            .map_err(ConnectError::JwtInvalid)?
+            .claims;
+
+        if !payload.aud.iter().any(|x| x == Self::AUDIENCE) {
+            return Err(ConnectError::JwtInvalid(
+                jsonwebtoken::errors::ErrorKind::InvalidAudience.into()
+            ));
+        }
+
+        Ok(payload)
+    }
+
+    /// Validates the given access token, reloading the keys if the token is
+    /// signed with an unknown key.
+    ///
+    /// The keys are reloaded at most once per [Self::RELOAD_INTERVAL] and
+    /// only if they were loaded from a file or url.
+    ///
+    /// # Params
+    ///
+    /// * `token` -> Access token issued by the EVE-SSO
+    ///
+    /// # Errors
+    ///
+    /// - Same as [EveJwtValidator::validate]
+    /// - If the keys could not be reloaded
+    ///
+    /// # Returns
+    ///
+    /// Validated payload of the token
+    ///
+    pub async fn validate_or_reload(&self, token: &str) -> Result<EveOAuthPayload, ConnectError> {
+        match self.validate(token) {
+            Err(ConnectError::JwtUnknownKey(kid)) => {
+                if !self.reload().await? {
+                    return Err(ConnectError::JwtUnknownKey(kid));
+                }
+                self.validate(token)
+            }
+            x => x,
+        }
+    }
+
+    /// Loads the keys again from their source.
+    ///
+    /// # Errors
+    ///
+    /// If the keys could not be loaded or parsed
+    ///
+    /// # Returns
+    ///
+    /// `true` if the keys were reloaded, `false` if there is no source or
+    /// the last reload was less than [Self::RELOAD_INTERVAL] ago
+    ///
+    async fn reload(&self) -> Result<bool, ConnectError> {
+        let source = if let Some(x) = self.source.as_ref() {
+            x
+        } else {
+            return Ok(false);
+        };
+
+        {
+            let mut reloaded = self.reloaded
+                .write()
+                .unwrap_or_else(PoisonError::into_inner);
+            if reloaded.map(|x| x.elapsed() < Self::RELOAD_INTERVAL).unwrap_or_default() {
+                return Ok(false);
+            }
+            *reloaded = Some(Instant::now());
+        }
+
+        tracing::info!("Unknown key, reloading the keys of the EVE-SSO");
+        let keys = Self::parse_keys(&Self::load_keys(source).await?)?;
+        *self.keys
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = keys;
+        Ok(true)
+    }
+
+    /// Reads the keys from the given source.
+    ///
+    /// # Params
+    ///
+    /// * `source` -> File or url of the keys
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read or the keys could not be downloaded
+    ///
+    /// # Returns
+    ///
+    /// Json containing the public keys
+    ///
+    async fn load_keys(source: &JwksSource) -> Result<String, ConnectError> {
+        match source {
+            JwksSource::File(path) => {
+                std::fs::read_to_string(path)
+                    .map_err(ConnectError::JwksReadError)
+            }
+            JwksSource::Url(url, client) => {
+                client
+                    .get(url)
+                    .timeout(Self::JWKS_TIMEOUT)
+                    .send()
+                    .await
+                    .and_then(|x| x.error_for_status())
+                    .map_err(ConnectError::JwksDownloadFailed)?
+                    .text()
+                    .await
+                    .map_err(ConnectError::JwksDownloadFailed)
+            }
+        }
+    }
+
+    /// Parses the given key set.
+    ///
+    /// # Params
+    ///
+    /// * `jwks` -> Json containing the public keys
+    ///
+    /// # Errors
+    ///
+    /// If the keys could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// Parsed keys
+    ///
+    fn parse_keys(jwks: &str) -> Result<JwkSet, ConnectError> {
+        serde_json::from_str::<JwkSet>(jwks)
+            .map_err(ConnectError::JwksParseError)
+    }
+}
+
+/// Decoded access token
+#[derive(Clone, Debug, Deserialize)]
+pub struct EveOAuthPayload {
+    /// List of all permissions that where granted
+    #[serde(rename = "scp", default, deserialize_with = "deserialize_scopes")]
+    pub scopes:     Vec<String>,
+    /// User identification, format `CHARACTER:EVE:<character_id>`
+    pub sub:        String,
+    /// Name of the character
+    #[serde(default)]
+    pub name:       String,
+    /// Hash of the owner, changes when the character is transferred
+    #[serde(default)]
+    pub owner:      String,
+    /// Issuer of the token
+    pub iss:        String,
+    /// Audience of the token, contains the client id
+    pub aud:        Vec<String>,
+    /// Time the token expires
+    #[serde(rename = "exp", with = "chrono::serde::ts_seconds")]
+    pub expires_at: DateTime<Utc>,
+}
+
+impl EveOAuthPayload {
+    /// Gets the character id from the user identification.
+    ///
+    /// # Errors
+    ///
+    /// If the user identification could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// The character id
+    ///
+    pub fn character_id(&self) -> Result<CharacterId, ConnectError> {
+        let character_id = self
+            .sub
+            .replace("CHARACTER:EVE:", "")
+            .parse::<i32>()
+            .map_err(ConnectError::OAuthParseCharacterId)?;
+        Ok(character_id.into())
+    }
+
+    /// Checks if the given scope was granted.
+    ///
+    /// # Params
+    ///
+    /// * `scope` -> Scope to check, for example `esi-assets.read_assets.v1`
+    ///
+    /// # Returns
+    ///
+    /// `true` if the scope was granted
+    ///
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|x| x == scope)
+    }
+
+    /// Checks if the token is expired.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the token is no longer valid
+    ///
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+/// Parses the `scp` field, the EVE-SSO sends a single scope as string and
+/// multiple scopes as list.
+///
+/// # Errors
+///
+/// If the value is neither a string nor a list of strings
+///
+/// # Returns
+///
+/// List of all scopes
+///
+fn deserialize_scopes<'de, D>(d: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    /// Either a single scope or a list of scopes
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Scp {
+        /// A single scope
+        Str(String),
+        /// Multiple scopes
+        StrArray(Vec<String>),
+    }
+
+    Ok(match Scp::deserialize(d)? {
+        Scp::Str(x)      => vec![x],
+        Scp::StrArray(x) => x,
+    })
+}
+
+/// Reads the character id from an access token without validating it.
+///
+/// Only meant for tokens that were received directly from the EVE-SSO, for
+/// example to add the character to errors.
+///
+/// # Params
+///
+/// * `token` -> Access token issued by the EVE-SSO
+///
+/// # Returns
+///
+/// - [None] if the payload could not be read
+/// - [CharacterId] of the token
+///
+pub(crate) fn unverified_character_id(token: &str) -> Option<CharacterId> {
+    /// Subject of the payload
+    #[derive(Deserialize)]
+    struct Subject {
+        /// User identification
+        sub: String,
+    }
+
+    unverified_claims::<Subject>(token)?
+        .sub
+        .replace("CHARACTER:EVE:", "")
+        .parse::<i32>()
+        .ok()
+        .map(Into::into)
+}
+
+/// Reads the expiry from an access token without validating it.
+///
+/// Same restrictions as [unverified_character_id], the expiry is only used
+/// to refresh the token before the EVE-API rejects it.
+///
+/// # Params
+///
+/// * `token` -> Access token issued by the EVE-SSO
+///
+/// # Returns
+///
+/// - [None] if the payload could not be read
+/// - Unix timestamp in seconds at which the token expires
+///
+pub(crate) fn unverified_expiry(token: &str) -> Option<i64> {
+    /// Expiry of the payload
+    #[derive(Deserialize)]
+    struct Expiry {
+        /// Unix timestamp of the expiry
+        exp: i64,
+    }
+
+    unverified_claims::<Expiry>(token).map(|x| x.exp)
+}
+
+/// Reads the granted scopes from an access token without validating it.
+///
+/// Same restrictions as [unverified_character_id], the scopes are only used
+/// to reject requests before they are send.
+///
+/// # Params
+///
+/// * `token` -> Access token issued by the EVE-SSO
+///
+/// # Returns
+///
+/// - [None] if the payload could not be read
+/// - All scopes of the token
+///
+pub(crate) fn unverified_scopes(token: &str) -> Option<Vec<String>> {
+    /// Scopes of the payload
+    #[derive(Deserialize)]
+    struct Scopes {
+        /// List of all granted scopes
+        #[serde(rename = "scp", default, deserialize_with = "deserialize_scopes")]
+        scopes: Vec<String>,
+    }
+
+    unverified_claims::<Scopes>(token).map(|x| x.scopes)
+}
+
+/// Decodes the payload of an access token without validating it.
+///
+/// # Params
+///
+/// * `T`     -> Claims to read
+/// * `token` -> Access token issued by the EVE-SSO
+///
+/// # Returns
+///
+/// - [None] if the payload could not be decoded or parsed
+/// - Parsed claims
+///
+fn unverified_claims<T>(token: &str) -> Option<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let payload = token.split('.').nth(1)?;
+    let payload = base64::decode_config(payload, base64::URL_SAFE_NO_PAD).ok()?;
+    serde_json::from_slice::<T>(&payload).ok()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod jwt_tests {
+    use super::*;
+    use crate::testing::*;
+    use hyper::Method;
+
+    #[tokio::test]
+    async fn validates_signed_token() {
+        let esi = MockEsi::start().await;
+        let validator = esi.builder().jwt_validator().await.unwrap();
+
+        let token = esi.builder().access_token("code").await.unwrap();
+        let payload = token.validate(&validator).unwrap();
+        assert_eq!(payload.character_id().unwrap(), CHARACTER_ID);
+        assert!(payload.has_scope(SCOPES[0]));
+        assert!(!payload.is_expired());
+    }
+
+    #[test]
+    fn rejects_invalid_tokens() {
+        let validator = EveJwtValidator::new(JWKS, "caph_client_id").unwrap();
+        let claims = claims(CHARACTER_ID);
+
+        let mut expired = claims.clone();
+        expired["exp"] = serde_json::json!(Utc::now().timestamp() - 3600);
+        let mut issuer = claims.clone();
+        issuer["iss"] = serde_json::json!("https://example.com");
+        let mut audience = claims;
+        audience["aud"] = serde_json::json!(["other_client_id", "EVE Online"]);
+
+        for claims in [expired, issuer, audience] {
+            let token = sign(&claims);
+            assert!(matches!(validator.validate(&token), Err(ConnectError::JwtInvalid(_))));
+        }
+
+        let mut unsigned = sign(&self::claims(CHARACTER_ID));
+        unsigned.push('x');
+        assert!(validator.validate(&unsigned).is_err());
+
+        let other = EveJwtValidator::new(r#"{"keys": []}"#, "caph_client_id").unwrap();
+        let token = sign(&self::claims(CHARACTER_ID));
+        assert!(matches!(other.validate(&token), Err(ConnectError::JwtUnknownKey(_))));
+    }
+
+    #[tokio::test]
+    async fn unknown_key_reloads_keys() {
+        let esi = MockEsi::start().await;
+        esi.mock(Method::GET, "/oauth/jwks", MockResponse::json(r#"{"keys": []}"#));
+        let validator = esi.builder().jwt_validator().await.unwrap();
+        let token = sign(&claims(CHARACTER_ID));
+        assert!(matches!(validator.validate(&token), Err(ConnectError::JwtUnknownKey(_))));
+
+        // the EVE-SSO rotated its keys
+        esi.mock(Method::GET, "/oauth/jwks", MockResponse::json(JWKS));
+        let payload = validator.clone().validate_or_reload(&token).await.unwrap();
+        assert_eq!(payload.character_id().unwrap(), CHARACTER_ID);
+        assert!(validator.validate(&token).is_ok());
+    }
+
+    #[test]
+    fn single_scope_as_string() {
+        let validator = EveJwtValidator::new(JWKS, "caph_client_id").unwrap();
+        let mut claims = claims(CHARACTER_ID);
+        claims["scp"] = serde_json::json!("publicData");
+
+        let payload = validator.validate(&sign(&claims)).unwrap();
+        assert_eq!(payload.scopes, vec!["publicData".to_string()]);
+    }
+}
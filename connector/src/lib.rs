@@ -23,36 +23,86 @@
 #![allow(clippy::redundant_field_names)]
 #![feature(stmt_expr_attributes)]
 
+/// Module containing caches for API responses
+mod cache;
 /// Module for handling characters
 mod character;
 /// Module containing clients to the EVE-API
 mod client;
+/// Module for contracts of characters and corporations
+mod contract;
 /// Module for handling corporations
 mod corporation;
+/// Module for all public industry api requests
+mod industry;
+/// Module for declaring endpoints of the EVE-API
+mod endpoint;
 /// Module containing possible errors
 mod error;
+/// Module for recording and replaying responses of the EVE-API
+#[cfg(feature = "fixtures")]
+mod fixture;
+/// Module for validating access tokens
+mod jwt;
 /// Module containing all macros
 mod macros;
+/// Module for the graph of the universe
+mod map;
+/// Module for all market api requests
+mod market;
+/// Module for moon mining
+mod mining;
+/// Module for fetching paginated endpoints
+mod pagination;
+/// Module for retrying failed requests
+mod retry;
+/// Module for reading the static data export
+pub mod sde;
+/// Module for keeping track of the tokens of authenticated clients
+mod token;
 /// Module for all universe api requests
 mod universe;
+/// Module for wallet journals and transactions
+mod wallet;
+
+/// Module containing a local stand-in for the EVE-API
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 /// Collects all services under one import
 pub mod services {
     pub use crate::character::EveCharacterService;
     pub use crate::corporation::EveCorporationService;
+    pub use crate::industry::EveIndustryService;
+    pub use crate::market::EveMarketService;
 }
 
+pub use self::cache::*;
 pub use self::character::*;
 pub use self::client::*;
+pub use self::contract::*;
 pub use self::corporation::*;
+pub use self::endpoint::*;
 pub use self::error::*;
+#[cfg(feature = "fixtures")]
+pub use self::fixture::*;
+pub use self::industry::*;
+pub use self::jwt::*;
+pub use self::map::*;
+pub use self::market::*;
+pub use self::mining::*;
+pub use self::retry::*;
+pub use self::token::{SharedTokenStore, TokenStore};
 pub use self::universe::*;
+pub use self::wallet::*;
 
 use serde::{Deserialize, Deserializer, Serialize};
 
 eve_id!(AllianceId, i32, u32);
 eve_id!(CategoryId, i32, u32);
 eve_id!(CharacterId, i32, u32);
+eve_id!(ConstellationId, i32, u32);
+eve_id!(ContractId, i32, u32);
 eve_id!(CorporationId, i32, u32);
 eve_id!(GroupId, i32, u32);
 eve_id!(ItemId, i64, u64);
@@ -136,7 +186,7 @@ pub struct IndustryJobEntry {
 }
 
 /// List of all industry activities
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub enum IndustryActivity {
     /// Manufacturing of things
     Manufacturing,
@@ -0,0 +1,902 @@
+//! In-memory graph of the universe.
+//!
+//! The [EveMap] is built from the SDE and answers route questions without
+//! asking the EVE-API, including routes for jump capable ships.
+//!
+//! # Usage
+//!
+//! ``` rust,ignore
+//! use caph_connector::{EveMap, RoutePreference};
+//! use caph_connector::sde::EveSde;
+//!
+//! let sde = EveSde::from_path("./sde.zip").unwrap();
+//! let map = EveMap::from_sde(&sde).unwrap();
+//!
+//! let jita = map.system_by_name("Jita").unwrap().system_id;
+//! let amarr = map.system_by_name("Amarr").unwrap().system_id;
+//! let route = map.route(jita, amarr, RoutePreference::Safest).unwrap();
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+use crate::sde::EveSde;
+use crate::{ConnectError, ConstellationId, RegionId, SystemId};
+
+/// Meters in a light year
+pub const METERS_PER_LY: f64 = 9_460_730_472_580_800.0;
+
+/// Graph of all systems and their stargates
+#[derive(Clone, Debug, Default)]
+pub struct EveMap {
+    /// All regions
+    regions:        HashMap<RegionId, MapRegion>,
+    /// All constellations
+    constellations: HashMap<ConstellationId, MapConstellation>,
+    /// All systems
+    systems:        HashMap<SystemId, MapSystem>,
+    /// Systems that are connected by a stargate
+    gates:          HashMap<SystemId, Vec<SystemId>>,
+}
+
+impl EveMap {
+    /// Directory of the known space within the SDE
+    const SDE_UNIVERSE: &'static str = "sde/fsd/universe/eve/";
+    /// Names of all celestials within the SDE
+    const SDE_NAMES:    &'static str = "sde/bsd/invNames.yaml";
+
+    /// Region of Pochven, jump drives can not be used to get in there
+    const POCHVEN: RegionId = RegionId(10_000_070);
+
+    /// Additional cost of a system that is not in high-sec when looking for
+    /// the safest route
+    const INSECURE_PENALTY: u64 = 1_000;
+
+    /// Creates a new map.
+    ///
+    /// # Params
+    ///
+    /// * `regions`        -> All regions
+    /// * `constellations` -> All constellations
+    /// * `systems`        -> All systems
+    /// * `connections`    -> Pairs of systems connected by a stargate
+    ///
+    /// # Returns
+    ///
+    /// New map instance
+    ///
+    pub fn new(
+        regions:        Vec<MapRegion>,
+        constellations: Vec<MapConstellation>,
+        systems:        Vec<MapSystem>,
+        connections:    Vec<(SystemId, SystemId)>,
+    ) -> Self {
+        let mut gates: HashMap<SystemId, Vec<SystemId>> = HashMap::new();
+        for (a, b) in connections {
+            gates.entry(a).or_default().push(b);
+            gates.entry(b).or_default().push(a);
+        }
+        for neighbours in gates.values_mut() {
+            neighbours.sort();
+            neighbours.dedup();
+        }
+
+        Self {
+            regions:        regions.into_iter().map(|x| (x.region_id, x)).collect(),
+            constellations: constellations.into_iter().map(|x| (x.constellation_id, x)).collect(),
+            systems:        systems.into_iter().map(|x| (x.system_id, x)).collect(),
+            gates,
+        }
+    }
+
+    /// Builds the map of the known space from the SDE.
+    ///
+    /// Wormhole and abyssal space are not part of the map.
+    ///
+    /// # Params
+    ///
+    /// * `sde` -> Loaded SDE
+    ///
+    /// # Errors
+    ///
+    /// If the universe files or the names could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// New map instance
+    ///
+    pub fn from_sde(sde: &EveSde) -> Result<Self, ConnectError> {
+        /// Entry of `invNames.yaml`
+        #[derive(Deserialize)]
+        struct SdeName {
+            /// Id of the celestial
+            #[serde(rename = "itemID")]
+            item_id:   i64,
+            /// Name of the celestial
+            #[serde(rename = "itemName")]
+            item_name: String,
+        }
+
+        /// Content of a `region.yaml`
+        #[derive(Deserialize)]
+        struct SdeRegion {
+            /// Id of the region
+            #[serde(rename = "regionID")]
+            region_id: RegionId,
+        }
+
+        /// Content of a `constellation.yaml`
+        #[derive(Deserialize)]
+        struct SdeConstellation {
+            /// Id of the constellation
+            #[serde(rename = "constellationID")]
+            constellation_id: ConstellationId,
+        }
+
+        /// Content of a `solarsystem.yaml`
+        #[derive(Deserialize)]
+        struct SdeSystem {
+            /// Id of the system
+            #[serde(rename = "solarSystemID")]
+            system_id: SystemId,
+            /// Security status
+            security:  f64,
+            /// Position in meters
+            center:    [f64; 3],
+            /// Stargates of the system
+            #[serde(default)]
+            stargates: HashMap<i64, SdeStargate>,
+        }
+
+        /// Stargate within a `solarsystem.yaml`
+        #[derive(Deserialize)]
+        struct SdeStargate {
+            /// Id of the stargate on the other side
+            destination: i64,
+        }
+
+        let names = sde
+            .parse::<Vec<SdeName>>(Self::SDE_NAMES)?
+            .into_iter()
+            .map(|x| (x.item_id, x.item_name))
+            .collect::<HashMap<_, _>>();
+        let name = |id: i64| names.get(&id).cloned().unwrap_or_default();
+
+        // the directory of a file identifies the region or constellation
+        // it belongs to
+        let region_dirs = sde
+            .parse_all::<SdeRegion>(Self::SDE_UNIVERSE, "/region.yaml")?
+            .into_iter()
+            .map(|(path, x)| (parent_dir(&path).to_string(), x.region_id))
+            .collect::<HashMap<_, _>>();
+        let constellation_dirs = sde
+            .parse_all::<SdeConstellation>(Self::SDE_UNIVERSE, "/constellation.yaml")?
+            .into_iter()
+            .map(|(path, x)| (parent_dir(&path).to_string(), x.constellation_id))
+            .collect::<HashMap<_, _>>();
+
+        let mut constellations = Vec::new();
+        for (dir, constellation_id) in constellation_dirs.iter() {
+            let region_id = match region_dirs.get(parent_dir(dir)) {
+                Some(x) => *x,
+                None    => continue,
+            };
+            constellations.push(MapConstellation {
+                constellation_id: *constellation_id,
+                region_id,
+                name:             name(**constellation_id as i64),
+            });
+        }
+
+        let mut systems = Vec::new();
+        let mut gate_system = HashMap::new();
+        let mut gate_destination = Vec::new();
+        for (path, system) in sde.parse_all::<SdeSystem>(Self::SDE_UNIVERSE, "/solarsystem.yaml")? {
+            let constellation_dir = parent_dir(parent_dir(&path));
+            let (constellation_id, region_id) = match (
+                constellation_dirs.get(constellation_dir),
+                region_dirs.get(parent_dir(constellation_dir)),
+            ) {
+                (Some(c), Some(r)) => (*c, *r),
+                _                  => continue,
+            };
+
+            for (gate_id, gate) in system.stargates {
+                gate_system.insert(gate_id, system.system_id);
+                gate_destination.push((system.system_id, gate.destination));
+            }
+
+            systems.push(MapSystem {
+                system_id:        system.system_id,
+                name:             name(*system.system_id),
+                security:         system.security,
+                constellation_id,
+                region_id,
+                position:         Position {
+                    x: system.center[0],
+                    y: system.center[1],
+                    z: system.center[2],
+                },
+            });
+        }
+
+        let connections = gate_destination
+            .into_iter()
+            .filter_map(|(from, gate)| gate_system.get(&gate).map(|to| (from, *to)))
+            .collect::<Vec<_>>();
+        let regions = region_dirs
+            .into_values()
+            .map(|region_id| MapRegion {
+                region_id,
+                name: name(*region_id as i64),
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Self::new(regions, constellations, systems, connections))
+    }
+
+    /// Gets a single system.
+    ///
+    /// # Params
+    ///
+    /// * `system_id` -> [SystemId] of the system
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the system is not part of the map
+    /// - [MapSystem] of the system
+    ///
+    pub fn system(&self, system_id: SystemId) -> Option<&MapSystem> {
+        self.systems.get(&system_id)
+    }
+
+    /// Gets a single system by its name, the name is case insensitive.
+    ///
+    /// # Params
+    ///
+    /// * `name` -> Name of the system, for example `Jita`
+    ///
+    /// # Returns
+    ///
+    /// - [None] if there is no system with that name
+    /// - [MapSystem] of the system
+    ///
+    pub fn system_by_name(&self, name: &str) -> Option<&MapSystem> {
+        self.systems
+            .values()
+            .find(|x| x.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Gets a single constellation.
+    ///
+    /// # Params
+    ///
+    /// * `constellation_id` -> [ConstellationId] of the constellation
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the constellation is not part of the map
+    /// - [MapConstellation] of the constellation
+    ///
+    pub fn constellation(&self, constellation_id: ConstellationId) -> Option<&MapConstellation> {
+        self.constellations.get(&constellation_id)
+    }
+
+    /// Gets a single region.
+    ///
+    /// # Params
+    ///
+    /// * `region_id` -> [RegionId] of the region
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the region is not part of the map
+    /// - [MapRegion] of the region
+    ///
+    pub fn region(&self, region_id: RegionId) -> Option<&MapRegion> {
+        self.regions.get(&region_id)
+    }
+
+    /// Gets all systems that are connected to the given system by a
+    /// stargate.
+    ///
+    /// # Params
+    ///
+    /// * `system_id` -> [SystemId] of the system
+    ///
+    /// # Returns
+    ///
+    /// List of neighbouring systems, empty if the system is unknown
+    ///
+    pub fn neighbours(&self, system_id: SystemId) -> &[SystemId] {
+        self.gates
+            .get(&system_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Gets the distance between two systems in light years.
+    ///
+    /// # Params
+    ///
+    /// * `from` -> [SystemId] of the first system
+    /// * `to`   -> [SystemId] of the second system
+    ///
+    /// # Returns
+    ///
+    /// - [None] if one of the systems is unknown
+    /// - Distance in light years
+    ///
+    pub fn distance_ly(&self, from: SystemId, to: SystemId) -> Option<f64> {
+        let from = self.systems.get(&from)?;
+        let to = self.systems.get(&to)?;
+        Some(from.position.distance(&to.position) / METERS_PER_LY)
+    }
+
+    /// Checks if a jump drive can jump from one system to the other.
+    ///
+    /// Jump drives can not be activated in high-sec and can not jump into
+    /// high-sec or Pochven.
+    ///
+    /// # Params
+    ///
+    /// * `from`     -> [SystemId] the jump starts
+    /// * `to`       -> [SystemId] of the destination
+    /// * `range_ly` -> Jump range of the ship in light years
+    ///
+    /// # Returns
+    ///
+    /// `true` if the jump is possible
+    ///
+    pub fn can_jump(&self, from: SystemId, to: SystemId, range_ly: f64) -> bool {
+        let (origin, destination) = match (self.systems.get(&from), self.systems.get(&to)) {
+            (Some(a), Some(b)) => (a, b),
+            _                  => return false,
+        };
+
+        from != to &&
+        !origin.is_highsec() &&
+        !destination.is_highsec() &&
+        destination.region_id != Self::POCHVEN &&
+        origin.position.distance(&destination.position) <= range_ly * METERS_PER_LY
+    }
+
+    /// Finds a route between two systems using stargates.
+    ///
+    /// # Params
+    ///
+    /// * `from`       -> [SystemId] the route starts
+    /// * `to`         -> [SystemId] the route ends
+    /// * `preference` -> What the route should optimize for
+    ///
+    /// # Returns
+    ///
+    /// - [None] if there is no route
+    /// - All systems of the route, including start and end
+    ///
+    pub fn route(
+        &self,
+        from:       SystemId,
+        to:         SystemId,
+        preference: RoutePreference,
+    ) -> Option<Vec<SystemId>> {
+        if !self.systems.contains_key(&from) || !self.systems.contains_key(&to) {
+            return None;
+        }
+
+        match preference {
+            RoutePreference::Shortest => self.shortest_route(from, to),
+            RoutePreference::Safest   => {
+                let route = self.cheapest_route(from, to, |_, next| {
+                    if self.systems.get(&next).is_some_and(MapSystem::is_highsec) {
+                        Some(1)
+                    } else {
+                        Some(1 + Self::INSECURE_PENALTY)
+                    }
+                }, |_| Vec::new())?;
+                Some(route.into_iter().map(|(x, _)| x).collect())
+            }
+        }
+    }
+
+    /// Finds a route for a jump capable ship, for example a jump freighter.
+    ///
+    /// Stargates and jumps are combined, so that routes from or to high-sec
+    /// are possible. The route uses as few stargates and jumps as possible.
+    ///
+    /// # Params
+    ///
+    /// * `from`     -> [SystemId] the route starts
+    /// * `to`       -> [SystemId] the route ends
+    /// * `range_ly` -> Jump range of the ship in light years
+    ///
+    /// # Returns
+    ///
+    /// - [None] if there is no route
+    /// - All steps of the route
+    ///
+    pub fn jump_route(
+        &self,
+        from:     SystemId,
+        to:       SystemId,
+        range_ly: f64,
+    ) -> Option<JumpRoute> {
+        if !self.systems.contains_key(&from) || !self.systems.contains_key(&to) {
+            return None;
+        }
+
+        let route = self.cheapest_route(from, to, |_, _| Some(1), |current| {
+            let origin = match self.systems.get(&current) {
+                Some(x) if !x.is_highsec() => x,
+                _                          => return Vec::new(),
+            };
+
+            self.systems
+                .values()
+                .filter(|x| self.can_jump(origin.system_id, x.system_id, range_ly))
+                .map(|x| x.system_id)
+                .collect()
+        })?;
+
+        let mut steps = Vec::new();
+        let mut previous = None;
+        for (system_id, kind) in route {
+            let distance_ly = match (kind, previous) {
+                (RouteStepKind::Jump, Some(p)) => self.distance_ly(p, system_id).unwrap_or_default(),
+                _                              => 0.0,
+            };
+            steps.push(RouteStep { system_id, kind, distance_ly });
+            previous = Some(system_id);
+        }
+        Some(JumpRoute { steps })
+    }
+
+    /// Finds the route with the least stargates.
+    ///
+    /// # Params
+    ///
+    /// * `from` -> [SystemId] the route starts
+    /// * `to`   -> [SystemId] the route ends
+    ///
+    /// # Returns
+    ///
+    /// - [None] if there is no route
+    /// - All systems of the route, including start and end
+    ///
+    fn shortest_route(&self, from: SystemId, to: SystemId) -> Option<Vec<SystemId>> {
+        let mut previous = HashMap::new();
+        let mut visited = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                return Some(Self::collect_route(&previous, to));
+            }
+
+            for next in self.neighbours(current) {
+                if visited.insert(*next) {
+                    previous.insert(*next, current);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Finds the cheapest route with Dijkstra.
+    ///
+    /// # Params
+    ///
+    /// * `from`      -> [SystemId] the route starts
+    /// * `to`        -> [SystemId] the route ends
+    /// * `gate_cost` -> Cost of a stargate, [None] to skip the gate
+    /// * `jumps`     -> Systems reachable with a jump drive, costs `1` each
+    ///
+    /// # Returns
+    ///
+    /// - [None] if there is no route
+    /// - All systems of the route with the way they were reached
+    ///
+    fn cheapest_route<G, J>(
+        &self,
+        from:      SystemId,
+        to:        SystemId,
+        gate_cost: G,
+        jumps:     J,
+    ) -> Option<Vec<(SystemId, RouteStepKind)>>
+    where
+        G: Fn(SystemId, SystemId) -> Option<u64>,
+        J: Fn(SystemId) -> Vec<SystemId>,
+    {
+        let mut costs = HashMap::from([(from, 0u64)]);
+        let mut previous = HashMap::new();
+        let mut queue = BinaryHeap::from([Reverse((0u64, from))]);
+
+        while let Some(Reverse((cost, current))) = queue.pop() {
+            if current == to {
+                let mut route = vec![(to, previous.get(&to).map_or(RouteStepKind::Start, |(_, kind)| *kind))];
+                let mut system = to;
+                while let Some((prev, _)) = previous.get(&system) {
+                    let kind = previous.get(prev).map_or(RouteStepKind::Start, |(_, kind)| *kind);
+                    route.push((*prev, kind));
+                    system = *prev;
+                }
+                route.reverse();
+                return Some(route);
+            }
+            if costs.get(&current).is_some_and(|x| cost > *x) {
+                continue;
+            }
+
+            let gates = self
+                .neighbours(current)
+                .iter()
+                .filter_map(|next| gate_cost(current, *next).map(|x| (*next, x, RouteStepKind::Gate)));
+            let jumps = jumps(current)
+                .into_iter()
+                .map(|next| (next, 1, RouteStepKind::Jump));
+
+            for (next, step_cost, kind) in gates.chain(jumps) {
+                let next_cost = cost + step_cost;
+                if costs.get(&next).is_some_and(|x| *x <= next_cost) {
+                    continue;
+                }
+                costs.insert(next, next_cost);
+                previous.insert(next, (current, kind));
+                queue.push(Reverse((next_cost, next)));
+            }
+        }
+        None
+    }
+
+    /// Walks the route back from the end.
+    ///
+    /// # Params
+    ///
+    /// * `previous` -> System every system was reached from
+    /// * `to`       -> Last system of the route
+    ///
+    /// # Returns
+    ///
+    /// All systems of the route, including start and end
+    ///
+    fn collect_route(previous: &HashMap<SystemId, SystemId>, to: SystemId) -> Vec<SystemId> {
+        let mut route = vec![to];
+        let mut system = to;
+        while let Some(prev) = previous.get(&system) {
+            route.push(*prev);
+            system = *prev;
+        }
+        route.reverse();
+        route
+    }
+}
+
+/// Region of the map
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MapRegion {
+    /// [RegionId] of the region
+    pub region_id: RegionId,
+    /// Name of the region
+    pub name:      String,
+}
+
+/// Constellation of the map
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MapConstellation {
+    /// [ConstellationId] of the constellation
+    pub constellation_id: ConstellationId,
+    /// Region the constellation is located in
+    pub region_id:        RegionId,
+    /// Name of the constellation
+    pub name:             String,
+}
+
+/// System of the map
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MapSystem {
+    /// [SystemId] of the system
+    pub system_id:        SystemId,
+    /// Name of the system
+    pub name:             String,
+    /// Exact security status, the game shows it rounded
+    pub security:         f64,
+    /// Constellation the system is located in
+    pub constellation_id: ConstellationId,
+    /// Region the system is located in
+    pub region_id:        RegionId,
+    /// Position of the system within the universe
+    pub position:         Position,
+}
+
+impl MapSystem {
+    /// Checks if the system is high-sec.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the rounded security status is at least `0.5`
+    ///
+    pub fn is_highsec(&self) -> bool {
+        self.security >= 0.45
+    }
+}
+
+/// Position of a system in meters
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct Position {
+    /// X coordinate
+    pub x: f64,
+    /// Y coordinate
+    pub y: f64,
+    /// Z coordinate
+    pub z: f64,
+}
+
+impl Position {
+    /// Gets the distance to the given position.
+    ///
+    /// # Params
+    ///
+    /// * `other` -> Position to measure the distance to
+    ///
+    /// # Returns
+    ///
+    /// Distance in meters
+    ///
+    pub fn distance(&self, other: &Position) -> f64 {
+        ((self.x - other.x).powi(2) +
+         (self.y - other.y).powi(2) +
+         (self.z - other.z).powi(2)).sqrt()
+    }
+}
+
+/// What a route should optimize for
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutePreference {
+    /// Least number of stargates
+    Shortest,
+    /// Stays in high-sec as long as possible
+    Safest,
+}
+
+/// Route of a jump capable ship
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JumpRoute {
+    /// All steps of the route, the first step is the start
+    pub steps: Vec<RouteStep>,
+}
+
+impl JumpRoute {
+    /// Gets the number of stargates used.
+    ///
+    /// # Returns
+    ///
+    /// Number of stargates
+    ///
+    pub fn gates(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|x| x.kind == RouteStepKind::Gate)
+            .count()
+    }
+
+    /// Gets the number of jumps.
+    ///
+    /// # Returns
+    ///
+    /// Number of jumps
+    ///
+    pub fn jumps(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|x| x.kind == RouteStepKind::Jump)
+            .count()
+    }
+
+    /// Gets the distance of all jumps.
+    ///
+    /// # Returns
+    ///
+    /// Distance in light years
+    ///
+    pub fn distance_ly(&self) -> f64 {
+        self.steps
+            .iter()
+            .map(|x| x.distance_ly)
+            .sum()
+    }
+
+    /// Calculates the fuel needed for all jumps.
+    ///
+    /// # Params
+    ///
+    /// * `fuel_per_ly` -> Isotopes per light year after skills are applied
+    ///
+    /// # Returns
+    ///
+    /// Number of isotopes, rounded up for every jump
+    ///
+    pub fn fuel(&self, fuel_per_ly: f64) -> u64 {
+        self.steps
+            .iter()
+            .filter(|x| x.kind == RouteStepKind::Jump)
+            .map(|x| (x.distance_ly * fuel_per_ly).ceil() as u64)
+            .sum()
+    }
+}
+
+/// Single step of a [JumpRoute]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RouteStep {
+    /// System that is reached with this step
+    pub system_id:   SystemId,
+    /// How the system is reached
+    pub kind:        RouteStepKind,
+    /// Distance of the jump in light years, `0` for stargates
+    pub distance_ly: f64,
+}
+
+/// How a system of a route is reached
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteStepKind {
+    /// First system of the route
+    Start,
+    /// Reached through a stargate
+    Gate,
+    /// Reached with a jump drive
+    Jump,
+}
+
+/// Gets the parent directory of a path within the zip.
+///
+/// # Params
+///
+/// * `path` -> Path separated by `/`
+///
+/// # Returns
+///
+/// Everything before the last `/`, empty if there is none
+///
+fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(x, _)| x)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod map_tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::ZipWriter;
+    use zip::write::FileOptions;
+
+    /// Creates a system with the given position in light years
+    fn system(id: i64, security: f64, x_ly: f64) -> MapSystem {
+        MapSystem {
+            system_id:        SystemId(id),
+            name:             format!("System {}", id),
+            security,
+            constellation_id: ConstellationId(20_000_001),
+            region_id:        RegionId(10_000_001),
+            position:         Position { x: x_ly * METERS_PER_LY, y: 0.0, z: 0.0 },
+        }
+    }
+
+    /// Small map
+    ///
+    /// ```text
+    /// 1 (1.0) - 2 (0.9) - 3 (0.7) - 4 (0.5) - 5 (0.3) - 6 (-0.2)
+    ///  \                                                /
+    ///   7 (0.2) --------------------------------------
+    /// ```
+    fn map() -> EveMap {
+        let systems = vec![
+            system(1, 1.0, 0.0),
+            system(2, 0.9, 2.0),
+            system(3, 0.7, 4.0),
+            system(4, 0.5, 6.0),
+            system(5, 0.3, 8.0),
+            system(6, -0.2, 14.0),
+            system(7, 0.2, 1.0),
+        ];
+        let connections = vec![
+            (SystemId(1), SystemId(2)),
+            (SystemId(2), SystemId(3)),
+            (SystemId(3), SystemId(4)),
+            (SystemId(4), SystemId(5)),
+            (SystemId(5), SystemId(6)),
+            (SystemId(1), SystemId(7)),
+            (SystemId(7), SystemId(6)),
+        ];
+        EveMap::new(Vec::new(), Vec::new(), systems, connections)
+    }
+
+    #[test]
+    fn gate_routes() {
+        let map = map();
+
+        let shortest = map.route(SystemId(1), SystemId(6), RoutePreference::Shortest).unwrap();
+        assert_eq!(shortest, vec![SystemId(1), SystemId(7), SystemId(6)]);
+
+        let safest = map.route(SystemId(1), SystemId(4), RoutePreference::Safest).unwrap();
+        assert_eq!(safest, vec![SystemId(1), SystemId(2), SystemId(3), SystemId(4)]);
+
+        assert!(map.route(SystemId(1), SystemId(99), RoutePreference::Shortest).is_none());
+    }
+
+    #[test]
+    fn jump_range_and_distance() {
+        let map = map();
+
+        assert_eq!(map.distance_ly(SystemId(5), SystemId(6)), Some(6.0));
+        assert!(map.can_jump(SystemId(5), SystemId(6), 6.0));
+        assert!(!map.can_jump(SystemId(5), SystemId(6), 5.9));
+        // no jumps into or out of high-sec
+        assert!(!map.can_jump(SystemId(5), SystemId(4), 10.0));
+        assert!(!map.can_jump(SystemId(4), SystemId(5), 10.0));
+    }
+
+    #[test]
+    fn jump_freighter_route() {
+        let map = map();
+
+        // jumping from 7 to 6 is out of range, so only gates are used
+        let route = map.jump_route(SystemId(1), SystemId(6), 5.0).unwrap();
+        assert_eq!(route.steps.len(), 3);
+        assert_eq!(route.steps[0].kind, RouteStepKind::Start);
+        assert_eq!(route.gates(), 2);
+        assert_eq!(route.jumps(), 0);
+        assert_eq!(route.fuel(1_000.0), 0);
+
+        // no jumps into high-sec, the last step has to be a gate
+        let route = map.jump_route(SystemId(7), SystemId(4), 10.0).unwrap();
+        assert_eq!(route.steps.last().unwrap().kind, RouteStepKind::Gate);
+
+        // a single jump is cheaper than two gates
+        let route = map.jump_route(SystemId(7), SystemId(5), 7.0).unwrap();
+        assert_eq!(route.gates(), 0);
+        assert_eq!(route.jumps(), 1);
+        assert_eq!(route.distance_ly(), 7.0);
+        assert_eq!(route.fuel(1_000.5), 7_004);
+    }
+
+    #[test]
+    fn from_sde() {
+        let dir = "sde/fsd/universe/eve/TheForge";
+        let files = [
+            (format!("{}/region.yaml", dir), "regionID: 10000002\n".to_string()),
+            (format!("{}/Kimotoro/constellation.yaml", dir), "constellationID: 20000020\n".to_string()),
+            (
+                format!("{}/Kimotoro/Jita/solarsystem.yaml", dir),
+                "solarSystemID: 30000142\nsecurity: 0.9459\ncenter: [0.0, 0.0, 0.0]\nstargates:\n  50001248:\n    destination: 50001249\n".to_string(),
+            ),
+            (
+                format!("{}/Kimotoro/Perimeter/solarsystem.yaml", dir),
+                format!("solarSystemID: 30000144\nsecurity: 0.9\ncenter: [{}, 0.0, 0.0]\nstargates:\n  50001249:\n    destination: 50001248\n", METERS_PER_LY),
+            ),
+            (
+                "sde/bsd/invNames.yaml".to_string(),
+                "- itemID: 10000002\n  itemName: The Forge\n- itemID: 20000020\n  itemName: Kimotoro\n- itemID: 30000142\n  itemName: Jita\n- itemID: 30000144\n  itemName: Perimeter\n".to_string(),
+            ),
+        ];
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (path, content) in files {
+            zip.start_file(path, FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        let sde = EveSde::from_bytes(zip.finish().unwrap().into_inner()).unwrap();
+        let map = EveMap::from_sde(&sde).unwrap();
+
+        let jita = map.system_by_name("jita").unwrap();
+        assert_eq!(jita.system_id, SystemId(30_000_142));
+        assert!(jita.is_highsec());
+        assert_eq!(map.region(jita.region_id).unwrap().name, "The Forge");
+        assert_eq!(map.constellation(jita.constellation_id).unwrap().name, "Kimotoro");
+
+        assert_eq!(map.neighbours(jita.system_id), &[SystemId(30_000_144)]);
+        assert_eq!(map.distance_ly(SystemId(30_000_142), SystemId(30_000_144)), Some(1.0));
+    }
+}
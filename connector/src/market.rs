@@ -0,0 +1,343 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+
+use crate::{LocationId, RegionId, SystemId, TypeId};
+use crate::{ConnectError, Endpoint, EveAuthClient, RequestClient, Route};
+
+/// Orders of a region
+const ORDERS: Endpoint = Endpoint::new("latest/markets/{region_id}/orders")
+    .paginated()
+    .query(&["order_type", "type_id"]);
+/// Daily history of a type in a region
+const HISTORY: Endpoint = Endpoint::new("latest/markets/{region_id}/history")
+    .query(&["type_id"]);
+/// Average and adjusted prices of all types
+const PRICES: Endpoint = Endpoint::new("latest/markets/prices");
+/// Orders of a structure
+const STRUCTURE_ORDERS: Endpoint = Endpoint::new("latest/markets/structures/{structure_id}")
+    .scope("esi-markets.structure_markets.v1")
+    .paginated();
+
+/// Wrapper for the market endpoints
+#[derive(Debug, Default)]
+pub struct EveMarketService;
+
+impl EveMarketService {
+    /// Gets all orders in the given region.
+    ///
+    /// # Params
+    ///
+    /// * `client`     -> Client for communicating with the EVE API
+    /// * `rid`        -> [RegionId] of the region
+    /// * `order_type` -> Only return buy, sell or all orders
+    /// * `tid`        -> Optional filter for a single [TypeId]
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    /// - If the [RegionId] is not a valid id
+    ///
+    /// # Returns
+    ///
+    /// List of all orders of all pages
+    ///
+    pub async fn orders(
+        &self,
+        client:     &impl RequestClient,
+        rid:        RegionId,
+        order_type: OrderType,
+        tid:        Option<TypeId>,
+    ) -> Result<Vec<MarketOrder>, ConnectError> {
+        let route = Self::orders_route(rid, order_type, tid);
+        client
+            .fetch_route_pages::<MarketOrder>(&route)
+            .await
+    }
+
+    /// Streams all orders in the given region.
+    ///
+    /// Same as [EveMarketService::orders], but the orders are returned as
+    /// soon as their page is loaded.
+    ///
+    /// # Params
+    ///
+    /// * `client`     -> Client for communicating with the EVE API
+    /// * `rid`        -> [RegionId] of the region
+    /// * `order_type` -> Only return buy, sell or all orders
+    /// * `tid`        -> Optional filter for a single [TypeId]
+    ///
+    /// # Returns
+    ///
+    /// Stream of orders, an error ends the stream
+    ///
+    pub fn orders_stream<'a, C>(
+        &self,
+        client:     &'a C,
+        rid:        RegionId,
+        order_type: OrderType,
+        tid:        Option<TypeId>,
+    ) -> BoxStream<'a, Result<MarketOrder, ConnectError>>
+    where
+        C: RequestClient,
+    {
+        let route = Self::orders_route(rid, order_type, tid);
+        client.fetch_route_stream::<MarketOrder>(&route)
+    }
+
+    /// Gets the daily market history of a type in the given region.
+    ///
+    /// # Params
+    ///
+    /// * `client` -> Client for communicating with the EVE API
+    /// * `rid`    -> [RegionId] of the region
+    /// * `tid`    -> [TypeId] of the type
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    /// - If the [RegionId] or [TypeId] is not a valid id
+    ///
+    /// # Returns
+    ///
+    /// One entry for every day, for roughly the last year
+    ///
+    pub async fn history(
+        &self,
+        client: &impl RequestClient,
+        rid:    RegionId,
+        tid:    TypeId,
+    ) -> Result<Vec<MarketHistory>, ConnectError> {
+        let route = HISTORY
+            .route()
+            .param("region_id", rid)
+            .query("type_id", tid);
+        client
+            .fetch_route::<Vec<MarketHistory>>(&route)
+            .await
+    }
+
+    /// Gets the average and adjusted prices of all types.
+    ///
+    /// The adjusted price is used by the game for calculating industry
+    /// job costs.
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    ///
+    /// # Returns
+    ///
+    /// List of prices for all types that have one
+    ///
+    pub async fn prices(
+        &self,
+        client: &impl RequestClient,
+    ) -> Result<Vec<MarketPrice>, ConnectError> {
+        client
+            .fetch_route::<Vec<MarketPrice>>(&PRICES.route())
+            .await
+    }
+
+    /// Gets all orders in the given structure.
+    ///
+    /// Requires the scope `esi-markets.structure_markets.v1` and docking
+    /// access to the structure.
+    ///
+    /// # Params
+    ///
+    /// * `client` -> Authenticated client
+    /// * `sid`    -> [LocationId] of the structure
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    /// - If the character has no access to the structure market
+    ///
+    /// # Returns
+    ///
+    /// List of all orders of all pages
+    ///
+    pub async fn structure_orders(
+        &self,
+        client: &EveAuthClient,
+        sid:    LocationId,
+    ) -> Result<Vec<MarketOrder>, ConnectError> {
+        let route = STRUCTURE_ORDERS
+            .route()
+            .param("structure_id", sid);
+        client
+            .fetch_route_pages::<MarketOrder>(&route)
+            .await
+    }
+
+    /// Builds the route for requesting regional orders.
+    ///
+    /// # Params
+    ///
+    /// * `rid`        -> [RegionId] of the region
+    /// * `order_type` -> Only return buy, sell or all orders
+    /// * `tid`        -> Optional filter for a single [TypeId]
+    ///
+    /// # Returns
+    ///
+    /// Route including the query
+    ///
+    fn orders_route(
+        rid:        RegionId,
+        order_type: OrderType,
+        tid:        Option<TypeId>,
+    ) -> Route {
+        let route = ORDERS
+            .route()
+            .param("region_id", rid)
+            .query("order_type", order_type.as_str());
+        if let Some(tid) = tid {
+            route.query("type_id", tid)
+        } else {
+            route
+        }
+    }
+}
+
+/// Type of orders to request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderType {
+    /// Buy and sell orders
+    All,
+    /// Only buy orders
+    Buy,
+    /// Only sell orders
+    Sell,
+}
+
+impl OrderType {
+    /// Gets the value that the EVE API expects.
+    ///
+    /// # Returns
+    ///
+    /// Value for the `order_type` query parameter
+    ///
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::All  => "all",
+            Self::Buy  => "buy",
+            Self::Sell => "sell",
+        }
+    }
+}
+
+/// Represents a single market order
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MarketOrder {
+    /// Unique id of the order
+    pub order_id:      i64,
+    /// [TypeId] of the traded type
+    pub type_id:       TypeId,
+    /// Station or structure the order is located in
+    pub location_id:   LocationId,
+    /// System the order is located in, not set for structure orders
+    #[serde(default)]
+    pub system_id:     Option<SystemId>,
+    /// True if it is a buy order
+    pub is_buy_order:  bool,
+    /// Price per unit
+    pub price:         f64,
+    /// Remaining quantity
+    pub volume_remain: i32,
+    /// Quantity when the order was issued
+    pub volume_total:  i32,
+    /// Minimum quantity of a single transaction
+    pub min_volume:    i32,
+    /// Range of buy orders, for example `station`, `region` or `5`
+    pub range:         String,
+    /// Date the order was issued
+    pub issued:        DateTime<Utc>,
+    /// Number of days the order is valid
+    pub duration:      i32,
+}
+
+/// Represents the market activity of a single day
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MarketHistory {
+    /// Day of the entry
+    pub date:        NaiveDate,
+    /// Average price
+    pub average:     f64,
+    /// Highest price
+    pub highest:     f64,
+    /// Lowest price
+    pub lowest:      f64,
+    /// Number of orders
+    pub order_count: i64,
+    /// Traded quantity
+    pub volume:      i64,
+}
+
+/// Represents the prices of a single type
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MarketPrice {
+    /// [TypeId] of the type
+    pub type_id:        TypeId,
+    /// Average price over all regions
+    #[serde(default)]
+    pub average_price:  Option<f64>,
+    /// Price used for calculating industry job costs
+    #[serde(default)]
+    pub adjusted_price: Option<f64>,
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod market_tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[tokio::test]
+    async fn regional_orders() {
+        let esi = MockEsi::start().await;
+        let client = esi.client().unwrap();
+
+        let orders = EveMarketService
+            .orders(&client, REGION_ID, OrderType::All, Some(TYPE_ID))
+            .await
+            .unwrap();
+        assert_eq!(orders.len(), 2);
+        assert!(orders.iter().all(|x| x.type_id == TYPE_ID));
+        assert_eq!(orders[0].system_id, Some(SYSTEM_ID));
+
+        assert_eq!(
+            esi.requests(),
+            vec![format!("GET /latest/markets/{}/orders?order_type=all&type_id=34", REGION_ID)]
+        );
+    }
+
+    #[tokio::test]
+    async fn history_and_prices() {
+        let esi = MockEsi::start().await;
+        let client = esi.client().unwrap();
+
+        let history = EveMarketService
+            .history(&client, REGION_ID, TYPE_ID)
+            .await
+            .unwrap();
+        assert_eq!(history[0].date, NaiveDate::from_ymd_opt(2023, 1, 6).unwrap());
+
+        let prices = EveMarketService.prices(&client).await.unwrap();
+        assert_eq!(prices[0].adjusted_price, Some(4.12));
+        assert_eq!(prices[1].average_price, None);
+    }
+
+    #[tokio::test]
+    async fn structure_orders() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+
+        let orders = EveMarketService
+            .structure_orders(&client, STRUCTURE_ID)
+            .await
+            .unwrap();
+        assert_eq!(orders[0].location_id, STRUCTURE_ID);
+        assert_eq!(orders[0].system_id, None);
+    }
+}
@@ -0,0 +1,45 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{CharacterId, CorporationId, LocationId, TypeId};
+
+/// Moon extraction of a corporation owned refinery
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MiningExtraction {
+    /// Refinery that runs the extraction
+    pub structure_id:          LocationId,
+    /// Moon the chunk is extracted from
+    pub moon_id:               i32,
+    /// Time the extraction was started
+    pub extraction_start_time: DateTime<Utc>,
+    /// Time the chunk arrives and can be fractured
+    pub chunk_arrival_time:    DateTime<Utc>,
+    /// Time the chunk fractures on its own
+    pub natural_decay_time:    DateTime<Utc>,
+}
+
+/// Structure that records what was mined around it
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MiningObserver {
+    /// Id of the observer, the [LocationId] of the structure
+    pub observer_id:   LocationId,
+    /// Kind of observer, currently always `structure`
+    pub observer_type: String,
+    /// Day the observer last recorded something
+    pub last_updated:  NaiveDate,
+}
+
+/// Ore a single character mined on a single day at an observer
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MiningLedgerEntry {
+    /// Character that mined the ore
+    pub character_id:            CharacterId,
+    /// Corporation the character was in while mining
+    pub recorded_corporation_id: CorporationId,
+    /// [TypeId] of the ore
+    pub type_id:                 TypeId,
+    /// Units mined on that day
+    pub quantity:                i64,
+    /// Day the ore was mined
+    pub last_updated:            NaiveDate,
+}
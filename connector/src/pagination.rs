@@ -0,0 +1,194 @@
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::future::Future;
+
+use crate::{CacheEntry, ConnectError};
+
+/// Default number of pages that are requested at the same time
+pub(crate) const DEFAULT_PAGE_CONCURRENCY: usize = 8;
+
+/// Streams all items of a paginated endpoint.
+///
+/// The first page is requested on its own, its `X-Pages` header determines
+/// how many pages follow. The remaining pages are requested with at most
+/// `concurrency` requests at the same time. The order of the items is the
+/// same as if the pages where requested one after another.
+///
+/// # Params
+///
+/// * `url`         -> Full url of the first page
+/// * `concurrency` -> Maximum number of pages requested at the same time
+/// * `fetch`       -> Requests the given url
+///
+/// # Returns
+///
+/// Stream of all items, after the first error the stream ends
+///
+pub(crate) fn stream<'a, T, F, Fut>(
+    url: String,
+    concurrency: usize,
+    fetch: F,
+) -> BoxStream<'a, Result<T, ConnectError>>
+where
+    T: DeserializeOwned + Send + 'a,
+    F: Fn(String) -> Fut + Clone + Send + 'a,
+    Fut: Future<Output = Result<CacheEntry, ConnectError>> + Send + 'a,
+{
+    stream::once(async move {
+        let entry = fetch(url.clone()).await?;
+        let first = parse::<T>(&entry)?;
+
+        let remaining = stream::iter(2..=entry.pages)
+            .map(move |page| {
+                let fetch = fetch.clone();
+                let separator = if url.contains('?') { '&' } else { '?' };
+                let url = format!("{}{}page={}", url, separator, page);
+                async move { parse::<T>(&fetch(url).await?) }
+            })
+            .buffered(concurrency.max(1));
+
+        Ok::<_, ConnectError>(
+            stream::iter(first.into_iter().map(Ok))
+                .chain(remaining.flat_map(into_items))
+                .boxed(),
+        )
+    })
+    .flat_map(|x| match x {
+        Ok(x) => x,
+        Err(e) => into_items(Err(e)),
+    })
+    .scan(false, |failed, x| {
+        // stop after the first error
+        if *failed {
+            return futures::future::ready(None);
+        }
+        *failed = x.is_err();
+        futures::future::ready(Some(x))
+    })
+    .boxed()
+}
+
+/// Parses the body of a page.
+///
+/// # Params
+///
+/// * `entry` -> Response of a single page
+///
+/// # Errors
+///
+/// If the body is not a list of `T`
+///
+/// # Returns
+///
+/// All items of the page
+///
+fn parse<T>(entry: &CacheEntry) -> Result<Vec<T>, ConnectError>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_slice::<Vec<T>>(&entry.body).map_err(ConnectError::ParseError)
+}
+
+/// Converts a parsed page into a stream of single items.
+///
+/// # Params
+///
+/// * `page` -> Parsed page or the error that occurred
+///
+/// # Returns
+///
+/// Stream of the items, or a single error
+///
+fn into_items<'a, T>(page: Result<Vec<T>, ConnectError>) -> BoxStream<'a, Result<T, ConnectError>>
+where
+    T: Send + 'a,
+{
+    match page {
+        Ok(x) => stream::iter(x.into_iter().map(Ok)).boxed(),
+        Err(e) => stream::iter(vec![Err(e)]).boxed(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod pagination_tests {
+    use crate::testing::*;
+    use crate::{EveCorporationService, RequestClient};
+    use futures::{StreamExt, TryStreamExt};
+    use hyper::Method;
+
+    /// Registers `pages` pages with a single number each
+    fn mock_pages(esi: &MockEsi, pages: u32) {
+        let path = format!("/latest/corporations/{}/numbers", CORPORATION_ID);
+        for page in 1..=pages {
+            let response = MockResponse::json(format!("[{}]", page))
+                .header("x-pages", &pages.to_string());
+            if page == 1 {
+                esi.mock(Method::GET, &path, response);
+            } else {
+                esi.mock(Method::GET, &format!("{}?page={}", path, page), response);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn pages_keep_their_order() {
+        let esi = MockEsi::start().await;
+        mock_pages(&esi, 300);
+        let client = esi.builder().page_concurrency(16).build().unwrap();
+
+        let path = format!("latest/corporations/{}/numbers", CORPORATION_ID);
+        let numbers = client.fetch_page::<u32>(&path).await.unwrap();
+        assert_eq!(numbers, (1..=300).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn stream_ends_after_error() {
+        let esi = MockEsi::start().await;
+        mock_pages(&esi, 3);
+        esi.mock(
+            Method::GET,
+            &format!("/latest/corporations/{}/numbers?page=2", CORPORATION_ID),
+            MockResponse::error(500, "Internal server error"),
+        );
+        let client = esi.client().unwrap();
+
+        let path = format!("latest/corporations/{}/numbers", CORPORATION_ID);
+        let items = client
+            .fetch_page_stream::<u32>(&path)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].as_ref().unwrap(), &1);
+        assert!(items[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn pages_of_paths_with_query() {
+        let esi = MockEsi::start().await;
+        let path = format!("/latest/markets/{}/orders?order_type=all", REGION_ID);
+        esi.mock(Method::GET, &path, MockResponse::json("[1]").header("x-pages", "2"));
+        esi.mock(
+            Method::GET,
+            &format!("{}&page=2", path),
+            MockResponse::json("[2]").header("x-pages", "2"),
+        );
+        let client = esi.client().unwrap();
+
+        let numbers = client.fetch_page::<u32>(&path[1..]).await.unwrap();
+        assert_eq!(numbers, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn corporation_assets_stream() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+
+        let assets = EveCorporationService::new(CORPORATION_ID)
+            .assets_stream(&client)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(assets.len(), 1);
+    }
+}
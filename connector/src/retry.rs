@@ -0,0 +1,455 @@
+use rand::Rng;
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::{ConnectError, EsiError, RequestError};
+
+/// Point in time until all requests to the EVE-API are paused.
+///
+/// The error limit of the EVE-API is counted per IP, so by default a single
+/// pause is shared between all clients of the process, see
+/// [ErrorLimitPause::global]. Cloning the pause is cheap and all clones share
+/// the same state.
+///
+#[derive(Clone, Debug, Default)]
+pub struct ErrorLimitPause(Arc<Mutex<Option<Instant>>>);
+
+impl ErrorLimitPause {
+    /// Creates a new pause that is independent of all other pauses.
+    ///
+    /// # Returns
+    ///
+    /// New pause that is not active
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pause that is shared by all policies of the process.
+    ///
+    /// # Returns
+    ///
+    /// Handle to the global pause
+    ///
+    pub fn global() -> Self {
+        static GLOBAL: OnceLock<ErrorLimitPause> = OnceLock::new();
+        GLOBAL.get_or_init(Self::new).clone()
+    }
+
+    /// Pauses all requests until the given point in time, unless they are
+    /// already paused longer.
+    ///
+    /// # Params
+    ///
+    /// * `until` -> End of the pause
+    ///
+    fn pause_until(&self, until: Instant) {
+        let mut pause = self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if pause.map(|x| x < until).unwrap_or(true) {
+            *pause = Some(until);
+        }
+    }
+
+    /// Waits until the pause is over.
+    async fn wait(&self) {
+        let until = *self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(until) = until {
+            let now = Instant::now();
+            if until > now {
+                tokio::time::sleep(until - now).await;
+            }
+        }
+    }
+}
+
+/// Configures how failed requests are retried.
+///
+/// Only requests that failed with a `5xx`, `420` or a timeout are retried.
+/// All other errors, for example a `403` or `404`, are returned directly.
+///
+/// Between two attempts the policy waits for an exponential backoff with
+/// jitter, or the time given by the `Retry-After` header. Both are limited
+/// by [RetryPolicy::max_delay].
+///
+/// All requests sharing the same [ErrorLimitPause] are paused as soon as the
+/// `X-ESI-Error-Limit-Remain` header drops to the configured threshold.
+/// The pause lasts until the error window is reset.
+///
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled with every retry
+    pub base_delay: Duration,
+    /// Upper bound for the delay between two attempts
+    pub max_delay: Duration,
+    /// Timeout for a single attempt
+    pub timeout: Duration,
+    /// Remaining error budget at which all requests are paused
+    pub error_limit_threshold: u32,
+    /// Pause shared by all requests, [ErrorLimitPause::global] by default
+    pub error_limit_pause: ErrorLimitPause,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            timeout: Duration::from_secs(30),
+            error_limit_threshold: 10,
+            error_limit_pause: ErrorLimitPause::global(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries a request.
+    ///
+    /// # Returns
+    ///
+    /// New policy without retries
+    ///
+    pub fn no_retry() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Checks if a request with the given status should be retried.
+    ///
+    /// # Params
+    ///
+    /// * `status` -> Status of the failed response
+    ///
+    /// # Returns
+    ///
+    /// `true` for all `5xx` and `420`
+    ///
+    pub fn is_retryable(status: StatusCode) -> bool {
+        status.is_server_error() || status.as_u16() == 420
+    }
+
+    /// Calculates the delay before the given retry.
+    ///
+    /// The delay is a random value between zero and the exponential backoff
+    /// for the attempt, limited by [RetryPolicy::max_delay].
+    ///
+    /// # Params
+    ///
+    /// * `attempt` -> Number of the retry, starting at 1
+    ///
+    /// # Returns
+    ///
+    /// Time to wait before the next attempt
+    ///
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        let jitter = rand::thread_rng().gen_range(0..=backoff.as_millis() as u64);
+        Duration::from_millis(jitter)
+    }
+
+    /// Sends the request created by `request` and retries it according to
+    /// the policy.
+    ///
+    /// # Params
+    ///
+    /// * `url`     -> Url of the request, used for errors and logging
+    /// * `request` -> Creates the request, called once for every attempt
+    ///
+    /// # Errors
+    ///
+    /// - [ConnectError::EsiError] if the EVE-API returns a non retryable
+    ///   status or all retries failed with a retryable status
+    /// - [ConnectError::RequestFailed] if the request could not be send
+    ///
+    /// # Returns
+    ///
+    /// Response with a successful status or `304 Not Modified`
+    ///
+    pub(crate) async fn send<F>(&self, url: &str, request: F) -> Result<Response, ConnectError>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut attempt = 0u32;
+
+        loop {
+            self.error_limit_pause.wait().await;
+
+            let (retry_after, error) = match request().timeout(self.timeout).send().await {
+                Ok(response) => {
+                    track_error_limit(
+                        response.headers(),
+                        self.error_limit_threshold,
+                        &self.error_limit_pause,
+                    );
+
+                    let status = response.status();
+                    if status.is_success() || status == StatusCode::NOT_MODIFIED {
+                        return Ok(response);
+                    }
+
+                    let retry_after = retry_after(response.headers());
+                    let error = EsiError::from_response(url, response).await;
+                    if !Self::is_retryable(status) {
+                        return Err(ConnectError::EsiError(error));
+                    }
+                    (retry_after, ConnectError::EsiError(error))
+                }
+                Err(e) => {
+                    let error = RequestError::new(url, e);
+                    if !error.is_retryable() {
+                        return Err(ConnectError::RequestFailed(error));
+                    }
+                    (None, ConnectError::RequestFailed(error))
+                }
+            };
+
+            if attempt >= self.max_retries {
+                tracing::error!({ url = url, retries = attempt }, "Too many retries.");
+                return Err(error);
+            }
+
+            attempt += 1;
+            let delay = retry_after
+                .map_or_else(|| self.backoff(attempt), |x| x.min(self.max_delay));
+            tracing::warn!(
+                { url = url, retry = attempt, delay = delay.as_millis() as u64, error = ?error },
+                "Request failed, retrying.",
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Reads the `Retry-After` header.
+///
+/// # Params
+///
+/// * `headers` -> Headers of the response
+///
+/// # Returns
+///
+/// The duration if the header is set and contains a number of seconds
+///
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get("retry-after")
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Pauses all requests until the error window is reset, if the remaining
+/// error budget dropped to the given threshold.
+///
+/// # Params
+///
+/// * `headers`   -> Headers of the response
+/// * `threshold` -> Remaining error budget at which requests are paused
+/// * `pause`     -> Pause that is extended
+///
+fn track_error_limit(headers: &HeaderMap, threshold: u32, pause: &ErrorLimitPause) {
+    /// Reads the header with the given name as number
+    fn header(headers: &HeaderMap, name: &str) -> Option<u64> {
+        headers
+            .get(name)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.parse::<u64>().ok())
+    }
+
+    let remain = header(headers, "x-esi-error-limit-remain");
+    let reset = header(headers, "x-esi-error-limit-reset");
+
+    if let (Some(remain), Some(reset)) = (remain, reset) {
+        if remain > threshold as u64 {
+            return;
+        }
+
+        tracing::warn!(
+            { remain = remain, reset = reset },
+            "ESI error limit almost reached, pausing all requests.",
+        );
+        pause.pause_until(Instant::now() + Duration::from_secs(reset));
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod retry_tests {
+    use super::*;
+    use crate::testing::*;
+    use hyper::Method;
+
+    /// Policy with short delays, so that the tests do not wait long.
+    ///
+    /// Every policy has its own pause, so that the tests do not pause each
+    /// other.
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+            error_limit_pause: ErrorLimitPause::new(),
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Counts the requests to the given path
+    fn count(esi: &MockEsi, path: &str) -> usize {
+        esi.requests()
+            .iter()
+            .filter(|x| *x == &format!("GET {}", path))
+            .count()
+    }
+
+    #[test]
+    fn only_transient_errors_are_retried() {
+        assert!(RetryPolicy::is_retryable(StatusCode::BAD_GATEWAY));
+        assert!(RetryPolicy::is_retryable(StatusCode::from_u16(420).unwrap_or_default()));
+        assert!(!RetryPolicy::is_retryable(StatusCode::FORBIDDEN));
+        assert!(!RetryPolicy::is_retryable(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn backoff_is_bounded() {
+        let policy = RetryPolicy::default();
+        for attempt in 1..10 {
+            assert!(policy.backoff(attempt) <= policy.max_delay);
+        }
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_until_max_retries() {
+        let esi = MockEsi::start().await;
+        esi.mock(Method::GET, "/retry/502", MockResponse::error(502, "Bad gateway"));
+        esi.mock(Method::GET, "/retry/420", MockResponse::error(420, "Error limited"));
+        let client = reqwest::Client::new();
+
+        for path in ["/retry/502", "/retry/420"] {
+            let url = format!("{}{}", esi.url(), path);
+            let error = policy(2)
+                .send(&url, || client.get(&url))
+                .await
+                .unwrap_err();
+
+            match error {
+                ConnectError::EsiError(e) => assert_eq!(e.url, url),
+                e => panic!("Unexpected error {:?}", e),
+            }
+            assert_eq!(count(&esi, path), 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let esi = MockEsi::start().await;
+        esi.mock(Method::GET, "/retry/403", MockResponse::error(403, "Forbidden"));
+        let client = reqwest::Client::new();
+
+        let url = format!("{}/retry/403", esi.url());
+        let error = policy(2)
+            .send(&url, || client.get(&url))
+            .await
+            .unwrap_err();
+
+        match error {
+            ConnectError::EsiError(e) => assert_eq!(e.status, StatusCode::FORBIDDEN),
+            e => panic!("Unexpected error {:?}", e),
+        }
+        assert_eq!(count(&esi, "/retry/403"), 1);
+    }
+
+    #[tokio::test]
+    async fn retry_succeeds_after_server_error() {
+        let esi = MockEsi::start().await;
+        esi.mock(
+            Method::GET,
+            "/retry/503",
+            MockResponse::error(503, "Unavailable").header("retry-after", "1"),
+        );
+        let client = reqwest::Client::new();
+        let url = format!("{}/retry/503", esi.url());
+
+        // the EVE-API recovers while the client waits for `Retry-After`
+        let recover = async {
+            while count(&esi, "/retry/503") == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            esi.mock(Method::GET, "/retry/503", MockResponse::json("[]"));
+        };
+        let policy = RetryPolicy {
+            max_delay: Duration::from_secs(5),
+            ..policy(2)
+        };
+        let (response, _) = tokio::join!(
+            policy.send(&url, || client.get(&url)),
+            recover,
+        );
+
+        assert_eq!(response.unwrap().status(), StatusCode::OK);
+        assert_eq!(count(&esi, "/retry/503"), 2);
+    }
+
+    #[tokio::test]
+    async fn error_limit_pauses_all_requests() {
+        let esi = MockEsi::start().await;
+        esi.mock(
+            Method::GET,
+            "/retry/limit",
+            MockResponse::json("[]")
+                .header("x-esi-error-limit-remain", "5")
+                .header("x-esi-error-limit-reset", "1"),
+        );
+        esi.mock(Method::GET, "/retry/other", MockResponse::json("[]"));
+        let client = reqwest::Client::new();
+
+        let limited = policy(0);
+        let url = format!("{}/retry/limit", esi.url());
+        limited.send(&url, || client.get(&url)).await.unwrap();
+
+        // a different policy sharing the pause still has to wait for the reset
+        let start = Instant::now();
+        let shared = RetryPolicy {
+            error_limit_pause: limited.error_limit_pause.clone(),
+            ..RetryPolicy::no_retry()
+        };
+        let url = format!("{}/retry/other", esi.url());
+        shared.send(&url, || client.get(&url)).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(900));
+
+        // a policy with its own pause is not affected
+        let start = Instant::now();
+        policy(0).send(&url, || client.get(&url)).await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(900));
+    }
+
+    #[tokio::test]
+    async fn retry_after_is_limited_by_max_delay() {
+        let esi = MockEsi::start().await;
+        esi.mock(
+            Method::GET,
+            "/retry/after",
+            MockResponse::error(503, "Unavailable").header("retry-after", "60"),
+        );
+        let client = reqwest::Client::new();
+        let url = format!("{}/retry/after", esi.url());
+
+        let start = Instant::now();
+        policy(1).send(&url, || client.get(&url)).await.unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(count(&esi, "/retry/after"), 2);
+    }
+}
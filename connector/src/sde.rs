@@ -0,0 +1,727 @@
+//! Access to the static data export (SDE) of EVE.
+//!
+//! The SDE is distributed as a single zip file containing yaml files. The
+//! [EveSde] keeps the zip in memory and only parses a file when it is
+//! accessed the first time, every further access is served from the parsed
+//! copy.
+//!
+//! # Usage
+//!
+//! ``` rust,ignore
+//! use caph_connector::TypeId;
+//! use caph_connector::sde::EveSde;
+//!
+//! let sde = EveSde::from_path("./sde.zip").unwrap();
+//! let tritanium = sde.type_entry(TypeId(34)).unwrap().unwrap();
+//! assert_eq!(tritanium.name(), Some("Tritanium"));
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::OnceLock;
+use zip::ZipArchive;
+
+use crate::{CategoryId, ConnectError, GroupId, IndustryActivity, TypeId};
+
+/// Parsed SDE, every file is parsed on first access.
+#[derive(Debug)]
+pub struct EveSde {
+    /// Raw bytes of the zip file
+    zip:        Vec<u8>,
+
+    /// Parsed `typeIDs.yaml`
+    types:      OnceLock<HashMap<TypeId, TypeEntry>>,
+    /// Parsed `groupIDs.yaml`
+    groups:     OnceLock<HashMap<GroupId, GroupEntry>>,
+    /// Parsed `categoryIDs.yaml`
+    categories: OnceLock<HashMap<CategoryId, CategoryEntry>>,
+    /// Parsed `blueprints.yaml`
+    blueprints: OnceLock<HashMap<TypeId, Blueprint>>,
+    /// Parsed `typeDogma.yaml`
+    type_dogma: OnceLock<HashMap<TypeId, TypeDogma>>,
+}
+
+impl EveSde {
+    /// Url of the latest official SDE
+    pub const URL: &'static str = "https://eve-static-data-export.s3-eu-west-1.amazonaws.com/tranquility/sde.zip";
+
+    /// Path of the types within the zip
+    const PATH_TYPES:      &'static str = "sde/fsd/typeIDs.yaml";
+    /// Path of the groups within the zip
+    const PATH_GROUPS:     &'static str = "sde/fsd/groupIDs.yaml";
+    /// Path of the categories within the zip
+    const PATH_CATEGORIES: &'static str = "sde/fsd/categoryIDs.yaml";
+    /// Path of the blueprints within the zip
+    const PATH_BLUEPRINTS: &'static str = "sde/fsd/blueprints.yaml";
+    /// Path of the dogma of all types within the zip
+    const PATH_TYPE_DOGMA: &'static str = "sde/fsd/typeDogma.yaml";
+
+    /// Creates a new instance from the bytes of a SDE zip.
+    ///
+    /// # Params
+    ///
+    /// * `zip` -> Raw bytes of the zip file
+    ///
+    /// # Errors
+    ///
+    /// If the bytes are not a valid zip file
+    ///
+    /// # Returns
+    ///
+    /// New instance, no file is parsed yet
+    ///
+    pub fn from_bytes(zip: Vec<u8>) -> Result<Self, ConnectError> {
+        ZipArchive::new(Cursor::new(zip.as_slice()))
+            .map_err(ConnectError::SdeZipLoadError)?;
+
+        Ok(Self {
+            zip,
+            types:      OnceLock::new(),
+            groups:     OnceLock::new(),
+            categories: OnceLock::new(),
+            blueprints: OnceLock::new(),
+            type_dogma: OnceLock::new(),
+        })
+    }
+
+    /// Creates a new instance from a SDE zip on disk.
+    ///
+    /// # Params
+    ///
+    /// * `path` -> Path to the zip file
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read or is not a valid zip file
+    ///
+    /// # Returns
+    ///
+    /// New instance, no file is parsed yet
+    ///
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, ConnectError> {
+        let zip = std::fs::read(path)
+            .map_err(ConnectError::SdeReadError)?;
+        Self::from_bytes(zip)
+    }
+
+    /// Creates a new instance from a SDE zip downloaded from the given url.
+    ///
+    /// # Params
+    ///
+    /// * `url` -> Url of the zip file, for the official SDE see [EveSde::URL]
+    ///
+    /// # Errors
+    ///
+    /// If the download failed or the file is not a valid zip file
+    ///
+    /// # Returns
+    ///
+    /// New instance, no file is parsed yet
+    ///
+    pub async fn from_url(url: &str) -> Result<Self, ConnectError> {
+        let zip = reqwest::get(url)
+            .await
+            .and_then(|x| x.error_for_status())
+            .map_err(ConnectError::SdeDownloadFailed)?
+            .bytes()
+            .await
+            .map_err(ConnectError::SdeDownloadFailed)?;
+        Self::from_bytes(zip.to_vec())
+    }
+
+    /// Gets all types.
+    ///
+    /// # Errors
+    ///
+    /// If the file is missing or could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// Map of all types
+    ///
+    pub fn types(&self) -> Result<&HashMap<TypeId, TypeEntry>, ConnectError> {
+        self.lazy(&self.types, Self::PATH_TYPES)
+    }
+
+    /// Gets a single type.
+    ///
+    /// # Params
+    ///
+    /// * `type_id` -> [TypeId] of the type
+    ///
+    /// # Errors
+    ///
+    /// If the file is missing or could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the type does not exist
+    /// - [TypeEntry] of the type
+    ///
+    pub fn type_entry(&self, type_id: TypeId) -> Result<Option<&TypeEntry>, ConnectError> {
+        self.types().map(|x| x.get(&type_id))
+    }
+
+    /// Gets all groups.
+    ///
+    /// # Errors
+    ///
+    /// If the file is missing or could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// Map of all groups
+    ///
+    pub fn groups(&self) -> Result<&HashMap<GroupId, GroupEntry>, ConnectError> {
+        self.lazy(&self.groups, Self::PATH_GROUPS)
+    }
+
+    /// Gets a single group.
+    ///
+    /// # Params
+    ///
+    /// * `group_id` -> [GroupId] of the group
+    ///
+    /// # Errors
+    ///
+    /// If the file is missing or could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the group does not exist
+    /// - [GroupEntry] of the group
+    ///
+    pub fn group(&self, group_id: GroupId) -> Result<Option<&GroupEntry>, ConnectError> {
+        self.groups().map(|x| x.get(&group_id))
+    }
+
+    /// Gets all categories.
+    ///
+    /// # Errors
+    ///
+    /// If the file is missing or could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// Map of all categories
+    ///
+    pub fn categories(&self) -> Result<&HashMap<CategoryId, CategoryEntry>, ConnectError> {
+        self.lazy(&self.categories, Self::PATH_CATEGORIES)
+    }
+
+    /// Gets a single category.
+    ///
+    /// # Params
+    ///
+    /// * `category_id` -> [CategoryId] of the category
+    ///
+    /// # Errors
+    ///
+    /// If the file is missing or could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the category does not exist
+    /// - [CategoryEntry] of the category
+    ///
+    pub fn category(
+        &self,
+        category_id: CategoryId,
+    ) -> Result<Option<&CategoryEntry>, ConnectError> {
+        self.categories().map(|x| x.get(&category_id))
+    }
+
+    /// Gets all blueprints.
+    ///
+    /// # Errors
+    ///
+    /// If the file is missing or could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// Map of all blueprints, keyed by the [TypeId] of the blueprint
+    ///
+    pub fn blueprints(&self) -> Result<&HashMap<TypeId, Blueprint>, ConnectError> {
+        self.lazy(&self.blueprints, Self::PATH_BLUEPRINTS)
+    }
+
+    /// Gets a single blueprint.
+    ///
+    /// # Params
+    ///
+    /// * `type_id` -> [TypeId] of the blueprint
+    ///
+    /// # Errors
+    ///
+    /// If the file is missing or could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the blueprint does not exist
+    /// - [Blueprint] with all its activities
+    ///
+    pub fn blueprint(&self, type_id: TypeId) -> Result<Option<&Blueprint>, ConnectError> {
+        self.blueprints().map(|x| x.get(&type_id))
+    }
+
+    /// Gets the dogma of all types.
+    ///
+    /// # Errors
+    ///
+    /// If the file is missing or could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// Map of the dogma of all types
+    ///
+    pub fn type_dogmas(&self) -> Result<&HashMap<TypeId, TypeDogma>, ConnectError> {
+        self.lazy(&self.type_dogma, Self::PATH_TYPE_DOGMA)
+    }
+
+    /// Gets the dogma of a single type.
+    ///
+    /// # Params
+    ///
+    /// * `type_id` -> [TypeId] of the type
+    ///
+    /// # Errors
+    ///
+    /// If the file is missing or could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the type has no dogma
+    /// - [TypeDogma] of the type
+    ///
+    pub fn type_dogma(&self, type_id: TypeId) -> Result<Option<&TypeDogma>, ConnectError> {
+        self.type_dogmas().map(|x| x.get(&type_id))
+    }
+
+    /// Gets the parsed file from the cell, parses it if necessary.
+    ///
+    /// Concurrent first accesses may parse the file more than once, only one
+    /// result is kept.
+    ///
+    /// # Params
+    ///
+    /// * `cell` -> Cell that holds the parsed file
+    /// * `path` -> Path of the file within the zip
+    ///
+    /// # Errors
+    ///
+    /// If the file is missing or could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// Parsed file
+    ///
+    fn lazy<'a, T>(
+        &self,
+        cell: &'a OnceLock<T>,
+        path: &str,
+    ) -> Result<&'a T, ConnectError>
+    where
+        T: DeserializeOwned,
+    {
+        if let Some(x) = cell.get() {
+            return Ok(x);
+        }
+
+        let parsed = self.parse::<T>(path)?;
+        Ok(cell.get_or_init(|| parsed))
+    }
+
+    /// Parses a single yaml file of the zip.
+    ///
+    /// # Params
+    ///
+    /// * `path` -> Path of the file within the zip
+    ///
+    /// # Errors
+    ///
+    /// If the file is missing or could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// Parsed file
+    ///
+    pub(crate) fn parse<T>(&self, path: &str) -> Result<T, ConnectError>
+    where
+        T: DeserializeOwned,
+    {
+        let mut zip = ZipArchive::new(Cursor::new(self.zip.as_slice()))
+            .map_err(ConnectError::SdeZipLoadError)?;
+        let file = zip
+            .by_name(path)
+            .map_err(|_| ConnectError::SdeFileNotFound(path.into()))?;
+        serde_yaml::from_reader(file)
+            .map_err(ConnectError::SdeParseError)
+    }
+
+    /// Parses all yaml files with the given name below a directory of the
+    /// zip.
+    ///
+    /// # Params
+    ///
+    /// * `dir`       -> Directory within the zip, for example `sde/fsd/universe/eve/`
+    /// * `file_name` -> Name of the files, for example `solarsystem.yaml`
+    ///
+    /// # Errors
+    ///
+    /// If the zip could not be read or a file could not be parsed
+    ///
+    /// # Returns
+    ///
+    /// Path and content of every matching file
+    ///
+    pub(crate) fn parse_all<T>(
+        &self,
+        dir:       &str,
+        file_name: &str,
+    ) -> Result<Vec<(String, T)>, ConnectError>
+    where
+        T: DeserializeOwned,
+    {
+        let mut zip = ZipArchive::new(Cursor::new(self.zip.as_slice()))
+            .map_err(ConnectError::SdeZipLoadError)?;
+
+        let mut files = Vec::new();
+        for i in 0..zip.len() {
+            let file = zip
+                .by_index(i)
+                .map_err(ConnectError::SdeZipLoadError)?;
+            let path = file.name().to_string();
+            if !path.starts_with(dir) || !path.ends_with(file_name) {
+                continue;
+            }
+
+            let parsed = serde_yaml::from_reader(file)
+                .map_err(ConnectError::SdeParseError)?;
+            files.push((path, parsed));
+        }
+        Ok(files)
+    }
+}
+
+/// Single entry of `typeIDs.yaml`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeEntry {
+    /// [GroupId] of the type
+    #[serde(rename = "groupID")]
+    pub group_id:        GroupId,
+    /// Name of the type, keyed by language
+    #[serde(default)]
+    pub name:            HashMap<String, String>,
+    /// True if the type is available in the game
+    #[serde(default)]
+    pub published:       bool,
+
+    /// Market group of the type, not every type can be sold
+    #[serde(rename = "marketGroupID")]
+    pub market_group_id: Option<i32>,
+    /// Meta group of the type, e.g. Tech 2
+    #[serde(rename = "metaGroupID")]
+    pub meta_group_id:   Option<i32>,
+    /// Number of items that are produced in one run
+    pub portion_size:    Option<i32>,
+    /// Volume of a single item
+    pub volume:          Option<f32>,
+}
+
+impl TypeEntry {
+    /// Gets the english name of the type.
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the type has no english name
+    /// - Name of the type
+    ///
+    pub fn name(&self) -> Option<&str> {
+        self.name.get("en").map(String::as_str)
+    }
+}
+
+/// Single entry of `groupIDs.yaml`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GroupEntry {
+    /// [CategoryId] of the group
+    #[serde(rename = "categoryID")]
+    pub category_id: CategoryId,
+    /// Name of the group, keyed by language
+    #[serde(default)]
+    pub name:        HashMap<String, String>,
+    /// True if the group is available in the game
+    #[serde(default)]
+    pub published:   bool,
+}
+
+impl GroupEntry {
+    /// Gets the english name of the group.
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the group has no english name
+    /// - Name of the group
+    ///
+    pub fn name(&self) -> Option<&str> {
+        self.name.get("en").map(String::as_str)
+    }
+}
+
+/// Single entry of `categoryIDs.yaml`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CategoryEntry {
+    /// Name of the category, keyed by language
+    #[serde(default)]
+    pub name:      HashMap<String, String>,
+    /// True if the category is available in the game
+    #[serde(default)]
+    pub published: bool,
+}
+
+impl CategoryEntry {
+    /// Gets the english name of the category.
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the category has no english name
+    /// - Name of the category
+    ///
+    pub fn name(&self) -> Option<&str> {
+        self.name.get("en").map(String::as_str)
+    }
+}
+
+/// Single entry of `blueprints.yaml`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Blueprint {
+    /// [TypeId] of the blueprint
+    #[serde(rename = "blueprintTypeID")]
+    pub blueprint_type_id:    TypeId,
+    /// Maximum number of runs of a copy
+    pub max_production_limit: i32,
+    /// All activities that can be done with the blueprint
+    pub activities:           BlueprintActivities,
+}
+
+impl Blueprint {
+    /// Gets a single activity of the blueprint.
+    ///
+    /// # Params
+    ///
+    /// * `activity` -> Activity to get
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the blueprint does not support the activity
+    /// - [BlueprintActivity] with materials, products and skills
+    ///
+    pub fn activity(&self, activity: IndustryActivity) -> Option<&BlueprintActivity> {
+        match activity {
+            IndustryActivity::Manufacturing              => self.activities.manufacturing.as_ref(),
+            IndustryActivity::MaterialEfficiencyResearch => self.activities.research_material.as_ref(),
+            IndustryActivity::TimeEfficiencyResearch     => self.activities.research_time.as_ref(),
+            IndustryActivity::Copying                    => self.activities.copying.as_ref(),
+            IndustryActivity::Invention                  => self.activities.invention.as_ref(),
+            IndustryActivity::Reactions                  => self.activities.reaction.as_ref(),
+            IndustryActivity::Unknown                    => None,
+        }
+    }
+}
+
+/// All activities of a blueprint
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct BlueprintActivities {
+    /// Making blueprint copies
+    pub copying:           Option<BlueprintActivity>,
+    /// Inventing a more advanced blueprint
+    pub invention:         Option<BlueprintActivity>,
+    /// Manufacturing of things
+    pub manufacturing:     Option<BlueprintActivity>,
+    /// Combining materials in a reaction
+    pub reaction:          Option<BlueprintActivity>,
+    /// Researching material efficiency
+    pub research_material: Option<BlueprintActivity>,
+    /// Researching time efficiency
+    pub research_time:     Option<BlueprintActivity>,
+}
+
+/// Single activity of a blueprint
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BlueprintActivity {
+    /// Time in seconds a single run takes
+    pub time:      i32,
+    /// Required materials
+    #[serde(default)]
+    pub materials: Vec<Material>,
+    /// Resulting products
+    #[serde(default)]
+    pub products:  Vec<Material>,
+    /// Required skills
+    #[serde(default)]
+    pub skills:    Vec<Skill>,
+}
+
+/// Material or product of an activity
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Material {
+    /// [TypeId] of the material
+    #[serde(rename = "typeID")]
+    pub type_id:     TypeId,
+    /// Quantity required or produced per run
+    pub quantity:    i32,
+    /// Chance of success, only set for invention products
+    pub probability: Option<f32>,
+}
+
+/// Skill required by an activity
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Skill {
+    /// [TypeId] of the skill
+    #[serde(rename = "typeID")]
+    pub type_id: TypeId,
+    /// Required level
+    pub level:   i32,
+}
+
+/// Single entry of `typeDogma.yaml`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeDogma {
+    /// All dogma attributes of the type
+    #[serde(default)]
+    pub dogma_attributes: Vec<DogmaAttribute>,
+    /// All dogma effects of the type
+    #[serde(default)]
+    pub dogma_effects:    Vec<DogmaEffect>,
+}
+
+impl TypeDogma {
+    /// Gets the value of a single attribute.
+    ///
+    /// # Params
+    ///
+    /// * `attribute_id` -> Id of the attribute
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the type does not have the attribute
+    /// - Value of the attribute
+    ///
+    pub fn attribute(&self, attribute_id: i32) -> Option<f64> {
+        self.dogma_attributes
+            .iter()
+            .find(|x| x.attribute_id == attribute_id)
+            .map(|x| x.value)
+    }
+}
+
+/// Dogma attribute of a type
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DogmaAttribute {
+    /// Id of the attribute
+    #[serde(rename = "attributeID")]
+    pub attribute_id: i32,
+    /// Value of the attribute
+    pub value:        f64,
+}
+
+/// Dogma effect of a type
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DogmaEffect {
+    /// Id of the effect
+    #[serde(rename = "effectID")]
+    pub effect_id:  i32,
+    /// True if the effect is active by default
+    #[serde(rename = "isDefault")]
+    pub is_default: bool,
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod sde_tests {
+    use super::*;
+    use crate::testing::*;
+    use hyper::Method;
+    use std::io::Write;
+    use zip::ZipWriter;
+    use zip::write::FileOptions;
+
+    /// Builds a small SDE zip containing a single entry per file
+    fn sde_zip() -> Vec<u8> {
+        let files = [
+            (EveSde::PATH_TYPES, "34:\n  groupID: 18\n  name:\n    de: Tritanium\n    en: Tritanium\n  portionSize: 1\n  published: true\n  volume: 0.01\n"),
+            (EveSde::PATH_GROUPS, "18:\n  categoryID: 4\n  name:\n    en: Mineral\n  published: true\n"),
+            (EveSde::PATH_CATEGORIES, "4:\n  name:\n    en: Material\n  published: true\n"),
+            (EveSde::PATH_BLUEPRINTS, "681:\n  activities:\n    copying:\n      time: 480\n    manufacturing:\n      materials:\n      - quantity: 86\n        typeID: 34\n      products:\n      - quantity: 1\n        typeID: 165\n      skills:\n      - level: 1\n        typeID: 3380\n      time: 600\n  blueprintTypeID: 681\n  maxProductionLimit: 300\n"),
+            (EveSde::PATH_TYPE_DOGMA, "34:\n  dogmaAttributes:\n  - attributeID: 161\n    value: 0.01\n  dogmaEffects: []\n"),
+        ];
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (path, content) in files {
+            zip.start_file(path, FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn typed_lookups() {
+        let sde = EveSde::from_bytes(sde_zip()).unwrap();
+
+        let entry = sde.type_entry(TYPE_ID).unwrap().unwrap();
+        assert_eq!(entry.name(), Some("Tritanium"));
+        assert_eq!(entry.group_id, GROUP_ID);
+        assert!(sde.type_entry(TypeId(0)).unwrap().is_none());
+
+        let group = sde.group(GROUP_ID).unwrap().unwrap();
+        assert_eq!(group.name(), Some("Mineral"));
+        let category = sde.category(group.category_id).unwrap().unwrap();
+        assert_eq!(category.name(), Some("Material"));
+
+        let blueprint = sde.blueprint(TypeId(681)).unwrap().unwrap();
+        let manufacturing = blueprint.activity(IndustryActivity::Manufacturing).unwrap();
+        assert_eq!(manufacturing.materials[0].type_id, TYPE_ID);
+        assert_eq!(manufacturing.products[0].type_id, TypeId(165));
+        assert!(blueprint.activity(IndustryActivity::Invention).is_none());
+
+        let dogma = sde.type_dogma(TYPE_ID).unwrap().unwrap();
+        assert_eq!(dogma.attribute(161), Some(0.01));
+    }
+
+    #[test]
+    fn missing_file_and_invalid_zip() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("sde/fsd/other.yaml", FileOptions::default()).unwrap();
+        let sde = EveSde::from_bytes(zip.finish().unwrap().into_inner()).unwrap();
+        assert!(matches!(sde.types(), Err(ConnectError::SdeFileNotFound(_))));
+
+        assert!(matches!(
+            EveSde::from_bytes(b"no zip".to_vec()),
+            Err(ConnectError::SdeZipLoadError(_))
+        ));
+        assert!(matches!(
+            EveSde::from_path("./does/not/exist.zip"),
+            Err(ConnectError::SdeReadError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn from_url() {
+        let esi = MockEsi::start().await;
+        esi.mock(Method::GET, "/sde.zip", MockResponse::json(sde_zip()));
+
+        let sde = EveSde::from_url(&format!("{}/sde.zip", esi.url())).await.unwrap();
+        assert!(sde.type_entry(TYPE_ID).unwrap().is_some());
+
+        assert!(matches!(
+            EveSde::from_url(&format!("{}/missing.zip", esi.url())).await,
+            Err(ConnectError::SdeDownloadFailed(_))
+        ));
+    }
+}
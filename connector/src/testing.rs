@@ -0,0 +1,505 @@
+//! Local stand-in for the EVE-API and the EVE-SSO.
+//!
+//! The [MockEsi] serves canned fixtures for the endpoints used by the
+//! services, so they can be tested without talking to the real API.
+//! All fixtures belong to [CHARACTER_ID], [CORPORATION_ID] and
+//! [STRUCTURE_ID].
+//!
+//! # Usage
+//!
+//! The module is only available with the feature `testing`.
+//!
+//! ``` rust,ignore
+//! use caph_connector::*;
+//! use caph_connector::testing::*;
+//!
+//! let esi = MockEsi::start().await;
+//! let client = esi.auth_client().unwrap();
+//!
+//! let jobs = EveCharacterService::new(CHARACTER_ID)
+//!     .industry_jobs(&client)
+//!     .await
+//!     .unwrap();
+//! assert_eq!(jobs.len(), 1);
+//! ```
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Server};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+use crate::{AllianceId, CharacterId, ConnectError, ContractId, CorporationId, GroupId, LocationId};
+use crate::{RegionId, StationId, SystemId, TypeId};
+use crate::{EveAuthClient, EveClient, EveClientBuilder, RetryPolicy};
+
+/// [CharacterId] of the character all fixtures belong to
+pub const CHARACTER_ID: CharacterId = CharacterId(2_117_000_001);
+/// [CorporationId] of the fixture character
+pub const CORPORATION_ID: CorporationId = CorporationId(98_000_001);
+/// [AllianceId] of the fixture corporation
+pub const ALLIANCE_ID: AllianceId = AllianceId(99_000_001);
+/// [LocationId] of the structure the fixture assets are located in
+pub const STRUCTURE_ID: LocationId = LocationId(1_030_000_000_001);
+/// [StationId] of the NPC station the fixture blueprints are located in
+pub const STATION_ID: StationId = StationId(60_003_760);
+/// [RegionId] of the fixture market
+pub const REGION_ID: RegionId = RegionId(10_000_002);
+/// [SystemId] of the fixture structure and station
+pub const SYSTEM_ID: SystemId = SystemId(30_000_142);
+/// [TypeId] of the fixture type
+pub const TYPE_ID: TypeId = TypeId(34);
+/// [GroupId] of the fixture type
+pub const GROUP_ID: GroupId = GroupId(18);
+/// [ContractId] of the finished item exchange of the fixture character
+pub const CONTRACT_ID: ContractId = ContractId(190_000_001);
+/// [ContractId] of the auction of the fixture character
+pub const AUCTION_ID: ContractId = ContractId(190_000_002);
+/// [ContractId] of the item exchange of the fixture corporation
+pub const CORPORATION_CONTRACT_ID: ContractId = ContractId(190_000_003);
+/// Refresh token that is accepted by the token endpoint
+pub const REFRESH_TOKEN: &str = "caph_refresh_token";
+/// Scopes that are contained in the issued access tokens
+pub const SCOPES: &[&str] = &[
+    "esi-assets.read_assets.v1",
+    "esi-assets.read_corporation_assets.v1",
+    "esi-characters.read_blueprints.v1",
+    "esi-contracts.read_character_contracts.v1",
+    "esi-contracts.read_corporation_contracts.v1",
+    "esi-corporations.read_blueprints.v1",
+    "esi-corporations.read_structures.v1",
+    "esi-industry.read_character_jobs.v1",
+    "esi-industry.read_corporation_mining.v1",
+    "esi-industry.read_corporation_jobs.v1",
+    "esi-markets.structure_markets.v1",
+    "esi-skills.read_skillqueue.v1",
+    "esi-skills.read_skills.v1",
+    "esi-universe.read_structures.v1",
+    "esi-wallet.read_character_wallet.v1",
+    "esi-wallet.read_corporation_wallets.v1",
+];
+/// Public keys served by the mocked EVE-SSO, matching the key the access
+/// tokens are signed with
+pub const JWKS: &str = include_str!("../fixtures/jwks.json");
+/// Private key the access tokens are signed with
+const JWT_PRIVATE_KEY: &str = include_str!("../fixtures/jwt_private_key.pem");
+
+/// Canned response of the [MockEsi].
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    /// Status code of the response
+    status: u16,
+    /// Additional headers of the response
+    headers: Vec<(String, String)>,
+    /// Raw body of the response
+    body: Vec<u8>,
+}
+
+impl MockResponse {
+    /// Creates a successful response with the given json body.
+    ///
+    /// # Params
+    ///
+    /// * `body` -> Json that is returned
+    ///
+    /// # Returns
+    ///
+    /// New response with status `200`
+    ///
+    pub fn json(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("content-type".into(), "application/json".into())],
+            body: body.into(),
+        }
+    }
+
+    /// Creates an error response in the format of the EVE-API.
+    ///
+    /// # Params
+    ///
+    /// * `status` -> Status code of the response
+    /// * `error`  -> Error message that is returned
+    ///
+    /// # Returns
+    ///
+    /// New response with the given status
+    ///
+    pub fn error(status: u16, error: &str) -> Self {
+        let body = serde_json::json!({ "error": error }).to_string();
+        Self::json(body).status(status)
+    }
+
+    /// Sets the status code of the response.
+    ///
+    /// # Params
+    ///
+    /// * `status` -> Status code
+    ///
+    /// # Returns
+    ///
+    /// The modified response
+    ///
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Adds a header to the response.
+    ///
+    /// # Params
+    ///
+    /// * `name`  -> Name of the header
+    /// * `value` -> Value of the header
+    ///
+    /// # Returns
+    ///
+    /// The modified response
+    ///
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// Registered routes, identified by method and path including the query
+type Routes = Arc<Mutex<HashMap<(Method, String), MockResponse>>>;
+
+/// In-process HTTP server that imitates the EVE-API and the EVE-SSO.
+///
+/// The server listens on a random local port and is shut down when the
+/// struct is dropped. Every request is matched by method and path, first
+/// including and then without the query. Unknown routes are answered with a
+/// `404` in the error format of the EVE-API.
+///
+#[derive(Debug)]
+pub struct MockEsi {
+    /// Address the server listens on
+    addr: SocketAddr,
+    /// Registered routes
+    routes: Routes,
+    /// All received requests in the format `METHOD /path?query`
+    requests: Arc<Mutex<Vec<String>>>,
+    /// Stops the server when dropped
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl MockEsi {
+    /// Starts a new server serving all fixtures.
+    ///
+    /// Must be called inside a tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no local port could be bound.
+    ///
+    /// # Returns
+    ///
+    /// Running server
+    ///
+    pub async fn start() -> Self {
+        let routes: Routes = Arc::new(Mutex::new(HashMap::new()));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+
+        let service_routes = routes.clone();
+        let service_requests = requests.clone();
+        let make_service = make_service_fn(move |_| {
+            let routes = service_routes.clone();
+            let requests = service_requests.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    handle(routes.clone(), requests.clone(), req)
+                }))
+            }
+        });
+
+        #[allow(clippy::expect_used)]
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .expect("Binding a local port should be possible")
+            .serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            shutdown_rx.await.ok();
+        }));
+
+        let esi = Self {
+            addr,
+            routes,
+            requests,
+            _shutdown: shutdown,
+        };
+        esi.register_fixtures();
+        esi
+    }
+
+    /// Gets the base url of the server.
+    ///
+    /// # Returns
+    ///
+    /// Url in the format `http://127.0.0.1:<port>`
+    ///
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Creates a builder that is configured to use this server for the
+    /// EVE-API and the EVE-SSO.
+    ///
+    /// Retries are disabled, so that errors show up directly.
+    ///
+    /// # Returns
+    ///
+    /// Preconfigured builder
+    ///
+    pub fn builder(&self) -> EveClientBuilder {
+        EveClientBuilder::new()
+            .esi_url(self.url())
+            .sso_url(self.url())
+            .user_agent("caph_connector tests")
+            .credentials("caph_client_id", "caph_secret_key")
+            .callback(format!("{}/callback", self.url()))
+            .retry_policy(RetryPolicy::no_retry())
+    }
+
+    /// Creates a new [EveClient] that uses this server.
+    ///
+    /// # Errors
+    ///
+    /// If the client could not be constructed
+    ///
+    /// # Returns
+    ///
+    /// New client
+    ///
+    pub fn client(&self) -> Result<EveClient, ConnectError> {
+        self.builder().build()
+    }
+
+    /// Creates a new [EveAuthClient] for [CHARACTER_ID] that uses this
+    /// server.
+    ///
+    /// # Errors
+    ///
+    /// If the client could not be constructed
+    ///
+    /// # Returns
+    ///
+    /// New client
+    ///
+    pub fn auth_client(&self) -> Result<EveAuthClient, ConnectError> {
+        self.builder().build_auth(REFRESH_TOKEN.into())
+    }
+
+    /// Registers a response, replacing an existing one for the same route.
+    ///
+    /// # Params
+    ///
+    /// * `method`   -> Method of the request
+    /// * `path`     -> Path of the request, may contain a query
+    /// * `response` -> Response to return
+    ///
+    /// # Panics
+    ///
+    /// Panics if the [Mutex] is not exclusive.
+    ///
+    pub fn mock(&self, method: Method, path: &str, response: MockResponse) {
+        #[allow(clippy::unwrap_used)]
+        self.routes
+            .lock()
+            .unwrap()
+            .insert((method, path.into()), response);
+    }
+
+    /// Gets all requests the server received so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the [Mutex] is not exclusive.
+    ///
+    /// # Returns
+    ///
+    /// List of requests in the format `METHOD /path?query`
+    ///
+    pub fn requests(&self) -> Vec<String> {
+        #[allow(clippy::unwrap_used)]
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Registers all fixtures and the token endpoint.
+    fn register_fixtures(&self) {
+        let cid = CHARACTER_ID;
+        let corp = CORPORATION_ID;
+
+        let fixtures = [
+            (Method::GET, format!("/latest/characters/{}/", cid), include_str!("../fixtures/character.json")),
+            (Method::GET, format!("/latest/characters/{}/assets", cid), include_str!("../fixtures/character_assets.json")),
+            (Method::POST, format!("/latest/characters/{}/assets/names", cid), include_str!("../fixtures/character_asset_names.json")),
+            (Method::GET, format!("/latest/characters/{}/blueprints", cid), include_str!("../fixtures/character_blueprints.json")),
+            (Method::GET, format!("/latest/characters/{}/industry/jobs", cid), include_str!("../fixtures/character_industry_jobs.json")),
+            (Method::GET, format!("/latest/characters/{}/skills", cid), include_str!("../fixtures/character_skills.json")),
+            (Method::GET, format!("/latest/characters/{}/skillqueue", cid), include_str!("../fixtures/character_skillqueue.json")),
+            (Method::GET, format!("/latest/characters/{}/attributes", cid), include_str!("../fixtures/character_attributes.json")),
+            (Method::GET, format!("/latest/characters/{}/contracts", cid), include_str!("../fixtures/character_contracts.json")),
+            (Method::GET, format!("/latest/characters/{}/contracts/{}/items", cid, CONTRACT_ID), include_str!("../fixtures/contract_items.json")),
+            (Method::GET, format!("/latest/characters/{}/contracts/{}/bids", cid, AUCTION_ID), include_str!("../fixtures/contract_bids.json")),
+            (Method::GET, format!("/latest/characters/{}/wallet", cid), include_str!("../fixtures/character_wallet.json")),
+            (Method::GET, format!("/latest/characters/{}/wallet/journal", cid), include_str!("../fixtures/character_wallet_journal.json")),
+            (Method::GET, format!("/latest/characters/{}/wallet/transactions", cid), include_str!("../fixtures/character_wallet_transactions.json")),
+            (Method::GET, format!("/latest/corporations/{}/", corp), include_str!("../fixtures/corporation.json")),
+            (Method::GET, format!("/latest/corporations/{}", corp), include_str!("../fixtures/corporation.json")),
+            (Method::GET, format!("/latest/corporations/{}/assets", corp), include_str!("../fixtures/corporation_assets.json")),
+            (Method::POST, format!("/latest/corporations/{}/assets/names", corp), include_str!("../fixtures/corporation_asset_names.json")),
+            (Method::GET, format!("/latest/corporations/{}/blueprints", corp), include_str!("../fixtures/corporation_blueprints.json")),
+            (Method::GET, format!("/latest/corporations/{}/industry/jobs", corp), include_str!("../fixtures/corporation_industry_jobs.json")),
+            (Method::GET, format!("/latest/corporations/{}/contracts", corp), include_str!("../fixtures/corporation_contracts.json")),
+            (Method::GET, format!("/latest/corporations/{}/contracts/{}/items", corp, CORPORATION_CONTRACT_ID), include_str!("../fixtures/corporation_contract_items.json")),
+            (Method::GET, format!("/latest/corporations/{}/contracts/{}/bids", corp, CORPORATION_CONTRACT_ID), "[]"),
+            (Method::GET, format!("/latest/corporations/{}/structures", corp), include_str!("../fixtures/corporation_structures.json")),
+            (Method::GET, format!("/latest/corporation/{}/mining/extractions", corp), include_str!("../fixtures/corporation_mining_extractions.json")),
+            (Method::GET, format!("/latest/corporation/{}/mining/observers", corp), include_str!("../fixtures/corporation_mining_observers.json")),
+            (Method::GET, format!("/latest/corporation/{}/mining/observers/{}", corp, STRUCTURE_ID), include_str!("../fixtures/corporation_mining_ledger.json")),
+            (Method::GET, format!("/latest/corporations/{}/wallets", corp), include_str!("../fixtures/corporation_wallets.json")),
+            (Method::GET, format!("/latest/corporations/{}/wallets/1/journal", corp), include_str!("../fixtures/corporation_wallet_journal.json")),
+            (Method::GET, format!("/latest/corporations/{}/wallets/7/journal", corp), "[]"),
+            (Method::GET, format!("/latest/corporations/{}/wallets/1/transactions", corp), include_str!("../fixtures/corporation_wallet_transactions.json")),
+            (Method::GET, format!("/latest/alliances/{}", ALLIANCE_ID), include_str!("../fixtures/alliance.json")),
+            (Method::GET, format!("/latest/universe/structures/{}", STRUCTURE_ID), include_str!("../fixtures/structure.json")),
+            (Method::GET, format!("/latest/universe/systems/{}", SYSTEM_ID), include_str!("../fixtures/system.json")),
+            (Method::GET, format!("/latest/universe/stations/{}", STATION_ID), include_str!("../fixtures/station.json")),
+            (Method::GET, format!("/latest/universe/types/{}", TYPE_ID), include_str!("../fixtures/type.json")),
+            (Method::GET, format!("/latest/universe/groups/{}", GROUP_ID), include_str!("../fixtures/group.json")),
+            (Method::GET, format!("/latest/markets/{}/orders", REGION_ID), include_str!("../fixtures/market_orders.json")),
+            (Method::GET, format!("/latest/markets/{}/history", REGION_ID), include_str!("../fixtures/market_history.json")),
+            (Method::GET, "/latest/industry/systems".into(), include_str!("../fixtures/industry_systems.json")),
+            (Method::GET, "/latest/industry/facilities".into(), include_str!("../fixtures/industry_facilities.json")),
+            (Method::GET, "/latest/markets/prices".into(), include_str!("../fixtures/market_prices.json")),
+            (Method::GET, format!("/latest/markets/structures/{}", STRUCTURE_ID), include_str!("../fixtures/structure_market_orders.json")),
+            (Method::POST, "/latest/universe/names".into(), include_str!("../fixtures/universe_names.json")),
+            (Method::POST, "/latest/universe/ids".into(), include_str!("../fixtures/universe_ids.json")),
+        ];
+        for (method, path, body) in fixtures {
+            self.mock(method, &path, MockResponse::json(body));
+        }
+
+        self.mock(Method::POST, "/v2/oauth/token", MockResponse::json(token()));
+        self.mock(Method::GET, "/oauth/jwks", MockResponse::json(JWKS));
+    }
+}
+
+/// Creates the claims of an access token issued by the mocked EVE-SSO.
+///
+/// # Params
+///
+/// * `character_id` -> Character the token belongs to
+///
+/// # Returns
+///
+/// Claims that are accepted by a validator for the client `caph_client_id`
+///
+pub fn claims(character_id: CharacterId) -> serde_json::Value {
+    serde_json::json!({
+        "scp":   SCOPES,
+        "sub":   format!("CHARACTER:EVE:{}", character_id),
+        "name":  "Caph Character",
+        "owner": "caph_owner_hash",
+        "iss":   "https://login.eveonline.com",
+        "aud":   ["caph_client_id", "EVE Online"],
+        "exp":   chrono::Utc::now().timestamp() + 1199,
+    })
+}
+
+/// Signs the given claims with the key published in [JWKS].
+///
+/// # Params
+///
+/// * `claims` -> Claims of the token
+///
+/// # Panics
+///
+/// Panics if the fixture key is not a valid RSA key.
+///
+/// # Returns
+///
+/// Signed access token
+///
+pub fn sign(claims: &serde_json::Value) -> String {
+    let mut header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256);
+    header.kid = Some("JWT-Signature-Key".into());
+
+    #[allow(clippy::unwrap_used)]
+    let key = jsonwebtoken::EncodingKey::from_rsa_pem(JWT_PRIVATE_KEY.as_bytes()).unwrap();
+    #[allow(clippy::unwrap_used)]
+    jsonwebtoken::encode(&header, claims, &key).unwrap()
+}
+
+/// Creates the response of the token endpoint.
+///
+/// The access token is signed with the key published in [JWKS].
+///
+/// # Returns
+///
+/// Serialized token response
+///
+pub fn token() -> String {
+    serde_json::json!({
+        "access_token":  sign(&claims(CHARACTER_ID)),
+        "token_type":    "Bearer",
+        "expires_in":    1199,
+        "refresh_token": REFRESH_TOKEN,
+    })
+    .to_string()
+}
+
+/// Answers a single request using the registered routes.
+///
+/// # Params
+///
+/// * `routes`   -> Registered routes
+/// * `requests` -> Log of all requests
+/// * `req`      -> Incoming request
+///
+/// # Errors
+///
+/// Never fails, unknown routes result in a `404`
+///
+/// # Returns
+///
+/// Response for the request
+///
+async fn handle(
+    routes: Routes,
+    requests: Arc<Mutex<Vec<String>>>,
+    req: Request<Body>,
+) -> Result<hyper::Response<Body>, Infallible> {
+    let path = req.uri().path().to_string();
+    let full_path = req
+        .uri()
+        .path_and_query()
+        .map_or_else(|| path.clone(), |x| x.to_string());
+
+    #[allow(clippy::unwrap_used)]
+    requests
+        .lock()
+        .unwrap()
+        .push(format!("{} {}", req.method(), full_path));
+
+    let response = {
+        #[allow(clippy::unwrap_used)]
+        let routes = routes.lock().unwrap();
+        routes
+            .get(&(req.method().clone(), full_path))
+            .or_else(|| routes.get(&(req.method().clone(), path)))
+            .cloned()
+            .unwrap_or_else(|| MockResponse::error(404, "Not found"))
+    };
+
+    let mut builder = hyper::Response::builder().status(response.status);
+    for (name, value) in response.headers {
+        builder = builder.header(name, value);
+    }
+    #[allow(clippy::unwrap_used)]
+    Ok(builder.body(Body::from(response.body)).unwrap())
+}
@@ -0,0 +1,118 @@
+use async_trait::*;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::CharacterId;
+use crate::jwt::{unverified_character_id, unverified_expiry, unverified_scopes};
+
+/// Time before the expiry of an `access_token` in which it is already
+/// refreshed, so that it does not expire while a request is in flight
+pub(crate) const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Shared handle to a [TokenStore], as stored by the clients
+pub type SharedTokenStore = Arc<dyn TokenStore>;
+
+/// Trait for persisting refresh tokens.
+///
+/// The EVE-SSO may return a new `refresh_token` when an `access_token` is
+/// requested, the old one becomes invalid at some point. The
+/// [crate::EveAuthClient] calls the store every time the `refresh_token`
+/// changes, so that the application can replace its copy.
+///
+/// Errors of a store should not abort a request, implementations should log
+/// them.
+///
+#[async_trait]
+pub trait TokenStore: Send + Sync {
+    /// Stores the new `refresh_token`.
+    ///
+    /// # Params
+    ///
+    /// * `previous`      -> `refresh_token` that was used until now
+    /// * `refresh_token` -> New `refresh_token` that replaces `previous`
+    ///
+    async fn store(&self, previous: &str, refresh_token: &str);
+}
+
+/// Tokens of a single [crate::EveAuthClient]
+#[derive(Debug)]
+pub(crate) struct TokenState {
+    /// Token to get a new `access_token`
+    pub(crate) refresh_token: String,
+    /// Token needed to get data that is behind auth
+    pub(crate) access_token:  Option<String>,
+    /// Point in time the `access_token` expires, [None] if unknown
+    pub(crate) expires_at:    Option<Instant>,
+    /// Character the tokens belong to, read from the `access_token`
+    pub(crate) character_id:  Option<CharacterId>,
+    /// Scopes granted to the `access_token`, [None] if they could not be
+    /// read
+    pub(crate) scopes:        Option<Vec<String>>,
+}
+
+impl TokenState {
+    /// Creates a new state without an `access_token`.
+    ///
+    /// # Params
+    ///
+    /// * `refresh_token` -> Token to get a new `access_token`
+    ///
+    /// # Returns
+    ///
+    /// New state instance
+    ///
+    pub(crate) fn new(refresh_token: String) -> Self {
+        Self {
+            refresh_token,
+            access_token: None,
+            expires_at:   None,
+            character_id: None,
+            scopes:       None,
+        }
+    }
+
+    /// Gets the `access_token` if it is valid for at least [REFRESH_MARGIN].
+    ///
+    /// An `access_token` without a known expiry is used until the EVE-API
+    /// rejects it.
+    ///
+    /// # Returns
+    ///
+    /// - [None] if there is no usable `access_token`
+    /// - The current `access_token`
+    ///
+    pub(crate) fn valid_access_token(&self) -> Option<&str> {
+        let valid = match self.expires_at {
+            Some(x) => x > Instant::now() + REFRESH_MARGIN,
+            None    => true,
+        };
+
+        if valid {
+            self.access_token.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// Sets a new `access_token`.
+    ///
+    /// If the lifetime is not known, the expiry is read from the `exp` claim
+    /// of the token.
+    ///
+    /// # Params
+    ///
+    /// * `access_token` -> New `access_token`
+    /// * `expires_in`   -> Lifetime of the token in seconds, [None] if unknown
+    ///
+    pub(crate) fn set_access_token(&mut self, access_token: String, expires_in: Option<i32>) {
+        let expires_in = expires_in
+            .map(i64::from)
+            .or_else(|| unverified_expiry(&access_token).map(|x| x - chrono::Utc::now().timestamp()));
+
+        self.character_id = unverified_character_id(&access_token);
+        self.scopes = unverified_scopes(&access_token);
+        self.access_token = Some(access_token);
+        self.expires_at = expires_in
+            .map(|x| Instant::now() + Duration::from_secs(x.max(0) as u64));
+    }
+}
@@ -1,7 +1,26 @@
 use serde::Deserialize;
 
-use crate::{SystemId, LocationId};
-use crate::{ConnectError, EveAuthClient, RequestClient};
+use crate::{CategoryId, GroupId, LocationId, StationId, SystemId, TypeId};
+use crate::{ConnectError, Endpoint, EveAuthClient, RequestClient};
+
+/// Maximum number of entries the bulk endpoints accept in a single request
+const MAX_BULK_ENTRIES: usize = 1000;
+
+/// Information about a structure
+const STRUCTURE: Endpoint = Endpoint::new("latest/universe/structures/{structure_id}")
+    .scope("esi-universe.read_structures.v1");
+/// Information about a system
+const SYSTEM: Endpoint = Endpoint::new("latest/universe/systems/{system_id}");
+/// Information about a station
+const STATION: Endpoint = Endpoint::new("latest/universe/stations/{station_id}");
+/// Information about a type
+const TYPE: Endpoint = Endpoint::new("latest/universe/types/{type_id}");
+/// Information about a group
+const GROUP: Endpoint = Endpoint::new("latest/universe/groups/{group_id}");
+/// Resolves ids to names
+const NAMES: Endpoint = Endpoint::new("latest/universe/names");
+/// Resolves names to ids
+const IDS: Endpoint = Endpoint::new("latest/universe/ids");
 
 /// Wrapper for character
 #[derive(Debug, Default)]
@@ -28,11 +47,12 @@ impl EveUniverseService {
         client: &EveAuthClient,
         lid:    LocationId,
     ) -> Result<(LocationId, Structure), ConnectError> {
-        let path = format!("latest/universe/structures/{}", lid);
+        let route = STRUCTURE
+            .route()
+            .param("structure_id", lid);
         client
-            .fetch::<Structure>(&path)
+            .fetch_route::<Structure>(&route)
             .await
-            .map_err(Into::into)
             .map(|x| (lid, x))
     }
 
@@ -52,11 +72,142 @@ impl EveUniverseService {
         client: &impl RequestClient,
         sid:    &SystemId,
     ) -> Result<System, ConnectError> {
-        let path = format!("latest/universe/systems/{}", sid);
+        let route = SYSTEM
+            .route()
+            .param("system_id", sid);
+        client
+            .fetch_route::<System>(&route)
+            .await
+    }
+
+    /// Gets information about the given NPC station.
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    /// - If the [StationId] is not a valid id
+    ///
+    /// # Returns
+    ///
+    /// Information about the station
+    ///
+    pub async fn station(
+        &self,
+        client: &impl RequestClient,
+        sid:    StationId,
+    ) -> Result<Station, ConnectError> {
+        let route = STATION
+            .route()
+            .param("station_id", sid);
+        client
+            .fetch_route::<Station>(&route)
+            .await
+    }
+
+    /// Gets information about the given type.
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    /// - If the [TypeId] is not a valid id
+    ///
+    /// # Returns
+    ///
+    /// Information about the type
+    ///
+    pub async fn type_info(
+        &self,
+        client: &impl RequestClient,
+        tid:    TypeId,
+    ) -> Result<TypeInfo, ConnectError> {
+        let route = TYPE
+            .route()
+            .param("type_id", tid);
+        client
+            .fetch_route::<TypeInfo>(&route)
+            .await
+    }
+
+    /// Gets information about the given group.
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    /// - If the [GroupId] is not a valid id
+    ///
+    /// # Returns
+    ///
+    /// Information about the group
+    ///
+    pub async fn group(
+        &self,
+        client: &impl RequestClient,
+        gid:    GroupId,
+    ) -> Result<GroupInfo, ConnectError> {
+        let route = GROUP
+            .route()
+            .param("group_id", gid);
         client
-            .fetch::<System>(&path)
+            .fetch_route::<GroupInfo>(&route)
             .await
-            .map_err(Into::into)
+    }
+
+    /// Resolves a list of ids to their names and categories.
+    ///
+    /// Supported are ids of characters, corporations, alliances, stations,
+    /// systems, constellations, regions, types and factions.
+    /// The ids are send in chunks of 1000, the maximum the EVE API accepts.
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    /// - If any of the ids is not valid, the EVE API rejects the whole chunk
+    ///
+    /// # Returns
+    ///
+    /// Name and category of every id
+    ///
+    pub async fn names(
+        &self,
+        client: &impl RequestClient,
+        ids:    Vec<i32>,
+    ) -> Result<Vec<UniverseName>, ConnectError> {
+        let mut names = Vec::new();
+        for chunk in ids.chunks(MAX_BULK_ENTRIES) {
+            let entries = client
+                .post_route::<_, Vec<UniverseName>>(chunk, &NAMES.route())
+                .await?;
+            names.extend(entries);
+        }
+        Ok(names)
+    }
+
+    /// Resolves a list of names to their ids.
+    ///
+    /// Only exact matches are resolved. The names are send in chunks of
+    /// 1000, the maximum the EVE API accepts.
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    ///
+    /// # Returns
+    ///
+    /// All resolved ids, grouped by their category
+    ///
+    pub async fn ids(
+        &self,
+        client: &impl RequestClient,
+        names:  Vec<String>,
+    ) -> Result<UniverseIds, ConnectError> {
+        let mut ids = UniverseIds::default();
+        for chunk in names.chunks(MAX_BULK_ENTRIES) {
+            let entries = client
+                .post_route::<_, UniverseIds>(chunk, &IDS.route())
+                .await?;
+            ids.extend(entries);
+        }
+        Ok(ids)
     }
 }
 
@@ -79,3 +230,198 @@ pub struct System {
     /// Id of the system
     pub system_id:       SystemId
 }
+
+/// Represents a NPC station
+#[derive(Clone, Debug, Deserialize)]
+pub struct Station {
+    /// Name of the station
+    pub name:       String,
+    /// Id of the station
+    pub station_id: StationId,
+    /// Id of the system the station is located in
+    pub system_id:  SystemId,
+}
+
+/// Represents a type
+#[derive(Clone, Debug, Deserialize)]
+pub struct TypeInfo {
+    /// Id of the type
+    pub type_id:  TypeId,
+    /// Id of the group the type belongs to
+    pub group_id: GroupId,
+    /// Name of the type
+    pub name:     String,
+    /// Volume of a single unit
+    #[serde(default)]
+    pub volume:   f32,
+}
+
+/// Represents a group
+#[derive(Clone, Debug, Deserialize)]
+pub struct GroupInfo {
+    /// Id of the group
+    pub group_id:    GroupId,
+    /// Id of the category the group belongs to
+    pub category_id: CategoryId,
+    /// Name of the group
+    pub name:        String,
+}
+
+/// Name of an id, returned by [EveUniverseService::names]
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct UniverseName {
+    /// Category of the id, for example `character` or `inventory_type`
+    pub category: String,
+    /// The resolved id
+    pub id:       i32,
+    /// Name of the id
+    pub name:     String,
+}
+
+/// Single id returned by [EveUniverseService::ids]
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct UniverseId {
+    /// The resolved id
+    pub id:   i32,
+    /// Name the id was resolved from
+    pub name: String,
+}
+
+/// Ids of names, returned by [EveUniverseService::ids]
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct UniverseIds {
+    /// Matching agents
+    #[serde(default)]
+    pub agents:          Vec<UniverseId>,
+    /// Matching alliances
+    #[serde(default)]
+    pub alliances:       Vec<UniverseId>,
+    /// Matching characters
+    #[serde(default)]
+    pub characters:      Vec<UniverseId>,
+    /// Matching constellations
+    #[serde(default)]
+    pub constellations:  Vec<UniverseId>,
+    /// Matching corporations
+    #[serde(default)]
+    pub corporations:    Vec<UniverseId>,
+    /// Matching factions
+    #[serde(default)]
+    pub factions:        Vec<UniverseId>,
+    /// Matching types
+    #[serde(default)]
+    pub inventory_types: Vec<UniverseId>,
+    /// Matching regions
+    #[serde(default)]
+    pub regions:         Vec<UniverseId>,
+    /// Matching stations
+    #[serde(default)]
+    pub stations:        Vec<UniverseId>,
+    /// Matching systems
+    #[serde(default)]
+    pub systems:         Vec<UniverseId>,
+}
+
+impl UniverseIds {
+    /// Appends all ids of `other`.
+    ///
+    /// # Params
+    ///
+    /// * `other` -> Ids to append
+    ///
+    pub fn extend(&mut self, other: UniverseIds) {
+        self.agents.extend(other.agents);
+        self.alliances.extend(other.alliances);
+        self.characters.extend(other.characters);
+        self.constellations.extend(other.constellations);
+        self.corporations.extend(other.corporations);
+        self.factions.extend(other.factions);
+        self.inventory_types.extend(other.inventory_types);
+        self.regions.extend(other.regions);
+        self.stations.extend(other.stations);
+        self.systems.extend(other.systems);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod universe_tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[tokio::test]
+    async fn structure_and_system() {
+        let esi = MockEsi::start().await;
+        let client = esi.auth_client().unwrap();
+        let service = EveUniverseService;
+
+        let (lid, structure) = service.structure(&client, STRUCTURE_ID).await.unwrap();
+        assert_eq!(lid, STRUCTURE_ID);
+        assert_eq!(structure.solar_system_id, SYSTEM_ID);
+
+        let system = service.system_name(&client, &SYSTEM_ID).await.unwrap();
+        assert_eq!(system.name, "Jita");
+
+        let station = service.station(&client, STATION_ID).await.unwrap();
+        assert_eq!(station.system_id, SYSTEM_ID);
+    }
+
+    #[tokio::test]
+    async fn bulk_requests_are_chunked() {
+        let esi = MockEsi::start().await;
+        let client = esi.client().unwrap();
+        let service = EveUniverseService;
+
+        let names = service
+            .names(&client, (0..2500).collect())
+            .await
+            .unwrap();
+        // every chunk returns the same two fixture entries
+        assert_eq!(names.len(), 6);
+        assert_eq!(names[0].category, "character");
+
+        let ids = service
+            .ids(&client, vec!["Tritanium".into(), "Jita".into()])
+            .await
+            .unwrap();
+        assert_eq!(ids.inventory_types[0].id, *TYPE_ID);
+        assert_eq!(ids.systems[0].id, *SYSTEM_ID as i32);
+
+        let requests = esi.requests();
+        assert_eq!(requests.iter().filter(|x| *x == "POST /latest/universe/names").count(), 3);
+    }
+
+    #[tokio::test]
+    async fn posts_are_cached() {
+        let esi = MockEsi::start().await;
+        let expires = (chrono::Utc::now() + chrono::Duration::minutes(5)).to_rfc2822();
+        esi.mock(
+            hyper::Method::POST,
+            "/latest/universe/names",
+            MockResponse::json(include_str!("../fixtures/universe_names.json"))
+                .header("expires", &expires),
+        );
+        let client = esi.client().unwrap().with_cache(crate::MemoryCache::new());
+        let service = EveUniverseService;
+
+        service.names(&client, vec![1, 2]).await.unwrap();
+        service.names(&client, vec![1, 2]).await.unwrap();
+        service.names(&client, vec![3]).await.unwrap();
+
+        // the second request is served from the cache, the third has a different body
+        assert_eq!(esi.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn type_and_group() {
+        let esi = MockEsi::start().await;
+        let client = esi.client().unwrap();
+        let service = EveUniverseService;
+
+        let info = service.type_info(&client, TYPE_ID).await.unwrap();
+        assert_eq!(info.group_id, GROUP_ID);
+
+        let group = service.group(&client, info.group_id).await.unwrap();
+        assert_eq!(*group.category_id, 4);
+    }
+}
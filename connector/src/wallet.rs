@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{LocationId, TypeId};
+
+/// Single entry of a wallet journal, shared by characters and corporations
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JournalEntry {
+    /// Unique ID of the entry
+    pub id:              i64,
+    /// Date the transaction was performed
+    pub date:            DateTime<Utc>,
+    /// Type of the transaction, for example `player_donation` or
+    /// `brokers_fee`
+    pub ref_type:        String,
+    /// Information about the transaction
+    pub description:     String,
+
+    /// ISK amount, negative if ISK left the wallet
+    #[serde(default)]
+    pub amount:          f64,
+    /// Balance of the wallet after the transaction
+    #[serde(default)]
+    pub balance:         Option<f64>,
+    /// Reason given by the player, for example for donations
+    #[serde(default)]
+    pub reason:          Option<String>,
+
+    /// Character, corporation or alliance that sent the ISK
+    #[serde(default)]
+    pub first_party_id:  Option<i32>,
+    /// Character, corporation or alliance that received the ISK
+    #[serde(default)]
+    pub second_party_id: Option<i32>,
+
+    /// Id of the object the transaction is about, the kind of object is
+    /// given by `context_id_type`
+    #[serde(default)]
+    pub context_id:      Option<i64>,
+    /// Kind of object `context_id` references
+    #[serde(default)]
+    pub context_id_type: Option<JournalContextType>,
+
+    /// Tax that was paid
+    #[serde(default)]
+    pub tax:             Option<f64>,
+    /// Corporation that received the tax
+    #[serde(default)]
+    pub tax_receiver_id: Option<i32>,
+}
+
+/// Kind of object the `context_id` of a [JournalEntry] references
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalContextType {
+    /// Id of an alliance
+    AllianceId,
+    /// Id of a character
+    CharacterId,
+    /// Id of a contract
+    ContractId,
+    /// Id of a corporation
+    CorporationId,
+    /// Id of a solar system, used for bounties
+    EveSystem,
+    /// Id of an industry job
+    IndustryJobId,
+    /// Id of a market transaction, see [WalletTransaction::transaction_id]
+    MarketTransactionId,
+    /// Id of a planet
+    PlanetId,
+    /// Id of a station
+    StationId,
+    /// Id of a structure
+    StructureId,
+    /// Id of a solar system
+    SystemId,
+    /// Id of a type
+    TypeId,
+    /// Kind that is not known yet
+    #[serde(other)]
+    Unknown,
+}
+
+/// Single market transaction, shared by characters and corporations
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WalletTransaction {
+    /// Unique ID of the transaction
+    pub transaction_id: i64,
+    /// Id of the [JournalEntry] that belongs to the transaction
+    pub journal_ref_id: i64,
+    /// Date the transaction was performed
+    pub date:           DateTime<Utc>,
+    /// Character or corporation on the other side of the transaction
+    pub client_id:      i32,
+    /// True if the item was bought
+    pub is_buy:         bool,
+    /// Station or structure the transaction took place
+    pub location_id:    LocationId,
+    /// [TypeId] of the item
+    pub type_id:        TypeId,
+    /// Number of items
+    pub quantity:       i32,
+    /// Price of a single item
+    pub unit_price:     f64,
+
+    /// True if the transaction was done for the character and not for its
+    /// corporation, only set for characters
+    #[serde(default)]
+    pub is_personal:    Option<bool>,
+}
+
+impl WalletTransaction {
+    /// Gets the ISK amount of the whole transaction.
+    ///
+    /// # Returns
+    ///
+    /// Price of all items, negative if the items were bought
+    ///
+    pub fn amount(&self) -> f64 {
+        let amount = self.unit_price * self.quantity as f64;
+        if self.is_buy {
+            -amount
+        } else {
+            amount
+        }
+    }
+}
+
+/// Represents a wallet entry
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WalletEntry {
+    /// Current balance of the division
+    pub balance: f64,
+    /// Devision number, eg: 1 is the master wallet
+    pub division: u8,
+}
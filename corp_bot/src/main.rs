@@ -1,6 +1,6 @@
 mod asset;
 
-use appraisal::{Appraisal, Janice};
+use appraisal::{Appraisal, AppraisalOptions, Janice};
 use caph_connector::{EveAuthClient, CorporationService};
 use num_format::{Locale, ToFormattedString};
 use reqwest::{header::{HeaderMap, HeaderValue}, Client};
@@ -83,7 +83,7 @@ Total           {} ISK
                 (wallet.moon as u64).to_formatted_string(&Locale::de),
                 (wallet.alliance as u64).to_formatted_string(&Locale::de),
                 (asset_worth as u64).to_formatted_string(&Locale::de),
-                ((wallet.master + wallet.moon + wallet.moon + asset_worth) as u64).to_formatted_string(&Locale::de)
+                ((wallet.master + wallet.moon + wallet.moon + asset_worth as f64) as u64).to_formatted_string(&Locale::de)
             ),
             allowed_mentions: AllowedMentions::default()
         })
@@ -97,7 +97,7 @@ Total           {} ISK
     Ok(())
 }
 
-async fn assets(pool: &PgPool) -> f32 {
+async fn assets(pool: &PgPool) -> f64 {
     let info = sqlx::query!(r#"
                 SELECT
                     c.corporation_id AS "corporation_id!",
@@ -125,27 +125,19 @@ async fn assets(pool: &PgPool) -> f32 {
         .filter(|x| !x.is_blueprint_copy)
         .collect::<Vec<_>>();
 
-    let mut entries = Vec::new();
-    for asset in assets {
-        let name = sqlx::query!("
-                    SELECT name
-                    FROM items
-                    WHERE type_id = $1
-                ",
-                *asset.type_id
-            )
-            .fetch_one(pool)
-            .await
-            .unwrap()
-            .name;
-        entries.push(format!("{} {}", name, asset.quantity));
-    }
+    let types = assets
+        .into_iter()
+        .map(|x| (x.type_id, x.quantity as u64))
+        .collect::<Vec<_>>();
+    let entries = appraisal::parser::entries(pool, types)
+        .await
+        .unwrap();
 
     Janice::validate().unwrap();
     let janice = Janice::init().unwrap();
 
     janice
-        .create(false, entries)
+        .create(&AppraisalOptions::default(), entries)
         .await
         .unwrap()
         .sell_price
@@ -172,7 +164,7 @@ async fn journal(pool: &PgPool) {
     let client = EveAuthClient::new(info.refresh_token).unwrap();
     let corporation_service = CorporationService::new(info.corporation_id.into());
     let journal = corporation_service
-        .wallet_journal(&client)
+        .wallet_journal(&client, 1)
         .await
         .unwrap();
     dbg!(journal);
@@ -211,7 +203,7 @@ async fn wallets(pool: &PgPool) -> Wallets {
 }
 
 struct Wallets {
-    master:   f32,
-    moon:     f32,
-    alliance: f32,
+    master:   f64,
+    moon:     f64,
+    alliance: f64,
 }
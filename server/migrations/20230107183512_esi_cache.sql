@@ -0,0 +1,13 @@
+-- Cache for responses of the EVE-API, used by caph_connector::PostgresCache
+CREATE TABLE esi_cache (
+    key           VARCHAR     NOT NULL,
+
+    etag          VARCHAR,
+    last_modified VARCHAR,
+    expires       TIMESTAMPTZ,
+
+    pages         INTEGER     NOT NULL,
+    body          BYTEA       NOT NULL,
+
+    PRIMARY KEY (key)
+);
@@ -0,0 +1,34 @@
+-- Moon pulls are imported from the EVE-API, which does not report the
+-- materials of an extraction
+ALTER TABLE moon_pulls
+  ALTER COLUMN material_1        DROP NOT NULL,
+  ALTER COLUMN material_2        DROP NOT NULL,
+  ALTER COLUMN material_1_amount DROP NOT NULL,
+  ALTER COLUMN material_2_amount DROP NOT NULL,
+
+  -- Refinery that runs the extraction
+  ADD COLUMN structure_id     BIGINT,
+  ADD COLUMN moon_id          INTEGER,
+  -- Time the extraction was started
+  ADD COLUMN extraction_start TIMESTAMPTZ,
+  -- Time the chunk fractures on its own
+  ADD COLUMN natural_decay    TIMESTAMPTZ,
+
+  ADD PRIMARY KEY (id);
+
+CREATE UNIQUE INDEX moon_pulls_extraction
+  ON moon_pulls(structure_id, extraction_start);
+
+ALTER TABLE moon_materials
+  ALTER COLUMN amount TYPE BIGINT,
+
+  -- Character that mined the material
+  ADD COLUMN character_id INTEGER,
+  -- Day the material was mined
+  ADD COLUMN mined_on     DATE,
+
+  ADD CONSTRAINT moon_materials_moon
+    FOREIGN KEY (moon) REFERENCES moon_pulls(id) ON DELETE CASCADE;
+
+CREATE UNIQUE INDEX moon_materials_mined
+  ON moon_materials(moon, character_id, type_id, mined_on);
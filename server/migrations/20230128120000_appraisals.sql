@@ -0,0 +1,43 @@
+-- Appraisals created by any provider of the appraisal library
+CREATE TABLE appraisals (
+  id          UUID        NOT NULL DEFAULT uuid_generate_v4(),
+
+  -- Provider that created the appraisal
+  provider    VARCHAR     NOT NULL,
+  -- Code and uri for sharing, only set by some providers
+  code        VARCHAR,
+  uri         VARCHAR,
+
+  -- Prices for all items
+  sell_price  REAL        NOT NULL,
+  split_price REAL        NOT NULL,
+  buy_price   REAL        NOT NULL,
+
+  created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+  PRIMARY KEY (id)
+);
+
+CREATE TABLE appraisal_items (
+  appraisal   UUID        NOT NULL,
+
+  type_id     INTEGER     NOT NULL,
+  name        VARCHAR     NOT NULL,
+  quantity    BIGINT      NOT NULL,
+
+  -- Provider that produced the prices
+  provider    VARCHAR     NOT NULL,
+  -- Prices for a single item
+  sell_price  REAL        NOT NULL,
+  split_price REAL        NOT NULL,
+  buy_price   REAL        NOT NULL,
+
+  -- Same as the appraisal, so that the price history does not need a join
+  created_at  TIMESTAMPTZ NOT NULL,
+
+  CONSTRAINT appraisal_items_appraisal
+    FOREIGN KEY (appraisal) REFERENCES appraisals(id) ON DELETE CASCADE
+);
+
+CREATE INDEX appraisal_items_history
+  ON appraisal_items(type_id, created_at);
@@ -0,0 +1,8 @@
+-- Market hub the prices of an appraisal were taken from
+ALTER TABLE appraisals
+  ADD COLUMN region_id   INTEGER NOT NULL DEFAULT 10000002,
+  ADD COLUMN location_id BIGINT  NOT NULL DEFAULT 60003760;
+
+ALTER TABLE appraisals
+  ALTER COLUMN region_id   DROP DEFAULT,
+  ALTER COLUMN location_id DROP DEFAULT;
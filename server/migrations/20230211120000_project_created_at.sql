@@ -0,0 +1,5 @@
+-- Time a project was created, contracts issued before it are no deliveries.
+-- Existing projects keep NULL, as their creation time is unknown.
+ALTER TABLE projects ADD COLUMN created_at TIMESTAMPTZ;
+
+ALTER TABLE projects ALTER COLUMN created_at SET DEFAULT NOW();
@@ -0,0 +1,3 @@
+-- Products that are imported from the clipboard have no known material efficiency
+ALTER TABLE project_products ALTER COLUMN meff DROP NOT NULL;
+ALTER TABLE project_products ALTER COLUMN meff DROP DEFAULT;
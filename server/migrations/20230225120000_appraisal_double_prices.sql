@@ -0,0 +1,11 @@
+-- REAL only has about 7 significant digits, which is not enough for the
+-- totals of large appraisals
+ALTER TABLE appraisals
+  ALTER COLUMN sell_price  TYPE DOUBLE PRECISION,
+  ALTER COLUMN split_price TYPE DOUBLE PRECISION,
+  ALTER COLUMN buy_price   TYPE DOUBLE PRECISION;
+
+ALTER TABLE appraisal_items
+  ALTER COLUMN sell_price  TYPE DOUBLE PRECISION,
+  ALTER COLUMN split_price TYPE DOUBLE PRECISION,
+  ALTER COLUMN buy_price   TYPE DOUBLE PRECISION;
@@ -0,0 +1,13 @@
+-- Options the appraisal was created with, so that it can be re-valued with
+-- the same options
+ALTER TABLE appraisals
+  ADD COLUMN pricing    VARCHAR NOT NULL DEFAULT 'top5',
+  ADD COLUMN percentile REAL,
+  ADD COLUMN persist    BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Only shared appraisals have a code
+UPDATE appraisals SET persist = code IS NOT NULL;
+
+ALTER TABLE appraisals
+  ALTER COLUMN pricing DROP DEFAULT,
+  ALTER COLUMN persist DROP DEFAULT;
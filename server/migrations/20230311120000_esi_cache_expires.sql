@@ -0,0 +1,2 @@
+-- Expired entries without ETag are removed periodically
+CREATE INDEX esi_cache_expires ON esi_cache (expires) WHERE etag IS NULL;
@@ -2,10 +2,12 @@ mod api;
 mod auth_character;
 mod error;
 mod service;
+mod token_store;
 mod user;
 
 pub use self::api::*;
 pub use self::auth_character::*;
 pub use self::error::*;
 pub use self::service::*;
+pub use self::token_store::*;
 pub use self::user::*;
@@ -1,8 +1,8 @@
-use caph_connector::{CharacterId, EveAuthClient, AllianceId, CorporationId};
+use caph_connector::{CharacterId, EveAuthClient, AllianceId, CorporationId, PostgresCache};
 use sqlx::PgPool;
 use std::convert::Infallible;
 use warp::{Filter, Rejection};
-use crate::{Error, AuthError, ProjectId};
+use crate::{Error, AuthError, LoginTokenStore, ProjectId};
 
 #[derive(Clone, Debug)]
 pub struct AuthCharacter {
@@ -98,7 +98,9 @@ impl AuthCharacter {
             .refresh_token(cid)
             .await?;
         let client = EveAuthClient::new(refresh_token)
-            .map_err(AuthError::CreateAuthClient)?;
+            .map_err(AuthError::CreateAuthClient)?
+            .with_cache(PostgresCache::new(self.pool.clone()))
+            .with_token_store(LoginTokenStore::new(self.pool.clone()));
         Ok(client)
     }
 
@@ -1,8 +1,8 @@
-use crate::{error::Error, ProjectId};
+use crate::{error::Error, LoginTokenStore, ProjectId};
 
 use async_trait::*;
 use axum::extract::{FromRequest, Extension, RequestParts};
-use caph_connector::{CharacterId, EveAuthClient, EveOAuthToken};
+use caph_connector::{CharacterId, EveAuthClient, EveJwtValidator, EveOAuthToken};
 use sqlx::PgPool;
 use tracing::instrument;
 use serde::Deserialize;
@@ -19,6 +19,10 @@ pub const ESI_READ_CORPORATION_ASSETS:        &str = "esi-assets.read_corporatio
 
 pub const ESI_READ_INDUSTRY_JOBS:             &str = "esi-industry.read_character_jobs.v1";
 pub const ESI_READ_CORPORATION_INDUSTRY_JOBS: &str = "esi-corporations.read_blueprints.v1";
+pub const ESI_READ_CORPORATION_MINING:        &str = "esi-industry.read_corporation_mining.v1";
+
+pub const ESI_READ_CONTRACTS:                 &str = "esi-contracts.read_character_contracts.v1";
+pub const ESI_READ_CORPORATION_CONTRACTS:     &str = "esi-contracts.read_corporation_contracts.v1";
 
 pub const ESI_DEFAULT_SCOPE: &[&str] = &[
     ESI_PUBLIC_DATA
@@ -44,6 +48,9 @@ const ESI_ALL: &[&str] = &[
     ESI_READ_CORPORATION_ASSETS,
     ESI_READ_INDUSTRY_JOBS,
     ESI_READ_CORPORATION_INDUSTRY_JOBS,
+    ESI_READ_CORPORATION_MINING,
+    ESI_READ_CONTRACTS,
+    ESI_READ_CORPORATION_CONTRACTS,
 ];
 
 /// Handles authentication and authorisation.
@@ -52,7 +59,9 @@ const ESI_ALL: &[&str] = &[
 /// Authorisation is configured and handled by this service.
 #[derive(Clone, Debug)]
 pub struct AuthService {
-    pool: PgPool
+    pool: PgPool,
+    /// Validates the access tokens send by the EVE-SSO
+    jwt:  EveJwtValidator,
 }
 
 impl AuthService {
@@ -61,16 +70,19 @@ impl AuthService {
     /// # Params
     ///
     /// * `pool` -> Connection pool to postgres&str
+    /// * `jwt`  -> Validator for the access tokens of the EVE-SSO
     ///
     /// # Returns
     ///
     /// New instance of the [AuthService].
     ///
     pub fn new(
-        pool: PgPool
+        pool: PgPool,
+        jwt:  EveJwtValidator,
     ) -> Self {
         Self {
-            pool
+            pool,
+            jwt,
         }
     }
 
@@ -242,6 +254,17 @@ impl AuthService {
         Ok(alts)
     }
 
+    /// Creates a store that keeps the refresh tokens of the logins up to
+    /// date.
+    ///
+    /// # Returns
+    ///
+    /// Store for [EveAuthClient::with_token_store]
+    ///
+    pub fn token_store(&self) -> LoginTokenStore {
+        LoginTokenStore::new(self.pool.clone())
+    }
+
     #[instrument(err)]
     pub async fn refresh_token(
         &self,
@@ -395,8 +418,14 @@ impl AuthService {
         state:     &str,
         character: EveOAuthToken
     ) -> Result<(), Error> {
-        let character_id = character.character_id()?;
-        let scp = character.payload()?.scp.into_vec();
+        let payload = self.jwt
+            .validate_or_reload(&character.access_token)
+            .await
+            .map_err(|e| {
+                tracing::warn!({ error = %e }, "Invalid access token");
+                Error::InvalidUser
+            })?;
+        let character_id = payload.character_id()?;
 
         sqlx::query!("
                 UPDATE logins
@@ -404,13 +433,14 @@ impl AuthService {
                     character_id = $1,
                     refresh_token = $2,
                     access_token = $3,
-                    expire_date = NOW() + interval '1199' second,
-                    token = $4
-                WHERE token = $5
+                    expire_date = $4,
+                    token = $5
+                WHERE token = $6
             ",
             *character_id,
             &character.refresh_token,
             &character.access_token,
+            payload.expires_at,
             token,
             state
         )
@@ -429,7 +459,7 @@ impl AuthService {
                 WHERE character_id = $1
             ",
             *character_id,
-            &scp,
+            &payload.scopes,
         )
         .execute(&self.pool)
         .await
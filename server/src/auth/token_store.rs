@@ -0,0 +1,106 @@
+use async_trait::*;
+use caph_connector::TokenStore;
+use sqlx::PgPool;
+
+/// Keeps the column `refresh_token` of the table `logins` up to date, when
+/// the EVE-SSO rotates a refresh token.
+#[derive(Clone, Debug)]
+pub struct LoginTokenStore(PgPool);
+
+impl LoginTokenStore {
+    /// Creates a new store instance.
+    ///
+    /// # Params
+    ///
+    /// * `pool` -> Connection pool to postgres
+    ///
+    /// # Returns
+    ///
+    /// New store instance
+    ///
+    pub fn new(pool: PgPool) -> Self {
+        Self(pool)
+    }
+}
+
+#[async_trait]
+impl TokenStore for LoginTokenStore {
+    async fn store(&self, previous: &str, refresh_token: &str) {
+        let result = sqlx::query!("
+                UPDATE logins
+                SET refresh_token = $2
+                WHERE refresh_token = $1
+            ",
+                previous,
+                refresh_token,
+            )
+            .execute(&self.0)
+            .await;
+
+        if let Err(e) = result {
+            tracing::error!({ error = e.to_string() }, "Error storing refresh token");
+        }
+    }
+}
+
+#[cfg(test)]
+mod token_store_tests {
+    use caph_connector::EveCharacterService;
+    use caph_connector::testing::*;
+    use hyper::Method;
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn rotated_refresh_token_is_saved() {
+        dotenvy::dotenv().ok();
+        let pg_addr = std::env::var("DATABASE_URL").unwrap();
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&pg_addr)
+            .await
+            .unwrap();
+
+        sqlx::query!("
+                INSERT INTO logins (token, character_id, refresh_token)
+                VALUES ('caph_token_store_test', $1, $2)
+                ON CONFLICT (token) DO UPDATE SET refresh_token = EXCLUDED.refresh_token
+            ",
+                *CHARACTER_ID,
+                REFRESH_TOKEN,
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let esi = MockEsi::start().await;
+        let token = serde_json::json!({
+            "access_token":  sign(&claims(CHARACTER_ID)),
+            "token_type":    "Bearer",
+            "expires_in":    1199,
+            "refresh_token": "caph_rotated_refresh_token",
+        });
+        esi.mock(Method::POST, "/v2/oauth/token", MockResponse::json(token.to_string()));
+
+        let client = esi
+            .auth_client()
+            .unwrap()
+            .with_token_store(LoginTokenStore::new(pool.clone()));
+        EveCharacterService::new(CHARACTER_ID)
+            .industry_jobs(&client)
+            .await
+            .unwrap();
+
+        let refresh_token = sqlx::query!("
+                DELETE FROM logins
+                WHERE token = 'caph_token_store_test'
+                RETURNING refresh_token
+            ")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .refresh_token;
+        assert_eq!(refresh_token, Some("caph_rotated_refresh_token".into()));
+    }
+}
@@ -51,7 +51,8 @@ impl AuthUser {
         )
         .await?;
 
-        let client = EveAuthClient::new(refresh_token)?;
+        let client = EveAuthClient::new(refresh_token)?
+            .with_token_store(self.auth_service.token_store());
         Ok(client)
     }
 
@@ -1,7 +1,7 @@
-use crate::{AuthService, ESI_DEFAULT_SCOPE, TimedCache};
+use crate::{AuthService, ESI_DEFAULT_SCOPE, LoginTokenStore};
 use crate::error::Error;
 
-use caph_connector::{AllianceId, CharacterId, EveCharacterService, CorporationId, EveAuthClient, BlueprintEntry, EveClient, TypeId, EveCorporationService, IndustryJobEntry, CharacterInfo, CorporationInfo};
+use caph_connector::{AllianceId, CharacterId, EveCharacterService, CorporationId, EveAuthClient, BlueprintEntry, EveClient, TypeId, EveCorporationService, CharacterInfo, CorporationInfo, PostgresCache};
 use serde::Serialize;
 use sqlx::PgPool;
 
@@ -125,7 +125,9 @@ impl CharacterService {
                 .refresh_token(&cid)
                 .await?;
 
-            let client = EveAuthClient::new(refresh_token)?;
+            let client = EveAuthClient::new(refresh_token)?
+                .with_cache(PostgresCache::new(self.pool.clone()))
+                .with_token_store(LoginTokenStore::new(self.pool.clone()));
             let character_service = EveCharacterService::new(cid);
             let character_bps = character_service.blueprints(&client).await?;
 
@@ -161,7 +163,9 @@ impl CharacterService {
                 .refresh_token(&char_id)
                 .await?;
 
-            let client = EveAuthClient::new(refresh_token)?;
+            let client = EveAuthClient::new(refresh_token)?
+                .with_cache(PostgresCache::new(self.pool.clone()))
+                .with_token_store(LoginTokenStore::new(self.pool.clone()));
             let corporation_service = EveCorporationService::new(corp_id);
             let corporation_bps = corporation_service
                 .blueprints(&client)
@@ -2,16 +2,25 @@ use axum::body::BoxBody;
 use axum::http::StatusCode;
 use axum::Json;
 use axum::response::IntoResponse;
+use caph_connector::ConnectError;
 use hmac::digest::InvalidLength;
 use serde_json::json;
 use std::env::VarError;
 use std::fmt;
+use warp::{Rejection, Reply};
+
+use crate::AuthError;
+use crate::industry::error::IndustryError;
+use crate::market::error::MarketError;
+use crate::moon::error::MoonError;
 
 // TODO: Rename to Error
 #[derive(Debug)]
 pub enum Error {
     /// Contains all errors that can come from the appraisal library
     AppraisalError(appraisal::Error),
+    /// Contains all errors of creating or reading stored appraisals
+    MarketError(MarketError),
 
     SerdeJsonError(serde_json::Error),
     Database(sqlx::Error),
@@ -25,6 +34,9 @@ pub enum Error {
     NotFound,
     Unauthorized,
     BadRequest,
+    /// Text copied from the EVE client contains lines that could not be
+    /// parsed or resolved, contains those lines
+    UnparsedLines(Vec<String>),
 
     /// The address could not be parsed
     CouldNotParseServerListenAddr,
@@ -65,6 +77,12 @@ impl From<caph_connector::ConnectError> for Error {
     }
 }
 
+impl From<MarketError> for Error {
+    fn from(e: MarketError) -> Self {
+        Self::MarketError(e)
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)
@@ -73,23 +91,176 @@ impl fmt::Display for Error {
 
 impl warp::reject::Reject for Error {}
 
-impl IntoResponse for Error {
-    fn into_response(self) -> axum::http::Response<BoxBody> {
-        let (status, msg) = match self {
-            Error::BadRequest   => (StatusCode::BAD_REQUEST, "Bad Request"),
-            Error::InvalidUser  => (StatusCode::FORBIDDEN, "Forbidden"),
-            Error::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized"),
-            Error::NotFound     => (StatusCode::NOT_FOUND, "Requested entry not found"),
+impl Error {
+    /// Gets the status and message that is send to the client.
+    ///
+    /// # Returns
+    ///
+    /// Status code and a message that is safe to show
+    ///
+    fn status(&self) -> (StatusCode, &'static str) {
+        match self {
+            Error::BadRequest       => (StatusCode::BAD_REQUEST, "Bad Request"),
+            Error::UnparsedLines(_) => (StatusCode::BAD_REQUEST, "Some lines could not be parsed"),
+            Error::InvalidUser      => (StatusCode::FORBIDDEN, "Forbidden"),
+            Error::Unauthorized     => (StatusCode::UNAUTHORIZED, "Unauthorized"),
+            Error::NotFound         => (StatusCode::NOT_FOUND, "Requested entry not found"),
+            Error::MarketError(MarketError::AppraisalNotFound) => {
+                (StatusCode::NOT_FOUND, "Requested entry not found")
+            }
+            Error::ConnectError(e)  => connect_error_status(e),
             _ => {
                 tracing::error!("Error {:?}", self);
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
             }
-        };
+        }
+    }
 
-        let body = Json(json!({
-            "error": msg
-        }));
+    /// Creates the body that is send to the client.
+    ///
+    /// # Params
+    ///
+    /// * `msg` -> Message that is safe to show
+    ///
+    /// # Returns
+    ///
+    /// Body with the message and the lines that could not be parsed
+    ///
+    fn body(&self, msg: &str) -> serde_json::Value {
+        match self {
+            Error::UnparsedLines(x) => json!({
+                "error":    msg,
+                "unparsed": x,
+            }),
+            _ => json!({
+                "error": msg
+            }),
+        }
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> axum::http::Response<BoxBody> {
+        let (status, msg) = self.status();
+        let body = Json(self.body(msg));
 
         (status, body).into_response()
     }
 }
+
+/// Maps errors of the EVE-API to the status that is send to the client.
+///
+/// # Params
+///
+/// * `e` -> Error of the EVE-API
+///
+/// # Returns
+///
+/// - `403` if the character is missing a scope or role
+/// - `404` if the EVE-API does not know the entry
+/// - `502` if the EVE-API is not available
+/// - `500` for everything else
+///
+fn connect_error_status(e: &ConnectError) -> (StatusCode, &'static str) {
+    tracing::warn!(
+        { error = %e, endpoint = ?e.endpoint(), character_id = ?e.character_id() },
+        "Error from the EVE-API"
+    );
+
+    if let ConnectError::MissingScope(_) = e {
+        return (StatusCode::FORBIDDEN, "The character is missing a required scope or role");
+    }
+
+    match e.status() {
+        Some(StatusCode::UNAUTHORIZED) |
+        Some(StatusCode::FORBIDDEN) => {
+            (StatusCode::FORBIDDEN, "The character is missing a required scope or role")
+        }
+        Some(StatusCode::NOT_FOUND) => {
+            (StatusCode::NOT_FOUND, "Requested entry not found")
+        }
+        _ if e.is_retryable() => {
+            (StatusCode::BAD_GATEWAY, "The EVE-API is not available")
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+    }
+}
+
+/// Converts the rejections of the warp api into responses.
+///
+/// Rejections that are not caused by an error of this crate are passed on.
+///
+/// # Params
+///
+/// * `rejection` -> Rejection of a route
+///
+/// # Errors
+///
+/// If the rejection is not caused by an error of this crate
+///
+/// # Returns
+///
+/// Response with the status and message of the error
+///
+pub async fn recover(rejection: Rejection) -> Result<impl Reply, Rejection> {
+    if let Some(e) = rejection.find::<Error>() {
+        let (status, msg) = e.status();
+        let body = warp::reply::json(&e.body(msg));
+        return Ok(warp::reply::with_status(body, status));
+    }
+
+    let (status, msg) = if let Some(AuthError::CreateAuthClient(e)) = rejection.find::<AuthError>() {
+        connect_error_status(e)
+    } else if let Some(IndustryError::FetchCharacterAssetName(e)) = rejection.find::<IndustryError>() {
+        connect_error_status(e)
+    } else if let Some(
+        MoonError::FetchExtractions(e) |
+        MoonError::FetchObservers(e) |
+        MoonError::FetchLedger(e)
+    ) = rejection.find::<MoonError>() {
+        connect_error_status(e)
+    } else if let Some(MarketError::AppraisalNotFound) = rejection.find::<MarketError>() {
+        (StatusCode::NOT_FOUND, "Requested entry not found")
+    } else {
+        return Err(rejection);
+    };
+
+    let body = warp::reply::json(&json!({
+        "error": msg
+    }));
+    Ok(warp::reply::with_status(body, status))
+}
+
+#[cfg(test)]
+mod error_tests {
+    use caph_connector::{EveCharacterService, EveMarketService};
+    use caph_connector::testing::*;
+    use hyper::Method;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn connect_errors_map_to_status() {
+        let esi = MockEsi::start().await;
+        esi.mock(
+            Method::GET,
+            &format!("/latest/characters/{}/blueprints", CHARACTER_ID),
+            MockResponse::error(403, "token not valid for scope(s)"),
+        );
+        esi.mock(Method::GET, "/latest/markets/prices", MockResponse::error(503, "Service Unavailable"));
+
+        let error: Error = EveCharacterService::new(CHARACTER_ID)
+            .blueprints(&esi.auth_client().unwrap())
+            .await
+            .unwrap_err()
+            .into();
+        assert_eq!(error.into_response().status(), StatusCode::FORBIDDEN);
+
+        let error: Error = EveMarketService
+            .prices(&esi.client().unwrap())
+            .await
+            .unwrap_err()
+            .into();
+        assert_eq!(error.into_response().status(), StatusCode::BAD_GATEWAY);
+    }
+}
@@ -389,6 +389,9 @@ impl AssetService {
 
     /// Gets all locations that the character has assets in.
     /// 
+    /// Citadels are resolved using the structure endpoint, NPC stations are
+    /// not part of the SDE tables and resolved using the station endpoint.
+    /// 
     /// # Params
     /// 
     /// * `pool`   > Connection to postgres
@@ -412,8 +415,11 @@ impl AssetService {
                         location_flag = 'Hangar' OR
                         location_flag = 'Deliveries'
                     )
-                    -- Only filter on citadels
-                    AND location_id > 1000000000000
+                    -- Only filter on citadels and npc stations
+                    AND (
+                        location_id > 1000000000000 OR
+                        location_id BETWEEN 60000000 AND 64000000
+                    )
                     AND character_id = $1
                     GROUP BY location_id
             ",
@@ -428,8 +434,13 @@ impl AssetService {
 
         let universe_service = EveUniverseService::default();
         let mut structures = FuturesUnordered::new();
+        let mut stations = FuturesUnordered::new();
         for id in ids {
-            structures.push(universe_service.structure(client, id));
+            if *id > 1_000_000_000_000 {
+                structures.push(universe_service.structure(client, id));
+            } else {
+                stations.push(universe_service.station(client, (*id).into()));
+            }
         }
 
         let mut location_ids  = Vec::new();
@@ -440,6 +451,11 @@ impl AssetService {
             system_ids.push(*structure.solar_system_id);
             names.push(structure.name);
         }
+        while let Some(Ok(station)) = stations.next().await {
+            location_ids.push(*station.station_id);
+            system_ids.push(*station.system_id);
+            names.push(station.name);
+        }
 
         sqlx::query!("
                     INSERT INTO asset_locations
@@ -467,3 +483,114 @@ impl AssetService {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod industry_tests {
+    use caph_connector::testing::*;
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+
+    fn character() -> AuthCharacterInfo {
+        AuthCharacterInfo {
+            alliance_id:    Some(ALLIANCE_ID),
+            character_id:   CHARACTER_ID,
+            corporation_id: CORPORATION_ID,
+        }
+    }
+
+    #[tokio::test]
+    async fn character_and_corporation_jobs() {
+        let esi = MockEsi::start().await;
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/caph")
+            .unwrap();
+        let service = IndustryService::new(pool);
+
+        let jobs = service
+            .character_jobs(vec![(character(), esi.auth_client().unwrap())])
+            .await
+            .unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].installer_id, CHARACTER_ID);
+
+        // the same corporation twice must not duplicate the jobs
+        let jobs = service
+            .corporation_jobs(vec![
+                (character(), esi.auth_client().unwrap()),
+                (character(), esi.auth_client().unwrap()),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].job_id, 500000002);
+    }
+
+    #[tokio::test]
+    async fn character_assets() {
+        dotenvy::dotenv().ok();
+        let pg_addr = std::env::var("DATABASE_URL").unwrap();
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&pg_addr)
+            .await
+            .unwrap();
+
+        sqlx::query!("
+                INSERT INTO characters
+                (
+                    character_id,
+                    corporation_id,
+                    character_name,
+                    corporation_name
+                )
+                VALUES ($1, $2, 'Caph Tester', 'Caph Test Corporation')
+                ON CONFLICT DO NOTHING
+            ",
+                *CHARACTER_ID,
+                *CORPORATION_ID,
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let esi = MockEsi::start().await;
+        IndustryService::new(pool.clone())
+            .character_assets(vec![(character(), esi.auth_client().unwrap())])
+            .await;
+
+        let assets = sqlx::query!("
+                SELECT item_id, location_id, reference_id
+                FROM assets
+                WHERE character_id = $1
+                ORDER BY item_id
+            ",
+                *CHARACTER_ID
+            )
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(assets.len(), 3);
+        // the minerals are in a container, located in the structure
+        assert_eq!(assets[1].location_id, *STRUCTURE_ID);
+        assert_eq!(assets[1].reference_id, Some(assets[0].item_id));
+
+        let locations = sqlx::query!("
+                SELECT location_id, system_id, name
+                FROM asset_locations
+                WHERE character_id = $1
+                ORDER BY location_id
+            ",
+                *CHARACTER_ID
+            )
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations[0].location_id, *STATION_ID);
+        assert_eq!(locations[0].system_id, *SYSTEM_ID);
+        assert_eq!(locations[1].name, "Jita - Caph Test Tatara");
+
+        AssetService::remove_assets(&pool, CHARACTER_ID).await.unwrap();
+    }
+}
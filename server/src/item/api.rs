@@ -17,6 +17,7 @@ impl ItemApi {
         Router::new()
             .route("/components", get(Self::components))
             .route("/buildable", get(Self::buildable))
+            .route("/parse", post(Self::parse))
             .route("/resolve", post(Self::resolve_id_from_name_bulk))
             .route("/resolve/id/:tid", post(Self::resolve_tid))
     }
@@ -55,6 +56,18 @@ impl ItemApi {
             .map_err(Into::into)
     }
 
+    /// Parses text that is copied from the EVE client and resolves the
+    /// names of all items.
+    async fn parse(
+        service: Extension<ItemService>,
+        body:    String,
+    ) -> Result<impl IntoResponse, Error> {
+        service
+            .parse(body)
+            .await
+            .map(|x| (StatusCode::OK, Json(x)))
+    }
+
     /// Takes a list of names and resolves those names to [TypeId]s.
     async fn resolve_id_from_name_bulk(
         service:       Extension<ItemService>,
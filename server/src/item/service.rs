@@ -1,18 +1,28 @@
 use crate::Error;
 
-use caph_connector::{TypeId, CategoryId, GroupId};
+use appraisal::ResolvedText;
+use caph_connector::{TypeId, CategoryId, GroupId, EveClient, EveUniverseService};
+use futures::stream::{self, StreamExt};
 use serde::{Serialize, Deserialize};
 use sqlx::PgPool;
 
+/// Maximum number of types that are resolved against the EVE API at once
+const ESI_CONCURRENCY: usize = 10;
+
 #[derive(Clone)]
 pub struct ItemService {
-    pool: PgPool
+    pool:   PgPool,
+    client: EveClient,
 }
 
 impl ItemService {
-    pub fn new(pool: PgPool) -> Self {
+    pub fn new(
+        pool:   PgPool,
+        client: EveClient,
+    ) -> Self {
         Self {
-            pool
+            pool,
+            client,
         }
     }
 
@@ -136,6 +146,32 @@ impl ItemService {
         Ok(entry)
     }
 
+    /// Parses text that is copied from the EVE client, for example an
+    /// inventory window, a multibuy list or an EFT fitting, and resolves
+    /// the names of all items.
+    ///
+    /// # Params
+    ///
+    /// * `text` -> Text copied from the EVE client
+    ///
+    /// # Errors
+    ///
+    /// If the database access failes.
+    ///
+    /// # Returns
+    ///
+    /// All resolved items and their quantity, together with all lines and
+    /// names that could not be parsed or resolved.
+    ///
+    pub async fn parse(
+        &self,
+        text: String,
+    ) -> Result<ResolvedText, Error> {
+        appraisal::parser::resolve(&self.pool, &text)
+            .await
+            .map_err(Error::AppraisalError)
+    }
+
     /// Takes a name and resolves the name to a [TypeId].
     /// 
     /// # Params
@@ -154,12 +190,15 @@ impl ItemService {
     /// is not found or does not match the filter, the returning array may
     /// be smaller than the given array.
     /// 
+    /// Names that are not in the SDE tables are resolved using the EVE API,
+    /// except when only buildable items are requested.
+    /// 
     pub async fn resolve_id_from_name_bulk(
         &self,
         names:  Vec<String>,
         filter: ResolveIdNameFilter
     ) -> Result<Vec<Item>, Error> {
-        let entries = if let Some(true) = filter.is_buildable {
+        let mut entries = if let Some(true) = filter.is_buildable {
             sqlx::query!(r#"
                     SELECT
                         bman.ptype_id AS "type_id!",
@@ -210,8 +249,74 @@ impl ItemService {
             })
             .collect::<Vec<_>>()
         };
+
+        let missing = names
+            .into_iter()
+            .filter(|x| !entries.iter().any(|e| &e.name == x))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() && filter.is_buildable != Some(true) {
+            entries.extend(self.resolve_id_from_name_esi(missing).await);
+        }
+
         Ok(entries)
     }
+
+    /// Resolves the given names using the EVE API.
+    /// 
+    /// The types are fetched concurrently, types that could not be fetched
+    /// are logged and left out.
+    /// 
+    /// # Params
+    /// 
+    /// * `names` -> List of names that should be resolved
+    /// 
+    /// # Returns
+    /// 
+    /// List of all items that could be resolved. If the EVE API is not
+    /// available, the list is empty.
+    /// 
+    async fn resolve_id_from_name_esi(
+        &self,
+        names: Vec<String>,
+    ) -> Vec<Item> {
+        let ids = match EveUniverseService.ids(&self.client, names).await {
+            Ok(x) => x,
+            Err(e) => {
+                tracing::warn!({ error = ?e }, "Could not resolve names");
+                return Vec::new();
+            }
+        };
+
+        stream::iter(ids.inventory_types)
+            .map(|entry| async move {
+                let info = EveUniverseService
+                    .type_info(&self.client, entry.id.into())
+                    .await?;
+                let group = EveUniverseService
+                    .group(&self.client, info.group_id)
+                    .await?;
+
+                Ok::<_, caph_connector::ConnectError>(Item {
+                    type_id:     info.type_id,
+                    category_id: group.category_id,
+                    group_id:    info.group_id,
+                    volume:      info.volume,
+                    name:        info.name,
+                })
+            })
+            .buffer_unordered(ESI_CONCURRENCY)
+            .filter_map(|x| async move {
+                match x {
+                    Ok(x) => Some(x),
+                    Err(e) => {
+                        tracing::warn!({ error = ?e }, "Could not resolve type, skipping");
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<_>>()
+            .await
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -10,6 +10,8 @@ pub mod character;
 pub mod error;
 pub mod industry;
 pub mod item;
+pub mod market;
+pub mod moon;
 pub mod project;
 pub mod structure;
 pub mod timed_cache;
@@ -20,6 +22,8 @@ pub use crate::character::*;
 pub use crate::error::*;
 pub use crate::industry::*;
 pub use crate::item::*;
+pub use crate::market::*;
+pub use crate::moon::*;
 pub use crate::project::*;
 pub use crate::structure::*;
 pub use crate::timed_cache::*;
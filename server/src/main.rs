@@ -1,4 +1,6 @@
+use appraisal::{Appraisal, AppraisalChain};
 use axum::{Extension, Router};
+use caph_connector::{EveClient, EveJwtValidator, PostgresCache};
 use caph_server::*;
 use sqlx::PgPool;
 use sqlx::postgres::PgPoolOptions;
@@ -32,9 +34,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .await?;
     sqlx::migrate!().run(&pool).await?;
 
-    let auth_service = AuthService::new(pool.clone());
+    let jwt = EveJwtValidator::from_env().await.map_err(Error::from)?;
+    let auth_service = AuthService::new(pool.clone(), jwt);
     let character_service = CharacterService::new(pool.clone(), auth_service.clone());
-    let item_service = ItemService::new(pool.clone());
+    let eve_client = EveClient::new()
+        .map_err(Error::from)?
+        .with_cache(PostgresCache::new(pool.clone()));
+    let item_service = ItemService::new(pool.clone(), eve_client);
+    let appraisal = AppraisalChain::init().map_err(Error::AppraisalError)?;
 
     //let project_blueprint_service =
     //    ProjectBlueprintService::new(pool.clone(), character_service.clone());
@@ -67,7 +74,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .map_err(|_| Error::CouldNotParseServerListenAddr)?;
     tracing::info!("Starting server");
 
-    let server = Server::new(auth_service, pool);
+    let server = Server::new(pool, Arc::new(appraisal));
     let v1 = axum::Server::bind(&bind).serve(app);
 
     let _ = tokio::join! {
@@ -79,25 +86,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 struct Server {
-    auth: AuthService,
-    pool: PgPool,
+    pool:      PgPool,
+    appraisal: Arc<AppraisalChain>,
 }
 
 impl Server {
-    pub fn new(auth: AuthService, pool: PgPool) -> Self {
-        Self { auth, pool }
+    pub fn new(pool: PgPool, appraisal: Arc<AppraisalChain>) -> Self {
+        Self { pool, appraisal }
     }
 
     pub async fn listen(self) {
         let base_path = warp::path!("api" / "v2" / ..).boxed();
 
         let indy = IndustryApi::api(self.pool.clone(), base_path.clone());
+        let market = MarketApi::api(self.pool.clone(), self.appraisal.clone(), base_path.clone());
+        let moons = MoonApi::api(self.pool.clone(), base_path.clone());
         let projects = ProjectApiV2::api(self.pool.clone(), base_path.clone());
         let structure = StructureApi::api(self.pool.clone(), base_path.clone());
 
         let routes = indy
+            .or(market)
+            .or(moons)
             .or(projects)
-            .or(structure);
+            .or(structure)
+            .recover(caph_server::recover);
         warp::serve(routes).run(([127, 0, 0, 1], 10102)).await
     }
 }
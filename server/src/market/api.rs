@@ -0,0 +1,159 @@
+use appraisal::AppraisalChain;
+use caph_connector::TypeId;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+use warp::{Filter, Reply, Rejection};
+use warp::filters::BoxedFilter;
+
+use crate::{AuthCharacter, with_authorization};
+use super::service::{AppraisalConfig, MarketService, PriceHistoryFilter, with_market_service};
+
+#[derive(Clone, Debug)]
+pub struct MarketApi;
+
+impl MarketApi {
+    /// Filters that build up the api for this part of the application
+    pub fn api(
+        pool:      PgPool,
+        appraisal: Arc<AppraisalChain>,
+        base_path: BoxedFilter<()>,
+    ) -> BoxedFilter<(impl Reply,)> {
+        let base_path = base_path
+            .clone()
+            .and(warp::path!("market" / ..))
+            .and(with_authorization(pool.clone()))
+            .and(with_market_service(pool.clone(), appraisal))
+            .boxed();
+
+        let appraise = base_path
+            .clone()
+            .and(warp::path!("appraisals"))
+            .and(warp::post())
+            .and(warp::body::json())
+            .and_then(Self::appraise)
+            .boxed();
+
+        let appraisal = base_path
+            .clone()
+            .and(warp::path!("appraisals" / Uuid))
+            .and(warp::get())
+            .and_then(Self::appraisal)
+            .boxed();
+
+        let revalue = base_path
+            .clone()
+            .and(warp::path!("appraisals" / Uuid / "revalue"))
+            .and(warp::post())
+            .and_then(Self::revalue)
+            .boxed();
+
+        let price_history = base_path
+            .clone()
+            .and(warp::path!("prices" / TypeId / "history"))
+            .and(warp::get())
+            .and(warp::query())
+            .and_then(Self::price_history)
+            .boxed();
+
+        appraise
+            .or(appraisal)
+            .or(revalue)
+            .or(price_history)
+            .boxed()
+    }
+
+    /// Creates a new appraisal and stores it.
+    ///
+    /// # Errors
+    ///
+    /// - If no provider is configured or none of them supports the market
+    ///   hub or answered
+    /// - If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// Stored appraisal
+    ///
+    async fn appraise(
+        _:       AuthCharacter,
+        service: MarketService,
+        body:    AppraisalConfig,
+    ) -> Result<impl Reply, Rejection> {
+        service
+            .appraise(&body.options, body.entries)
+            .await
+            .map_err(warp::reject::custom)
+            .map(|x| warp::reply::json(&x))
+    }
+
+    /// Gets a stored appraisal.
+    ///
+    /// # Errors
+    ///
+    /// - If the appraisal does not exist
+    /// - If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// Stored appraisal
+    ///
+    async fn appraisal(
+        _:       AuthCharacter,
+        service: MarketService,
+        id:      Uuid,
+    ) -> Result<impl Reply, Rejection> {
+        service
+            .appraisal(id)
+            .await
+            .map_err(warp::reject::custom)
+            .map(|x| warp::reply::json(&x))
+    }
+
+    /// Appraises the items of a stored appraisal with the current prices.
+    ///
+    /// # Errors
+    ///
+    /// - If the appraisal does not exist
+    /// - If no provider is configured or none of them answered
+    /// - If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// The stored and the new appraisal
+    ///
+    async fn revalue(
+        _:       AuthCharacter,
+        service: MarketService,
+        id:      Uuid,
+    ) -> Result<impl Reply, Rejection> {
+        service
+            .revalue(id)
+            .await
+            .map_err(warp::reject::custom)
+            .map(|x| warp::reply::json(&x))
+    }
+
+    /// Gets the prices of an item from all stored appraisals.
+    ///
+    /// # Errors
+    ///
+    /// - If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// Prices of the item, oldest first
+    ///
+    async fn price_history(
+        _:       AuthCharacter,
+        service: MarketService,
+        tid:     TypeId,
+        filter:  PriceHistoryFilter,
+    ) -> Result<impl Reply, Rejection> {
+        service
+            .price_history(tid, filter)
+            .await
+            .map_err(warp::reject::custom)
+            .map(|x| warp::reply::json(&x))
+    }
+}
@@ -0,0 +1,10 @@
+#[derive(Debug)]
+pub enum MarketError {
+    AppraisalNotFound,
+    CreateAppraisal(appraisal::Error),
+    SaveAppraisal(sqlx::Error),
+    FetchAppraisal(sqlx::Error),
+    FetchPriceHistory(sqlx::Error),
+}
+
+impl warp::reject::Reject for MarketError { }
@@ -0,0 +1,635 @@
+use appraisal::{Appraisal, AppraisalChain, AppraisalEntry, AppraisalInformation, AppraisalOptions, MarketHub, Pricing};
+use caph_connector::TypeId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::convert::Infallible;
+use std::sync::Arc;
+use uuid::Uuid;
+use warp::Filter;
+
+use super::error::MarketError;
+
+#[derive(Clone, Debug)]
+pub struct MarketService {
+    /// Database pool
+    pool:      PgPool,
+    /// Configured appraisal providers, shared between all requests
+    appraisal: Arc<AppraisalChain>,
+}
+
+impl MarketService {
+    pub fn new(
+        pool:      PgPool,
+        appraisal: Arc<AppraisalChain>,
+    ) -> Self {
+        Self {
+            pool,
+            appraisal,
+        }
+    }
+
+    /// Creates a new appraisal with the configured providers and stores it.
+    ///
+    /// # Params
+    ///
+    /// * `options` > Market hub, pricing and if the appraisal should be shared
+    /// * `entries` > Items that should be appraised
+    ///
+    /// # Errors
+    ///
+    /// - If no provider is configured or none of them supports the market
+    ///   hub or answered
+    /// - If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// Stored appraisal
+    ///
+    pub async fn appraise(
+        &self,
+        options: &AppraisalOptions,
+        entries: Vec<AppraisalEntry>,
+    ) -> Result<StoredAppraisal, MarketError> {
+        let information = self.appraisal
+            .create(options, entries)
+            .await
+            .map_err(MarketError::CreateAppraisal)?;
+
+        let id = self.save(options, &information).await?;
+        self.appraisal(id).await
+    }
+
+    /// Stores the totals and the prices of every item of an appraisal,
+    /// together with the options it was created with.
+    ///
+    /// # Params
+    ///
+    /// * `options`     > Options the appraisal was created with
+    /// * `information` > Appraisal created by any provider
+    ///
+    /// # Errors
+    ///
+    /// - If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// Id of the stored appraisal
+    ///
+    pub async fn save(
+        &self,
+        options:     &AppraisalOptions,
+        information: &AppraisalInformation,
+    ) -> Result<Uuid, MarketError> {
+        let mut transaction = self.pool
+            .begin()
+            .await
+            .map_err(MarketError::SaveAppraisal)?;
+
+        let appraisal = sqlx::query!("
+                INSERT INTO appraisals (
+                    provider,
+                    code,
+                    uri,
+                    sell_price,
+                    split_price,
+                    buy_price,
+                    region_id,
+                    location_id,
+                    pricing,
+                    percentile,
+                    persist
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                RETURNING id, created_at
+            ",
+                information.provider,
+                information.code,
+                information.uri,
+                information.sell_price,
+                information.split_price,
+                information.buy_price,
+                *information.market.region_id,
+                *information.market.location_id,
+                options.pricing.as_str(),
+                options.percentile,
+                options.persist,
+            )
+            .fetch_one(&mut transaction)
+            .await
+            .map_err(MarketError::SaveAppraisal)?;
+
+        let items = &information.items;
+        let type_ids = items
+            .iter()
+            .map(|x| *x.type_id)
+            .collect::<Vec<_>>();
+        let names = items
+            .iter()
+            .map(|x| x.name.clone())
+            .collect::<Vec<_>>();
+        let quantities = items
+            .iter()
+            .map(|x| x.amount as i64)
+            .collect::<Vec<_>>();
+        let providers = items
+            .iter()
+            .map(|x| x.provider.to_string())
+            .collect::<Vec<_>>();
+        let sell_prices = items
+            .iter()
+            .map(|x| x.sell_price)
+            .collect::<Vec<_>>();
+        let split_prices = items
+            .iter()
+            .map(|x| x.split_price)
+            .collect::<Vec<_>>();
+        let buy_prices = items
+            .iter()
+            .map(|x| x.buy_price)
+            .collect::<Vec<_>>();
+
+        sqlx::query!("
+                INSERT INTO appraisal_items (
+                    appraisal,
+                    created_at,
+                    type_id,
+                    name,
+                    quantity,
+                    provider,
+                    sell_price,
+                    split_price,
+                    buy_price
+                )
+                SELECT $1, $2, * FROM UNNEST(
+                    $3::INTEGER[],
+                    $4::VARCHAR[],
+                    $5::BIGINT[],
+                    $6::VARCHAR[],
+                    $7::DOUBLE PRECISION[],
+                    $8::DOUBLE PRECISION[],
+                    $9::DOUBLE PRECISION[]
+                )
+            ",
+                appraisal.id,
+                appraisal.created_at,
+                &type_ids,
+                &names,
+                &quantities,
+                &providers,
+                &sell_prices,
+                &split_prices,
+                &buy_prices,
+            )
+            .execute(&mut transaction)
+            .await
+            .map_err(MarketError::SaveAppraisal)?;
+
+        transaction
+            .commit()
+            .await
+            .map_err(MarketError::SaveAppraisal)?;
+        Ok(appraisal.id)
+    }
+
+    /// Gets a stored appraisal.
+    ///
+    /// # Params
+    ///
+    /// * `id` > Id of the appraisal
+    ///
+    /// # Errors
+    ///
+    /// - If the appraisal does not exist
+    /// - If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// Stored appraisal with all its items
+    ///
+    pub async fn appraisal(
+        &self,
+        id: Uuid,
+    ) -> Result<StoredAppraisal, MarketError> {
+        let appraisal = sqlx::query!("
+                SELECT
+                    id,
+                    provider,
+                    code,
+                    uri,
+                    sell_price,
+                    split_price,
+                    buy_price,
+                    region_id,
+                    location_id,
+                    pricing,
+                    percentile,
+                    persist,
+                    created_at
+                FROM appraisals
+                WHERE id = $1
+            ",
+                id,
+            )
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(MarketError::FetchAppraisal)?
+            .ok_or(MarketError::AppraisalNotFound)?;
+
+        let items = sqlx::query!("
+                SELECT
+                    type_id,
+                    name,
+                    quantity,
+                    provider,
+                    sell_price,
+                    split_price,
+                    buy_price
+                FROM appraisal_items
+                WHERE appraisal = $1
+                ORDER BY name
+            ",
+                id,
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(MarketError::FetchAppraisal)?
+            .into_iter()
+            .map(|x| StoredAppraisalItem {
+                type_id:     x.type_id.into(),
+                name:        x.name,
+                quantity:    x.quantity,
+                provider:    x.provider,
+                sell_price:  x.sell_price,
+                split_price: x.split_price,
+                buy_price:   x.buy_price,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(StoredAppraisal {
+            id:          appraisal.id,
+            provider:    appraisal.provider,
+            code:        appraisal.code,
+            uri:         appraisal.uri,
+            sell_price:  appraisal.sell_price,
+            split_price: appraisal.split_price,
+            buy_price:   appraisal.buy_price,
+            market:      MarketHub::new(appraisal.region_id.into(), appraisal.location_id.into()),
+            pricing:     Pricing::parse(&appraisal.pricing).unwrap_or(Pricing::Top5),
+            percentile:  appraisal.percentile,
+            persist:     appraisal.persist,
+            created_at:  appraisal.created_at,
+            items,
+        })
+    }
+
+    /// Appraises the items of a stored appraisal again with the current
+    /// prices and the same options, including the market hub. The new
+    /// appraisal is stored as well.
+    ///
+    /// # Params
+    ///
+    /// * `id` > Id of the appraisal that should be re-valued
+    ///
+    /// # Errors
+    ///
+    /// - If the appraisal does not exist
+    /// - If no provider is configured or none of them answered
+    /// - If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// The stored and the new appraisal
+    ///
+    pub async fn revalue(
+        &self,
+        id: Uuid,
+    ) -> Result<Revaluation, MarketError> {
+        let original = self.appraisal(id).await?;
+
+        let entries = original
+            .items
+            .iter()
+            .map(|x| AppraisalEntry::new(
+                x.type_id,
+                x.name.clone(),
+                x.quantity as u64,
+            ))
+            .collect::<Vec<_>>();
+        let current = self.appraise(&original.options(), entries).await?;
+
+        Ok(Revaluation {
+            original,
+            current,
+        })
+    }
+
+    /// Gets the prices of an item from all stored appraisals.
+    ///
+    /// # Params
+    ///
+    /// * `tid`    > [TypeId] of the item
+    /// * `filter` > Limits the provider and the time range
+    ///
+    /// # Errors
+    ///
+    /// - If the database is not available
+    ///
+    /// # Returns
+    ///
+    /// Prices of the item, oldest first
+    ///
+    pub async fn price_history(
+        &self,
+        tid:    TypeId,
+        filter: PriceHistoryFilter,
+    ) -> Result<Vec<PricePoint>, MarketError> {
+        let history = sqlx::query!("
+                SELECT
+                    ai.appraisal,
+                    ai.provider,
+                    ai.sell_price,
+                    ai.split_price,
+                    ai.buy_price,
+                    ai.created_at,
+                    a.region_id,
+                    a.location_id
+                FROM appraisal_items ai
+                JOIN appraisals a ON a.id = ai.appraisal
+                WHERE ai.type_id = $1
+                  AND ($2::VARCHAR IS NULL OR ai.provider = $2)
+                  AND ($3::TIMESTAMPTZ IS NULL OR ai.created_at >= $3)
+                  AND ($4::BIGINT IS NULL OR a.location_id = $4)
+                ORDER BY ai.created_at ASC
+            ",
+                *tid,
+                filter.provider,
+                filter.since,
+                filter.location_id,
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(MarketError::FetchPriceHistory)?
+            .into_iter()
+            .map(|x| PricePoint {
+                appraisal:   x.appraisal,
+                provider:    x.provider,
+                sell_price:  x.sell_price,
+                split_price: x.split_price,
+                buy_price:   x.buy_price,
+                market:      MarketHub::new(x.region_id.into(), x.location_id.into()),
+                created_at:  x.created_at,
+            })
+            .collect::<Vec<_>>();
+        Ok(history)
+    }
+}
+
+/// Filter for the API.
+///
+/// # Params
+///
+/// * `pool`      > Open connection to postgres
+/// * `appraisal` > Appraisal providers that are shared by all requests
+///
+/// # Returns
+///
+/// Initialized instance of [MarketService]
+///
+pub fn with_market_service(
+    pool:      PgPool,
+    appraisal: Arc<AppraisalChain>,
+)  -> impl Filter<Extract = (MarketService,), Error = Infallible> + Clone {
+    warp::any()
+        .map(move || MarketService::new(pool.clone(), appraisal.clone()))
+}
+
+/// Contains all information for creating a new appraisal
+#[derive(Debug, Deserialize)]
+pub struct AppraisalConfig {
+    /// Market hub, pricing and if the appraisal should be shared
+    #[serde(default)]
+    pub options: AppraisalOptions,
+    /// Items that should be appraised
+    pub entries: Vec<AppraisalEntry>,
+}
+
+/// Appraisal that is stored in the database
+#[derive(Debug, Serialize)]
+pub struct StoredAppraisal {
+    /// Id of the appraisal
+    pub id:          Uuid,
+    /// Provider that created the appraisal
+    pub provider:    String,
+    /// Optional code to share the appraisal
+    pub code:        Option<String>,
+    /// Uri for sharing the appraisal
+    pub uri:         Option<String>,
+    /// Sell price for all items
+    pub sell_price:  f64,
+    /// Split price for all items
+    pub split_price: f64,
+    /// Buy price for all items
+    pub buy_price:   f64,
+    /// Market hub the prices were taken from
+    pub market:      MarketHub,
+    /// How the prices were calculated
+    pub pricing:     Pricing,
+    /// Share of the total volume that was requested for [Pricing::Top5]
+    pub percentile:  Option<f32>,
+    /// Whether the provider was asked to store the appraisal for sharing
+    pub persist:     bool,
+    /// Time the appraisal was created
+    pub created_at:  DateTime<Utc>,
+    /// Breakdown of all items
+    pub items:       Vec<StoredAppraisalItem>,
+}
+
+impl StoredAppraisal {
+    /// Options the appraisal was created with.
+    ///
+    /// # Returns
+    ///
+    /// Options for creating the same appraisal again
+    ///
+    pub fn options(&self) -> AppraisalOptions {
+        AppraisalOptions {
+            hub:        self.market,
+            pricing:    self.pricing,
+            percentile: self.percentile,
+            persist:    self.persist,
+        }
+    }
+}
+
+/// Single item of a stored appraisal
+#[derive(Debug, Serialize)]
+pub struct StoredAppraisalItem {
+    /// [TypeId] of the item
+    pub type_id:     TypeId,
+    /// Name of the item
+    pub name:        String,
+    /// Appraised quantity
+    pub quantity:    i64,
+    /// Provider that produced the prices
+    pub provider:    String,
+    /// Sell price for a single item
+    pub sell_price:  f64,
+    /// Split price for a single item
+    pub split_price: f64,
+    /// Buy price for a single item
+    pub buy_price:   f64,
+}
+
+/// Stored appraisal and the same items appraised with the current prices
+#[derive(Debug, Serialize)]
+pub struct Revaluation {
+    /// Appraisal that was re-valued
+    pub original: StoredAppraisal,
+    /// Appraisal with the current prices
+    pub current:  StoredAppraisal,
+}
+
+/// Filter for the price history of an item
+#[derive(Debug, Default, Deserialize)]
+pub struct PriceHistoryFilter {
+    /// Only prices of the given provider, for example `janice`
+    pub provider:    Option<String>,
+    /// Only prices of appraisals that were created after the given time
+    pub since:       Option<DateTime<Utc>>,
+    /// Only prices of the given station or structure
+    pub location_id: Option<i64>,
+}
+
+/// Prices of an item at the time of an appraisal
+#[derive(Debug, Serialize)]
+pub struct PricePoint {
+    /// Appraisal the prices are from
+    pub appraisal:   Uuid,
+    /// Provider that produced the prices
+    pub provider:    String,
+    /// Sell price for a single item
+    pub sell_price:  f64,
+    /// Split price for a single item
+    pub split_price: f64,
+    /// Buy price for a single item
+    pub buy_price:   f64,
+    /// Market hub the prices were taken from
+    pub market:      MarketHub,
+    /// Time the appraisal was created
+    pub created_at:  DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod market_tests {
+    use appraisal::{AppraisalItem, ChainMode};
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+
+    /// TypeId that is not used by any real item
+    const TYPE_ID: i32 = 999_999_024;
+
+    /// Appraisal of a single item with the given split price
+    fn information(split_price: f64) -> AppraisalInformation {
+        AppraisalInformation {
+            sell_price:  split_price * 20f64,
+            split_price: split_price * 10f64,
+            buy_price:   0f64,
+            items:       vec![AppraisalItem {
+                type_id:           TypeId::from(TYPE_ID),
+                name:              "Test item".into(),
+                amount:            10,
+                sell_price:        split_price * 2f64,
+                split_price:       split_price,
+                buy_price:         0f64,
+                sell_price_total:  split_price * 20f64,
+                split_price_total: split_price * 10f64,
+                buy_price_total:   0f64,
+                provider:          "test",
+                spread:            0f64,
+                sources:           Vec::new(),
+            }],
+            code:        Some("abcd".into()),
+            uri:         None,
+            provider:    "test",
+            market:      MarketHub::AMARR,
+        }
+    }
+
+    #[tokio::test]
+    async fn appraisals_are_stored_with_their_price_history() {
+        dotenvy::dotenv().ok();
+        let pg_addr = std::env::var("DATABASE_URL").unwrap();
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&pg_addr)
+            .await
+            .unwrap();
+
+        let service = MarketService::new(
+            pool.clone(),
+            Arc::new(AppraisalChain::new(ChainMode::Fallback)),
+        );
+        let options = AppraisalOptions {
+            hub:        MarketHub::AMARR,
+            pricing:    Pricing::Immediate,
+            percentile: Some(0.1),
+            persist:    true,
+        };
+        let first = service.save(&options, &information(5f64)).await.unwrap();
+        let second = service.save(&options, &information(7f64)).await.unwrap();
+
+        let stored = service.appraisal(first).await.unwrap();
+        let history = service
+            .price_history(TypeId::from(TYPE_ID), PriceHistoryFilter::default())
+            .await
+            .unwrap();
+        let jita = service
+            .price_history(TypeId::from(TYPE_ID), PriceHistoryFilter {
+                location_id: Some(*MarketHub::JITA.location_id),
+                ..PriceHistoryFilter::default()
+            })
+            .await
+            .unwrap();
+        let other_provider = service
+            .price_history(TypeId::from(TYPE_ID), PriceHistoryFilter {
+                provider: Some("janice".into()),
+                ..PriceHistoryFilter::default()
+            })
+            .await
+            .unwrap();
+
+        sqlx::query!("
+                DELETE FROM appraisals
+                WHERE id = ANY($1)
+            ",
+                &vec![first, second],
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(stored.provider, "test");
+        assert_eq!(stored.code, Some("abcd".into()));
+        assert_eq!(stored.split_price, 50f64);
+        assert_eq!(stored.market, MarketHub::AMARR);
+        assert_eq!(stored.options(), options);
+        assert_eq!(stored.items.len(), 1);
+        assert_eq!(stored.items[0].quantity, 10);
+        assert_eq!(stored.items[0].sell_price, 10f64);
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].appraisal, first);
+        assert_eq!(history[0].split_price, 5f64);
+        assert_eq!(history[1].split_price, 7f64);
+        assert_eq!(history[0].market, MarketHub::AMARR);
+        assert!(jita.is_empty());
+        assert!(other_provider.is_empty());
+
+        assert!(matches!(
+            service.appraisal(first).await,
+            Err(MarketError::AppraisalNotFound)
+        ));
+    }
+}
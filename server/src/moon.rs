@@ -0,0 +1,6 @@
+pub mod api;
+pub mod error;
+pub mod service;
+
+pub use self::api::*;
+pub use self::service::*;
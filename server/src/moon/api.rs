@@ -0,0 +1,69 @@
+use sqlx::PgPool;
+use warp::{Filter, Reply, Rejection};
+use warp::filters::BoxedFilter;
+
+use crate::{AuthCharacter, with_authorization, ESI_READ_CORPORATION_MINING};
+use super::service::{MoonService, with_moon_service};
+
+#[derive(Clone, Debug)]
+pub struct MoonApi;
+
+impl MoonApi {
+    /// Filters that build up the api for this part of the application
+    pub fn api(
+        pool:      PgPool,
+        base_path: BoxedFilter<()>,
+    ) -> BoxedFilter<(impl Reply,)> {
+        let base_path = base_path
+            .clone()
+            .and(warp::path!("moons" / ..))
+            .and(with_authorization(pool.clone()))
+            .and(with_moon_service(pool.clone()))
+            .boxed();
+
+        let sync = base_path
+            .clone()
+            .and(warp::path!("sync"))
+            .and(warp::post())
+            .and_then(Self::sync)
+            .boxed();
+
+        sync
+            .boxed()
+    }
+
+    /// Imports the moon extractions and mining ledgers of every corporation
+    /// the logged in character or one of its alts has access to.
+    ///
+    /// # Errors
+    ///
+    /// - If the database is not available
+    /// - If the EVE API is not available
+    /// - If a character does not have the permission
+    ///
+    /// # Returns
+    ///
+    /// Empty json on success
+    ///
+    async fn sync(
+        auth:    AuthCharacter,
+        service: MoonService,
+    ) -> Result<impl Reply, Rejection> {
+        let mut characters = auth
+            .with_scope(ESI_READ_CORPORATION_MINING)
+            .await?;
+        // one character per corporation is enough
+        characters.sort_by_key(|x| x.corporation_id);
+        characters.dedup_by_key(|x| x.corporation_id);
+
+        for c in characters {
+            let client = auth.eve_auth_client(&c.character_id).await?;
+            service
+                .sync(&c, &client)
+                .await
+                .map_err(warp::reject::custom)?;
+        }
+
+        Ok(warp::reply::json(&()))
+    }
+}
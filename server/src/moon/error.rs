@@ -0,0 +1,12 @@
+use caph_connector::ConnectError;
+
+#[derive(Debug)]
+pub enum MoonError {
+    FetchExtractions(ConnectError),
+    FetchObservers(ConnectError),
+    FetchLedger(ConnectError),
+    SaveExtraction(sqlx::Error),
+    SaveLedger(sqlx::Error),
+}
+
+impl warp::reject::Reject for MoonError { }
@@ -0,0 +1,247 @@
+use caph_connector::{CharacterId, EveAuthClient, EveCorporationService, LocationId, MiningExtraction, MiningLedgerEntry};
+use sqlx::PgPool;
+use std::convert::Infallible;
+use warp::Filter;
+
+use super::error::MoonError;
+use crate::AuthCharacterInfo;
+
+#[derive(Clone, Debug)]
+pub struct MoonService {
+    pool: PgPool,
+}
+
+impl MoonService {
+    pub fn new(
+        pool: PgPool,
+    ) -> Self {
+        Self {
+            pool
+        }
+    }
+
+    /// Fetches all moon extractions and mining ledgers of the corporation of
+    /// the given character and stores them in the database.
+    ///
+    /// Mined ore is assigned to the latest extraction of the same structure
+    /// whose chunk arrived on or before the day the ore was mined. Ore that
+    /// was mined before the first known extraction is ignored.
+    ///
+    /// # Params
+    ///
+    /// * `character` > Character with the role to read the mining data
+    /// * `client`    > Authenticated client of that character
+    ///
+    /// # Errors
+    ///
+    /// - If the EVE API is not available
+    /// - If the character does not have the permission
+    /// - If the database is not available
+    ///
+    pub async fn sync(
+        &self,
+        character: &AuthCharacterInfo,
+        client:    &EveAuthClient,
+    ) -> Result<(), MoonError> {
+        let service = EveCorporationService::new(character.corporation_id);
+
+        let extractions = service
+            .mining_extractions(client)
+            .await
+            .map_err(MoonError::FetchExtractions)?;
+        for extraction in extractions {
+            self.save_extraction(character.character_id, &extraction).await?;
+        }
+
+        let observers = service
+            .mining_observers(client)
+            .await
+            .map_err(MoonError::FetchObservers)?;
+        for observer in observers {
+            let ledger = service
+                .mining_ledger(client, observer.observer_id)
+                .await
+                .map_err(MoonError::FetchLedger)?;
+            self.save_ledger(observer.observer_id, ledger).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Inserts the extraction, or updates its timers if it already exists.
+    ///
+    /// # Params
+    ///
+    /// * `cid`        > [CharacterId] of the character that imported it
+    /// * `extraction` > Extraction to save
+    ///
+    /// # Errors
+    ///
+    /// - If the database is not available
+    ///
+    async fn save_extraction(
+        &self,
+        cid:        CharacterId,
+        extraction: &MiningExtraction,
+    ) -> Result<(), MoonError> {
+        sqlx::query!("
+                INSERT INTO moon_pulls (
+                    character_id,
+                    structure_id,
+                    moon_id,
+                    extraction_start,
+                    extraction_time,
+                    natural_decay
+                )
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (structure_id, extraction_start)
+                DO UPDATE SET
+                    extraction_time = EXCLUDED.extraction_time,
+                    natural_decay   = EXCLUDED.natural_decay
+            ",
+                *cid,
+                *extraction.structure_id,
+                extraction.moon_id,
+                extraction.extraction_start_time,
+                extraction.chunk_arrival_time,
+                extraction.natural_decay_time,
+            )
+            .execute(&self.pool)
+            .await
+            .map_err(MoonError::SaveExtraction)
+            .map(drop)
+    }
+
+    /// Saves the mined quantities of the ledger, existing entries are
+    /// replaced as the ledger reports the total of a whole day.
+    ///
+    /// # Params
+    ///
+    /// * `sid`    > [LocationId] of the observing structure
+    /// * `ledger` > Ledger of the observer
+    ///
+    /// # Errors
+    ///
+    /// - If the database is not available
+    ///
+    async fn save_ledger(
+        &self,
+        sid:    LocationId,
+        ledger: Vec<MiningLedgerEntry>,
+    ) -> Result<(), MoonError> {
+        let mut transaction = self.pool
+            .begin()
+            .await
+            .map_err(MoonError::SaveLedger)?;
+
+        for entry in ledger {
+            sqlx::query!("
+                    INSERT INTO moon_materials (moon, character_id, type_id, amount, mined_on)
+                    SELECT id, $2, $3, $4, $5
+                    FROM moon_pulls
+                    WHERE structure_id = $1
+                      AND (extraction_time AT TIME ZONE 'UTC')::DATE <= $5
+                    ORDER BY extraction_time DESC
+                    LIMIT 1
+                    ON CONFLICT (moon, character_id, type_id, mined_on)
+                    DO UPDATE SET amount = EXCLUDED.amount
+                ",
+                    *sid,
+                    *entry.character_id,
+                    *entry.type_id,
+                    entry.quantity,
+                    entry.last_updated,
+                )
+                .execute(&mut transaction)
+                .await
+                .map_err(MoonError::SaveLedger)?;
+        }
+
+        transaction
+            .commit()
+            .await
+            .map_err(MoonError::SaveLedger)
+    }
+}
+
+/// Filter for the API.
+///
+/// # Params
+///
+/// * `pool` > Open connection to postgres
+///
+/// # Returns
+///
+/// Initialized instance of [MoonService]
+///
+pub fn with_moon_service(
+    pool: PgPool,
+)  -> impl Filter<Extract = (MoonService,), Error = Infallible> + Clone {
+    warp::any()
+        .map(move || MoonService::new(pool.clone()))
+}
+
+#[cfg(test)]
+mod moon_tests {
+    use caph_connector::testing::*;
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn extractions_and_ledger_are_saved() {
+        dotenvy::dotenv().ok();
+        let pg_addr = std::env::var("DATABASE_URL").unwrap();
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&pg_addr)
+            .await
+            .unwrap();
+
+        let esi = MockEsi::start().await;
+        let character = AuthCharacterInfo {
+            alliance_id:    Some(ALLIANCE_ID),
+            character_id:   CHARACTER_ID,
+            corporation_id: CORPORATION_ID,
+        };
+
+        let service = MoonService::new(pool.clone());
+        // syncing twice must not duplicate anything
+        for _ in 0..2 {
+            service
+                .sync(&character, &esi.auth_client().unwrap())
+                .await
+                .unwrap();
+        }
+
+        let materials = sqlx::query!(r#"
+                SELECT
+                    mm.character_id AS "character_id!",
+                    mm.amount
+                FROM moon_materials mm
+                JOIN moon_pulls mp ON mp.id = mm.moon
+                WHERE mp.structure_id = $1
+                ORDER BY mm.mined_on
+            "#,
+                *STRUCTURE_ID,
+            )
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+
+        sqlx::query!("
+                DELETE FROM moon_pulls
+                WHERE structure_id = $1
+            ",
+                *STRUCTURE_ID,
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].character_id, *CHARACTER_ID);
+        assert_eq!(materials[0].amount, 120_000);
+        assert_eq!(materials[1].amount, 80_000);
+    }
+}
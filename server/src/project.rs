@@ -1,5 +1,6 @@
 mod api;
 //mod blueprints;
+mod contract;
 //mod dependency;
 mod service;
 //mod storage;
@@ -8,6 +9,7 @@ mod dependency_v2;
 
 pub use self::api::*;
 //pub use self::blueprints::*;
+pub use self::contract::*;
 //pub use self::dependency::*;
 pub use self::service::*;
 //pub use self::storage::*;
@@ -1,4 +1,3 @@
-use appraisal::AppraisalInformation;
 use axum::{Json, Router};
 use axum::extract::{Extension, Path};
 use axum::response::IntoResponse;
@@ -13,7 +12,7 @@ use warp::filters::BoxedFilter;
 use crate::error::Error;
 
 use super::dependency_v2::dependency::Dependency;
-use crate::{AuthCharacter, ProjectId, ProjectConfig, with_authorization, ProjectServiceV2, with_project_service};
+use crate::{AuthCharacter, ProjectId, ProjectConfig, with_authorization, ProjectServiceV2, with_project_service, ESI_READ_CONTRACTS, ESI_READ_CORPORATION_CONTRACTS};
 
 #[deprecated]
 pub struct ProjectApi;
@@ -223,7 +222,7 @@ impl ProjectApi {
         user:      AuthUser,
         service:   Extension<ProjectService>,
         Path(pid): Path<ProjectId>
-    ) -> Result<Json<AppraisalInformation>, Error> {
+    ) -> Result<Json<StoredAppraisal>, Error> {
         user.assert_project_access(pid).await?;
 
         service
@@ -418,10 +417,18 @@ impl ProjectApiV2 {
             .and_then(Self::jobs)
             .boxed();
 
+        let contracts = path
+            .clone()
+            .and(warp::path!(ProjectId / "contracts"))
+            .and(warp::get())
+            .and_then(Self::contracts)
+            .boxed();
+
         all
             .or(by_id)
             .or(create)
             .or(jobs)
+            .or(contracts)
             .boxed()
     }
 
@@ -475,4 +482,43 @@ impl ProjectApiV2 {
             .map_err(Into::into)
             .map(|x| warp::reply::json(&x))
     }
+
+    /// Gets all contracts of the logged in character, its alts and their
+    /// corporations that deliver products of the project.
+    ///
+    /// # Errors
+    ///
+    /// - If the character has no access to the project
+    /// - If the database is not available
+    /// - If the EVE API is not available
+    ///
+    /// # Returns
+    ///
+    /// Delivery progress of every product and the matching contracts
+    ///
+    async fn contracts(
+        auth:    AuthCharacter,
+        service: ProjectServiceV2,
+        pid:     ProjectId,
+    ) -> Result<impl Reply, Rejection> {
+        auth.has_project_access(pid).await?;
+
+        let mut characters = Vec::new();
+        for c in auth.with_scope(ESI_READ_CONTRACTS).await? {
+            let client = auth.eve_auth_client(&c.character_id).await?;
+            characters.push((c, client));
+        }
+
+        let mut corporations = Vec::new();
+        for c in auth.with_scope(ESI_READ_CORPORATION_CONTRACTS).await? {
+            let client = auth.eve_auth_client(&c.character_id).await?;
+            corporations.push((c, client));
+        }
+
+        service
+            .contracts(pid, characters, corporations)
+            .await
+            .map_err(Into::into)
+            .map(|x| warp::reply::json(&x))
+    }
 }
@@ -0,0 +1,352 @@
+use caph_connector::{CharacterId, Contract, ContractId, ContractItem, ContractStatus, ContractType, TypeId};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::service::Product;
+
+/// Items of a project that were handed over with contracts
+#[derive(Debug, Default, Serialize)]
+pub struct ProjectDeliveries {
+    /// Progress of every product of the project
+    pub products:  Vec<ProductDelivery>,
+    /// All contracts that contain only products of the project
+    pub contracts: Vec<ProjectContract>,
+}
+
+impl ProjectDeliveries {
+    /// Links the given contracts to the products of a project.
+    ///
+    /// A contract belongs to the project if it is a candidate, see
+    /// [ProjectDeliveries::is_candidate], it only hands over items and every
+    /// item is a product of the project with a quantity that does not
+    /// exceed the quantity the project builds.
+    /// Finished contracts count as delivered, outstanding ones as pending.
+    /// Both are capped at the quantity the project builds.
+    ///
+    /// # Params
+    ///
+    /// * `products`   > Products of the project
+    /// * `created_at` > Time the project was created, [None] if unknown
+    /// * `contracts`  > Contracts with their items
+    ///
+    /// # Returns
+    ///
+    /// Progress of every product and the matching contracts
+    ///
+    pub fn from_contracts(
+        products:   Vec<Product>,
+        created_at: Option<DateTime<Utc>>,
+        contracts:  Vec<(Contract, Vec<ContractItem>)>,
+    ) -> Self {
+        let counts = products
+            .iter()
+            .map(|x| (x.type_id, x.count as i64))
+            .collect::<HashMap<_, _>>();
+
+        let mut delivered = HashMap::new();
+        let mut pending = HashMap::new();
+        let mut project_contracts = Vec::new();
+
+        for (contract, items) in contracts {
+            if !Self::is_candidate(&contract, created_at) {
+                continue;
+            }
+
+            let quantities = match Self::quantities(&items) {
+                Some(x) => x,
+                None    => continue,
+            };
+
+            let matches = !quantities.is_empty() && quantities
+                .iter()
+                .all(|(type_id, quantity)| {
+                    counts
+                        .get(type_id)
+                        .is_some_and(|count| quantity <= count)
+                });
+            if !matches {
+                continue;
+            }
+
+            let progress = if contract.status.is_finished() {
+                &mut delivered
+            } else {
+                &mut pending
+            };
+            for (type_id, quantity) in quantities.iter() {
+                *progress.entry(*type_id).or_insert(0i64) += quantity;
+            }
+
+            let mut items = quantities
+                .into_iter()
+                .map(|(type_id, quantity)| ProjectContractItem { type_id, quantity })
+                .collect::<Vec<_>>();
+            items.sort_by_key(|x| x.type_id);
+
+            project_contracts.push(ProjectContract {
+                contract_id:    contract.contract_id,
+                issuer_id:      contract.issuer_id,
+                status:         contract.status,
+                date_issued:    contract.date_issued,
+                date_completed: contract.date_completed,
+                items,
+            });
+        }
+
+        let products = products
+            .into_iter()
+            .map(|x| {
+                let count = x.count as i64;
+                let delivered = delivered
+                    .get(&x.type_id)
+                    .copied()
+                    .unwrap_or_default()
+                    .min(count);
+                let pending = pending
+                    .get(&x.type_id)
+                    .copied()
+                    .unwrap_or_default()
+                    .min(count - delivered);
+
+                ProductDelivery {
+                    type_id: x.type_id,
+                    name:    x.name,
+                    count,
+                    delivered,
+                    pending,
+                }
+            })
+            .collect::<Vec<_>>();
+        project_contracts.sort_by_key(|x| x.date_issued);
+
+        Self {
+            products,
+            contracts: project_contracts,
+        }
+    }
+
+    /// Checks if a contract can be a delivery without looking at its items.
+    ///
+    /// Only item exchanges that are outstanding, in progress or finished and
+    /// that were issued after the project was created are candidates.
+    ///
+    /// # Params
+    ///
+    /// * `contract`   > Contract to check
+    /// * `created_at` > Time the project was created, [None] if unknown
+    ///
+    /// # Returns
+    ///
+    /// `true` if the items of the contract need to be checked
+    ///
+    pub fn is_candidate(
+        contract:   &Contract,
+        created_at: Option<DateTime<Utc>>,
+    ) -> bool {
+        if contract.typ != ContractType::ItemExchange {
+            return false;
+        }
+
+        if created_at.is_some_and(|x| contract.date_issued < x) {
+            return false;
+        }
+
+        match contract.status {
+            ContractStatus::Outstanding |
+            ContractStatus::InProgress => true,
+            x                          => x.is_finished(),
+        }
+    }
+
+    /// Sums up the quantities of a contract that can be a delivery.
+    ///
+    /// # Returns
+    ///
+    /// - [None] if the contract can not be a delivery
+    /// - Quantity of every [TypeId] in the contract
+    ///
+    fn quantities(
+        items: &[ContractItem],
+    ) -> Option<HashMap<TypeId, i64>> {
+        let mut quantities = HashMap::new();
+        for item in items {
+            // the issuer asks for something in return
+            if !item.is_included {
+                return None;
+            }
+            *quantities.entry(item.type_id).or_insert(0i64) += item.quantity as i64;
+        }
+        Some(quantities)
+    }
+}
+
+/// Delivery progress of a single product
+#[derive(Debug, Serialize)]
+pub struct ProductDelivery {
+    /// [TypeId] of the product
+    pub type_id:   TypeId,
+    /// Name of the product
+    pub name:      String,
+    /// Number of items the project builds
+    pub count:     i64,
+    /// Number of items in finished contracts
+    pub delivered: i64,
+    /// Number of items in contracts that are not accepted yet
+    pub pending:   i64,
+}
+
+/// Contract that delivers products of a project
+#[derive(Debug, Serialize)]
+pub struct ProjectContract {
+    /// [ContractId] of the contract
+    pub contract_id:    ContractId,
+    /// Character that issued the contract
+    pub issuer_id:      CharacterId,
+    /// Current status of the contract
+    pub status:         ContractStatus,
+    /// Time the contract was created
+    pub date_issued:    DateTime<Utc>,
+    /// Time the contract was completed
+    pub date_completed: Option<DateTime<Utc>>,
+    /// Products in the contract
+    pub items:          Vec<ProjectContractItem>,
+}
+
+/// Product in a contract
+#[derive(Debug, Serialize)]
+pub struct ProjectContractItem {
+    /// [TypeId] of the product
+    pub type_id:  TypeId,
+    /// Number of items
+    pub quantity: i64,
+}
+
+#[cfg(test)]
+mod contract_tests {
+    use caph_connector::ContractAvailability;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn product(type_id: i32, count: u32) -> Product {
+        Product {
+            name:    format!("Product {}", type_id),
+            count,
+            meff:    Some(10),
+            type_id: type_id.into(),
+        }
+    }
+
+    fn contract(
+        contract_id: i32,
+        typ:         ContractType,
+        status:      ContractStatus,
+        items:       &[(i32, i32, bool)],
+    ) -> (Contract, Vec<ContractItem>) {
+        let contract = Contract {
+            contract_id:           contract_id.into(),
+            typ,
+            status,
+            availability:          ContractAvailability::Corporation,
+            issuer_id:             2_117_000_001.into(),
+            issuer_corporation_id: 98_000_001.into(),
+            assignee_id:           98_000_001,
+            acceptor_id:           0,
+            for_corporation:       false,
+            date_issued:           Utc::now(),
+            date_expired:          Utc::now(),
+            date_accepted:         None,
+            date_completed:        None,
+            title:                 None,
+            price:                 None,
+            buyout:                None,
+            reward:                None,
+            collateral:            None,
+            volume:                None,
+            days_to_complete:      None,
+            start_location_id:     None,
+            end_location_id:       None,
+        };
+        let items = items
+            .iter()
+            .enumerate()
+            .map(|(i, (type_id, quantity, is_included))| ContractItem {
+                record_id:    i as i64,
+                type_id:      (*type_id).into(),
+                quantity:     *quantity,
+                is_included:  *is_included,
+                is_singleton: false,
+                raw_quantity: None,
+            })
+            .collect::<Vec<_>>();
+        (contract, items)
+    }
+
+    #[test]
+    fn contracts_are_linked_by_items_and_quantities() {
+        let products = vec![product(11_186, 10), product(11_190, 5)];
+        let contracts = vec![
+            contract(1, ContractType::ItemExchange, ContractStatus::Finished, &[(11_186, 4, true), (11_190, 5, true)]),
+            contract(2, ContractType::ItemExchange, ContractStatus::Outstanding, &[(11_186, 3, true)]),
+            // too many items
+            contract(3, ContractType::ItemExchange, ContractStatus::Finished, &[(11_186, 11, true)]),
+            // item that is not part of the project
+            contract(4, ContractType::ItemExchange, ContractStatus::Finished, &[(11_186, 1, true), (34, 1, true)]),
+            // asks for items in return
+            contract(5, ContractType::ItemExchange, ContractStatus::Finished, &[(11_186, 1, true), (11_190, 1, false)]),
+            contract(6, ContractType::Courier, ContractStatus::Finished, &[(11_186, 1, true)]),
+            contract(7, ContractType::ItemExchange, ContractStatus::Deleted, &[(11_186, 1, true)]),
+        ];
+
+        let deliveries = ProjectDeliveries::from_contracts(products, None, contracts);
+
+        let contract_ids = deliveries
+            .contracts
+            .iter()
+            .map(|x| *x.contract_id)
+            .collect::<Vec<_>>();
+        assert_eq!(contract_ids, vec![1, 2]);
+
+        assert_eq!(deliveries.products[0].delivered, 4);
+        assert_eq!(deliveries.products[0].pending, 3);
+        assert_eq!(deliveries.products[1].delivered, 5);
+        assert_eq!(deliveries.products[1].pending, 0);
+    }
+
+    #[test]
+    fn contracts_before_the_project_are_ignored() {
+        let products = vec![product(11_186, 10)];
+        let created_at = Utc::now();
+
+        let (mut old, old_items) = contract(1, ContractType::ItemExchange, ContractStatus::Finished, &[(11_186, 4, true)]);
+        old.date_issued = created_at - chrono::Duration::days(1);
+        let (mut new, new_items) = contract(2, ContractType::ItemExchange, ContractStatus::Finished, &[(11_186, 3, true)]);
+        new.date_issued = created_at + chrono::Duration::minutes(1);
+
+        let deliveries = ProjectDeliveries::from_contracts(
+            products,
+            Some(created_at),
+            vec![(old, old_items), (new, new_items)],
+        );
+
+        assert_eq!(deliveries.contracts.len(), 1);
+        assert_eq!(*deliveries.contracts[0].contract_id, 2);
+        assert_eq!(deliveries.products[0].delivered, 3);
+    }
+
+    #[test]
+    fn progress_is_capped_at_count() {
+        let products = vec![product(11_186, 10)];
+        let contracts = vec![
+            contract(1, ContractType::ItemExchange, ContractStatus::Finished, &[(11_186, 6, true)]),
+            contract(2, ContractType::ItemExchange, ContractStatus::Finished, &[(11_186, 6, true)]),
+            contract(3, ContractType::ItemExchange, ContractStatus::Outstanding, &[(11_186, 5, true)]),
+        ];
+
+        let deliveries = ProjectDeliveries::from_contracts(products, None, contracts);
+
+        assert_eq!(deliveries.products[0].delivered, 10);
+        assert_eq!(deliveries.products[0].pending, 0);
+    }
+}
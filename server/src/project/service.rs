@@ -1,15 +1,18 @@
-use appraisal::{Appraisal, AppraisalInformation, Janice};
-use caph_connector::{CharacterId, GroupId, TypeId};
+use appraisal::AppraisalOptions;
+use caph_connector::{CharacterId, CorporationId, EveAuthClient, EveCharacterService, EveCorporationService, GroupId, TypeId};
+use futures::stream::{self, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::Infallible;
 use tracing::instrument;
 use uuid::Uuid;
 use warp::Filter;
 
 use crate::{Error, structure::structure::{StructureType, StructureRig}, project::dependency_v2::{dependency::{Dependency, DependencyTree, StructureMapping}}};
+use super::contract::ProjectDeliveries;
 use super::dependency_v2::dependency::DependencyTreeEntry;
+use crate::{AuthCharacterInfo, MarketService, StoredAppraisal};
 use crate::structure::structure::{Structure, Security};
 
 /// An id of a tracking entry
@@ -19,6 +22,9 @@ pub type ContainerId = Uuid;
 /// A project id is just a UUID, this type is just for clarification
 pub type ProjectId   = Uuid;
 
+/// Maximum number of contracts whose items are fetched at once
+const CONTRACT_ITEMS_CONCURRENCY: usize = 10;
+
 #[derive(Debug, Serialize)]
 pub struct GodProject {
     info:             Project,
@@ -299,45 +305,6 @@ impl ProjectService {
         Ok(Vec::new())
     }
 
-    /// Fetches the current market prices for all required raw materials.
-    ///
-    /// # Params
-    ///
-    /// * `pid` -> If of the project to calculate the cost
-    /// * `sid` -> [Systemid] of the system to fetch the prices from
-    ///
-    /// # Errors
-    ///
-    /// If the database access fails
-    ///
-    /// # Returns
-    ///
-    /// List of all raw materials that are required for the project and their price.
-    ///
-    #[instrument(err)]
-    pub async fn market_price(
-        &self,
-        pid: ProjectId,
-    ) -> Result<AppraisalInformation, Error> {
-        // TODO: check if janice is available, if not fallback to evepraisal (or do both)
-        let janice = Janice::init().map_err(Error::AppraisalError)?;
-
-        /*let raw_materials = self
-            .raw_materials(pid)
-            .await?
-            .into_iter()
-            .map(|x| format!("{} {}", x.name, x.product()))
-            .collect::<Vec<_>>();*/
-        let raw_materials = Vec::new();
-
-        janice.create(
-            true,
-            raw_materials
-        )
-        .await
-        .map_err(Error::AppraisalError)
-    }
-
     /// Gets all required buildsteps that are required to build the project.
     ///
     /// # Params
@@ -796,8 +763,7 @@ impl ProjectService {
             .map(|x| Product {
                 name:    x.name,
                 count:   x.count as u32,
-                // FIXME:
-                meff:    0u32,
+                meff:    x.meff.map(|x| x as u32),
                 type_id: x.type_id.into(),
             })
             .collect::<Vec<_>>();
@@ -954,12 +920,21 @@ pub struct Product {
     pub name:    String,
     /// Number of items that should be build
     pub count:   u32,
-    /// Material efficiency
-    pub meff:    u32,
+    /// Material efficiency, [None] if it is not known
+    pub meff:    Option<u32>,
     /// TypeId of the product
     pub type_id: TypeId,
 }
 
+/// Owner of a contract, required for fetching its items
+#[derive(Clone, Copy, Debug)]
+enum ContractOwner {
+    /// Contract of a character
+    Character(CharacterId),
+    /// Contract of a corporation
+    Corporation(CorporationId),
+}
+
 /// Determines what status a project currently has
 #[derive(Debug, sqlx::Type, Deserialize, Serialize)]
 #[sqlx(type_name = "PROJECT_STATUS")]
@@ -1081,8 +1056,9 @@ pub struct ProjectMarketItemPrice {
 pub struct ProductConfig {
     /// Number of items that should be build
     pub count:   u32,
-    /// Material efficiency
-    pub meff:    u32,
+    /// Material efficiency, [None] if it is not known
+    #[serde(default)]
+    pub meff:    Option<u32>,
     /// TypeId of the product
     pub type_id: TypeId,
 }
@@ -1091,7 +1067,7 @@ impl From<Product> for ProductConfig {
     fn from(x: Product) -> Self {
         Self {
             count:   x.count as u32,
-            meff:    x.meff as u32,
+            meff:    x.meff,
             type_id: x.type_id
         }
     }
@@ -1199,7 +1175,11 @@ pub struct ProjectConfig {
     /// List of all products that should be build
     pub products:   Vec<ProductConfig>,
     /// Status of the project
-    pub status:     Option<Status>
+    pub status:     Option<Status>,
+    /// Text copied from the EVE client, for example a multibuy list or an
+    /// EFT fitting, every item in it is added as product
+    #[serde(default)]
+    pub clipboard:  Option<String>,
 }
 
 pub struct ProjectServiceV2 {
@@ -1319,7 +1299,9 @@ impl ProjectServiceV2 {
     ///
     /// # Errors
     ///
-    /// When communicating with the database fails
+    /// - When communicating with the database fails
+    /// - When the clipboard contains lines that could not be parsed or
+    ///   resolved, or quantities that are too large, contains those lines
     ///
     /// # Returns
     ///
@@ -1328,9 +1310,33 @@ impl ProjectServiceV2 {
     #[instrument(err)]
     pub async fn create(
         &self,
-        cid: CharacterId,
-        cfg: ProjectConfig,
+        cid:     CharacterId,
+        mut cfg: ProjectConfig,
     ) -> Result<ProjectId, Error> {
+        if let Some(clipboard) = cfg.clipboard.take() {
+            let resolved = appraisal::parser::resolve(&self.pool, &clipboard)
+                .await
+                .map_err(Error::AppraisalError)?;
+
+            let mut unparsed = resolved.unparsed;
+            let mut products = Vec::new();
+            for entry in resolved.entries {
+                match u32::try_from(entry.quantity) {
+                    Ok(count) => products.push(ProductConfig {
+                        count,
+                        meff:    None,
+                        type_id: entry.type_id,
+                    }),
+                    Err(_) => unparsed.push(entry.to_string()),
+                }
+            }
+
+            if !unparsed.is_empty() {
+                return Err(Error::UnparsedLines(unparsed));
+            }
+            cfg.products.extend(products);
+        }
+
         let pid = sqlx::query!("
                 INSERT INTO projects
                 (
@@ -1442,6 +1448,103 @@ impl ProjectServiceV2 {
         Ok(entries)
     }
 
+    /// Links the item exchange contracts of the given characters and their
+    /// corporations to the products of the project.
+    ///
+    /// Only contracts issued after the project was created are considered,
+    /// their items are fetched concurrently.
+    ///
+    /// # Params
+    ///
+    /// * `pid`          -> [ProjectId] of the project
+    /// * `characters`   -> Characters and their clients, their contracts are read
+    /// * `corporations` -> Characters and their clients, their corporation contracts are read
+    ///
+    /// # Errors
+    ///
+    /// - If the database is not available
+    /// - If the EVE API is not available
+    /// - If a character does not have the permission
+    ///
+    /// # Returns
+    ///
+    /// Delivery progress of every product and the matching contracts
+    ///
+    pub async fn contracts(
+        &self,
+        pid:          ProjectId,
+        characters:   Vec<(AuthCharacterInfo, EveAuthClient)>,
+        corporations: Vec<(AuthCharacterInfo, EveAuthClient)>,
+    ) -> Result<ProjectDeliveries, Error> {
+        let products = self.fetch_products(&pid).await?;
+        let created_at = sqlx::query!("
+                SELECT created_at
+                FROM projects
+                WHERE project = $1
+            ",
+                pid
+            )
+            .fetch_optional(&self.pool)
+            .await?
+            .and_then(|x| x.created_at);
+
+        let mut candidates = Vec::new();
+        // contracts between alts are returned for both characters
+        let mut seen = HashSet::new();
+
+        for (c, client) in characters.iter() {
+            let contracts = EveCharacterService::new(c.character_id)
+                .contracts(client)
+                .await?;
+            for contract in contracts {
+                if ProjectDeliveries::is_candidate(&contract, created_at) &&
+                   seen.insert(contract.contract_id) {
+                    candidates.push((contract, client.clone(), ContractOwner::Character(c.character_id)));
+                }
+            }
+        }
+
+        let mut corporation_ids = HashSet::new();
+        for (c, client) in corporations.iter() {
+            // alts in the same corporation see the same contracts
+            if !corporation_ids.insert(c.corporation_id) {
+                continue;
+            }
+
+            let contracts = EveCorporationService::new(c.corporation_id)
+                .contracts(client)
+                .await?;
+            for contract in contracts {
+                if ProjectDeliveries::is_candidate(&contract, created_at) &&
+                   seen.insert(contract.contract_id) {
+                    candidates.push((contract, client.clone(), ContractOwner::Corporation(c.corporation_id)));
+                }
+            }
+        }
+
+        let contracts = stream::iter(candidates)
+            .map(|(contract, client, owner)| async move {
+                let items = match owner {
+                    ContractOwner::Character(x) => {
+                        EveCharacterService::new(x)
+                            .contract_items(&client, contract.contract_id)
+                            .await?
+                    }
+                    ContractOwner::Corporation(x) => {
+                        EveCorporationService::new(x)
+                            .contract_items(&client, contract.contract_id)
+                            .await?
+                    }
+                };
+                Ok::<_, Error>((contract, items))
+            })
+            .buffer_unordered(CONTRACT_ITEMS_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        Ok(ProjectDeliveries::from_contracts(products, created_at, contracts))
+    }
+
     /// Fetches the products that should be produced in a project.
     /// 
     /// # Errors
@@ -1473,7 +1576,7 @@ impl ProjectServiceV2 {
             .map(|x| Product {
                 name:    x.name,
                 count:   x.count as u32,
-                meff:    x.meff as u32,
+                meff:    x.meff.map(|x| x as u32),
                 type_id: x.type_id.into(),
             })
             .collect::<Vec<_>>();
@@ -1522,7 +1625,7 @@ impl ProjectServiceV2 {
             .collect::<Vec<_>>();
         let meff = products
             .iter()
-            .map(|x| x.meff as i32)
+            .map(|x| x.meff.map(|x| x as i32))
             .collect::<Vec<_>>();
         sqlx::query!("
                 INSERT INTO project_products
@@ -1543,13 +1646,54 @@ impl ProjectServiceV2 {
                 pid,
                 &type_ids,
                 &counts,
-                &meff
+                &meff as &[Option<i32>]
             )
             .execute(&self.pool)
             .await
             .map(drop)
             .map_err(Error::DatabaseError)
     }
+
+    /// Fetches the current market prices for all required raw materials.
+    ///
+    /// # Params
+    ///
+    /// * `market` -> Service that creates and stores the appraisal
+    /// * `pid`    -> Id of the project to calculate the cost
+    ///
+    /// # Errors
+    ///
+    /// - If the database access fails
+    /// - If no appraisal provider answered
+    ///
+    /// # Returns
+    ///
+    /// Stored appraisal of all raw materials that are required for the
+    /// project.
+    ///
+    #[instrument(err)]
+    pub async fn market_price(
+        &self,
+        market: &MarketService,
+        pid:    ProjectId,
+    ) -> Result<StoredAppraisal, Error> {
+        /*let raw_materials = self
+            .raw_materials(pid)
+            .await?
+            .into_iter()
+            .map(|x| format!("{} {}", x.name, x.product()))
+            .collect::<Vec<_>>();*/
+        let raw_materials = Vec::new();
+
+        let options = AppraisalOptions {
+            persist: true,
+            ..AppraisalOptions::default()
+        };
+        market
+            .appraise(&options, raw_materials)
+            .await
+            .map_err(Error::from)
+    }
 }
 
 impl std::fmt::Debug for ProjectServiceV2 {